@@ -13,6 +13,7 @@ use ts_rs::TS;
 use uuid::Uuid;
 
 use super::{
+    coding_agent_turn::CodingAgentTurn,
     execution_process_repo_state::{CreateExecutionProcessRepoState, ExecutionProcessRepoState},
     project::Project,
     repo::Repo,
@@ -120,6 +121,18 @@ pub enum ExecutorActionField {
     Other(Value),
 }
 
+/// Where a new coding agent turn picks up a session, see
+/// [`ExecutionProcess::session_continuation`]
+#[derive(Debug, Clone, Default)]
+pub struct SessionContinuation {
+    /// The agent's own session id to resume, once a turn has reported one
+    pub agent_session_id: Option<String>,
+    /// Follow-ups already run, i.e. coding agent turns after the initial one
+    pub follow_up_turns: u32,
+    /// Prompt of the latest coding agent turn
+    pub previous_prompt: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct MissingBeforeContext {
     pub id: Uuid,
@@ -385,6 +398,32 @@ impl ExecutionProcess {
         Ok(row.and_then(|r| r.agent_session_id))
     }
 
+    /// What the next coding agent turn in a session continues from, not counting dropped turns
+    pub async fn session_continuation(
+        pool: &SqlitePool,
+        session_id: Uuid,
+    ) -> Result<SessionContinuation, sqlx::Error> {
+        let agent_session_id =
+            Self::find_latest_coding_agent_turn_session_id(pool, session_id).await?;
+        let processes = Self::find_by_session_id(pool, session_id, false).await?;
+        let turns: Vec<_> = processes
+            .iter()
+            .filter(|process| process.run_reason == ExecutionProcessRunReason::CodingAgent)
+            .collect();
+        let previous_prompt = match turns.last() {
+            Some(latest) => CodingAgentTurn::find_by_execution_process_id(pool, latest.id)
+                .await?
+                .and_then(|turn| turn.prompt),
+            None => None,
+        };
+        Ok(SessionContinuation {
+            agent_session_id,
+            // The first turn is the initial request
+            follow_up_turns: turns.len().saturating_sub(1) as u32,
+            previous_prompt,
+        })
+    }
+
     /// Find latest execution process by session and run reason
     pub async fn find_latest_by_session_and_run_reason(
         pool: &SqlitePool,
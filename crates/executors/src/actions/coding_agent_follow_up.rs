@@ -1,17 +1,27 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
-#[cfg(not(feature = "qa-mode"))]
-use crate::profile::ExecutorConfigs;
 use crate::{
-    actions::Executable,
+    actions::{Executable, coding_agent_initial::contained_dir},
     approvals::ExecutorApprovalService,
     env::ExecutionEnv,
-    executors::{BaseCodingAgent, ExecutorError, SpawnedChild, StandardCodingAgentExecutor},
-    profile::ExecutorProfileId,
+    executors::{
+        BaseCodingAgent, CodingAgent, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+    },
+    profile::{ExecutorConfigs, ExecutorProfileId},
+};
+#[cfg(not(feature = "qa-mode"))]
+use crate::{
+    disk_space::available_space,
+    early_exit::{EARLY_EXIT_WINDOW, check_early_exit},
+    secret_scan,
+    wallclock::enforce_wallclock,
 };
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
@@ -26,6 +36,14 @@ pub struct CodingAgentFollowUpRequest {
     /// If None, uses the container_ref directory directly.
     #[serde(default)]
     pub working_dir: Option<String>,
+    /// Follow-ups already run in this session, not counting the initial request, counted
+    /// against the profile's `max_session_turns`
+    #[serde(default)]
+    pub session_turns: u32,
+    /// Prompt of the session's previous turn, compared against when the profile sets
+    /// `dedup_identical_prompts`
+    #[serde(default)]
+    pub previous_prompt: Option<String>,
 }
 
 impl CodingAgentFollowUpRequest {
@@ -34,16 +52,63 @@ impl CodingAgentFollowUpRequest {
         self.executor_profile_id.clone()
     }
 
-    pub fn effective_dir(&self, current_dir: &Path) -> std::path::PathBuf {
+    /// `working_dir` joined onto `current_dir`, contained the same way as
+    /// [`CodingAgentInitialRequest::effective_dir`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::effective_dir).
+    pub fn effective_dir(&self, current_dir: &Path) -> Result<PathBuf, ExecutorError> {
         match &self.working_dir {
-            Some(rel_path) => current_dir.join(rel_path),
-            None => current_dir.to_path_buf(),
+            Some(rel_path) => contained_dir(current_dir, Path::new(rel_path)),
+            None => Ok(current_dir.to_path_buf()),
         }
     }
 
+    /// Like [`Self::effective_dir`], with `~` and env vars in `working_dir` expanded first. An
+    /// expansion must still stay inside `current_dir`.
+    pub fn resolve_effective_dir(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<PathBuf, ExecutorError> {
+        match &self.working_dir {
+            Some(rel_path) => contained_dir(current_dir, &env.expand_working_dir(rel_path)?),
+            None => Ok(current_dir.to_path_buf()),
+        }
+    }
+
+    /// Model this follow-up will run on, as configured by its profile.
+    pub fn resolved_model(&self) -> Option<String> {
+        crate::profile::ExecutorConfigs::get_cached()
+            .get_coding_agent(&self.executor_profile_id)
+            .and_then(|agent| agent.resolved_model())
+    }
+
     pub fn base_executor(&self) -> BaseCodingAgent {
         self.executor_profile_id.executor
     }
+
+    /// Refuse the follow-up once the session has had the profile's `max_session_turns`
+    /// follow-ups.
+    pub fn check_session_turns(&self, agent: &CodingAgent) -> Result<(), ExecutorError> {
+        match agent.cmd_overrides().and_then(|cmd| cmd.max_session_turns) {
+            Some(max_turns) if self.session_turns >= max_turns => {
+                Err(ExecutorError::MaxTurnsExceeded { max_turns })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse a prompt identical to the previous turn's when the profile sets
+    /// `dedup_identical_prompts`. Surrounding whitespace is ignored.
+    pub fn check_duplicate_prompt(&self, agent: &CodingAgent) -> Result<(), ExecutorError> {
+        let dedup = agent
+            .cmd_overrides()
+            .is_some_and(|cmd| cmd.dedup_identical_prompts);
+        match &self.previous_prompt {
+            Some(previous) if dedup && previous.trim() == self.prompt.trim() => {
+                Err(ExecutorError::DuplicatePrompt)
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 #[async_trait]
@@ -55,7 +120,16 @@ impl Executable for CodingAgentFollowUpRequest {
         approvals: Arc<dyn ExecutorApprovalService>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        let effective_dir = self.effective_dir(current_dir);
+        let effective_dir = self.resolve_effective_dir(current_dir, env)?;
+        let executor_profile_id = self.get_executor_profile_id();
+        let agent = ExecutorConfigs::get_cached()
+            .get_coding_agent(&executor_profile_id)
+            .ok_or(ExecutorError::UnknownExecutorType(
+                executor_profile_id.to_string(),
+            ))?;
+        // Session limits apply whichever agent runs the turn, the QA mock included
+        self.check_session_turns(&agent)?;
+        self.check_duplicate_prompt(&agent)?;
 
         #[cfg(feature = "qa-mode")]
         {
@@ -68,18 +142,163 @@ impl Executable for CodingAgentFollowUpRequest {
 
         #[cfg(not(feature = "qa-mode"))]
         {
-            let executor_profile_id = self.get_executor_profile_id();
-            let mut agent = ExecutorConfigs::get_cached()
-                .get_coding_agent(&executor_profile_id)
-                .ok_or(ExecutorError::UnknownExecutorType(
-                    executor_profile_id.to_string(),
-                ))?;
-
+            let mut agent = agent;
+            agent.check_prompt_secrets(&self.prompt, secret_scan::scanner().as_ref())?;
             agent.use_approvals(approvals.clone());
+            agent.ensure_disk_space(&effective_dir, available_space)?;
+            agent.create_isolated_home().await?;
+            let recording = agent.start_recording(env).await?;
 
-            agent
+            let mut spawned = agent
                 .spawn_follow_up(&effective_dir, &self.prompt, &self.session_id, env)
+                .await?;
+            check_early_exit(&mut spawned, EARLY_EXIT_WINDOW)
                 .await
+                .map_err(|err| agent.classify_spawn_error(err))?;
+            if let Some(limit) = agent.cmd_overrides().and_then(|cmd| cmd.max_wallclock()) {
+                enforce_wallclock(&mut spawned, limit);
+            }
+            if let Some(proxy) = recording {
+                proxy.attach(&mut spawned);
+            }
+            Ok(spawned)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn agent_with_max_session_turns(max_turns: u32) -> CodingAgent {
+        serde_json::from_value(serde_json::json!({
+            "CLAUDE_CODE": { "max_session_turns": max_turns }
+        }))
+        .unwrap()
+    }
+
+    fn follow_up(session_id: &str, session_turns: u32) -> CodingAgentFollowUpRequest {
+        CodingAgentFollowUpRequest {
+            prompt: "Keep going".to_string(),
+            session_id: session_id.to_string(),
+            executor_profile_id: ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+            working_dir: None,
+            session_turns,
+            previous_prompt: None,
         }
     }
+
+    #[test]
+    fn test_follow_up_refused_at_max_session_turns() {
+        let agent = agent_with_max_session_turns(3);
+
+        assert!(
+            follow_up("session-1", 2)
+                .check_session_turns(&agent)
+                .is_ok()
+        );
+        assert!(matches!(
+            follow_up("session-1", 3).check_session_turns(&agent),
+            Err(ExecutorError::MaxTurnsExceeded { max_turns: 3 })
+        ));
+
+        let unlimited: CodingAgent =
+            serde_json::from_value(serde_json::json!({ "CLAUDE_CODE": {} })).unwrap();
+        assert!(
+            follow_up("session-1", 50)
+                .check_session_turns(&unlimited)
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_forked_session_starts_a_new_count() {
+        let agent = agent_with_max_session_turns(3);
+        assert!(
+            follow_up("session-1", 3)
+                .check_session_turns(&agent)
+                .is_err()
+        );
+
+        // A forked session has run no turns of its own yet
+        assert!(
+            follow_up("session-2", 0)
+                .check_session_turns(&agent)
+                .is_ok()
+        );
+
+        // Requests stored before the limit existed deserialize with a zero count
+        let stored: CodingAgentFollowUpRequest = serde_json::from_value(serde_json::json!({
+            "prompt": "Keep going",
+            "session_id": "session-1",
+            "executor_profile_id": { "executor": "CLAUDE_CODE" },
+        }))
+        .unwrap();
+        assert_eq!(stored.session_turns, 0);
+    }
+
+    #[test]
+    fn test_follow_up_working_dir_must_stay_in_workspace() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("repo")).unwrap();
+        let mut env = ExecutionEnv::new();
+        env.insert("HOME", "/home/agent");
+        env.insert("REPO", "repo");
+        let with_dir = |dir: &str| CodingAgentFollowUpRequest {
+            working_dir: Some(dir.to_string()),
+            ..follow_up("session-1", 1)
+        };
+
+        assert_eq!(
+            with_dir("$REPO")
+                .resolve_effective_dir(root.path(), &env)
+                .unwrap(),
+            root.path().join("repo")
+        );
+        for dir in ["../..", "repo/../..", "/etc", "$HOME", "~/work"] {
+            assert!(
+                matches!(
+                    with_dir(dir).resolve_effective_dir(root.path(), &env),
+                    Err(ExecutorError::InvalidWorkingDir(_))
+                ),
+                "{dir} should be rejected"
+            );
+        }
+        assert!(with_dir("../..").effective_dir(root.path()).is_err());
+    }
+
+    #[test]
+    fn test_identical_follow_up_rejected_when_dedup_enabled() {
+        let dedup: CodingAgent = serde_json::from_value(serde_json::json!({
+            "CLAUDE_CODE": { "dedup_identical_prompts": true }
+        }))
+        .unwrap();
+        let mut request = follow_up("session-1", 1);
+        request.previous_prompt = Some("Keep going\n".to_string());
+
+        assert!(matches!(
+            request.check_duplicate_prompt(&dedup),
+            Err(ExecutorError::DuplicatePrompt)
+        ));
+
+        // Without the setting the same prompt is sent again
+        let default: CodingAgent =
+            serde_json::from_value(serde_json::json!({ "CLAUDE_CODE": {} })).unwrap();
+        assert!(request.check_duplicate_prompt(&default).is_ok());
+    }
+
+    #[test]
+    fn test_different_follow_up_spawns_when_dedup_enabled() {
+        let dedup: CodingAgent = serde_json::from_value(serde_json::json!({
+            "CLAUDE_CODE": { "dedup_identical_prompts": true }
+        }))
+        .unwrap();
+        let mut request = follow_up("session-1", 1);
+
+        // The first follow-up of a session has nothing to compare against
+        assert!(request.check_duplicate_prompt(&dedup).is_ok());
+
+        request.previous_prompt = Some("Fix the failing test".to_string());
+        assert!(request.check_duplicate_prompt(&dedup).is_ok());
+    }
 }
@@ -1,21 +1,57 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
 
-#[cfg(not(feature = "qa-mode"))]
-use crate::profile::ExecutorConfigs;
 use crate::{
     actions::Executable,
     approvals::ExecutorApprovalService,
+    auto_commit::AutoCommit,
+    command::ResolvedCommand,
+    context_manifest::{FileRange, render_context_manifest},
+    dirty_worktree::{OnDirty, check_worktree},
     env::ExecutionEnv,
     executors::{
-        BaseCodingAgent, CodingAgent, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+        BaseCodingAgent, CodingAgent, ExecutorError, MaxTurnsSupport, SpawnedChild,
+        StandardCodingAgentExecutor, TEMPERATURE_RANGE,
     },
-    profile::ExecutorProfileId,
+    logs::{AgentEvent, utils::ModelErrorDetector},
+    model_fallback::fallback_after_exit,
+    profile::{ExecutorConfigs, ExecutorProfileId},
+};
+#[cfg(not(feature = "qa-mode"))]
+use crate::{
+    disk_space::available_space,
+    early_exit::{EARLY_EXIT_WINDOW, check_early_exit},
+    mcp_config::{temp_mcp_config_path, write_mcp_config_without},
+    model_fallback::spawn_with_fallback,
+    secret_scan,
+    turn_limit::enforce_turn_limit,
+    wallclock::enforce_wallclock,
 };
 
+/// A problem found by [`CodingAgentInitialRequest::validate`].
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ValidationIssue {
+    #[error("Prompt is empty")]
+    EmptyPrompt,
+    #[error("Unknown executor profile: {0}")]
+    UnknownProfile(String),
+    #[error("Model override '{model}' is not supported by {profile}")]
+    UnsupportedModel { model: String, profile: String },
+    #[error("Working directory is not a directory: {0}")]
+    InvalidWorkingDir(String),
+    #[error("Missing credentials for {0}")]
+    MissingToken(String),
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
 pub struct CodingAgentInitialRequest {
     pub prompt: String,
@@ -30,6 +66,46 @@ pub struct CodingAgentInitialRequest {
     /// If None, uses the container_ref directory directly.
     #[serde(default)]
     pub working_dir: Option<String>,
+    /// Optional cap on agent turns for this attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    /// Optional worktree files (or line ranges) rendered into the prompt as labeled excerpts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_manifest: Option<Vec<FileRange>>,
+    /// Only produce a plan, using the client's plan/dry-run mode.
+    #[serde(default)]
+    pub plan_only: bool,
+    /// Optional sampling temperature, within `0.0..=2.0`, for clients that support one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    /// Optional command (program and arguments) run in the effective directory once the agent
+    /// exits, e.g. `["cargo", "build"]`. A failing command fails the attempt.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub post_command: Option<Vec<String>>,
+    /// Optional caller-supplied tags (ticket id, user id, ...) passed through unredacted to the
+    /// log stream. Spawn metrics only count them, since they leave the machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashMap<String, String>>,
+    /// Optional MCP servers, by name, left out of the agent's MCP config for this attempt only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disable_mcp_servers: Option<Vec<String>>,
+    /// Optional commit of the agent's changes in the effective directory once it exits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_commit: Option<AutoCommit>,
+    /// What to do when the effective directory has uncommitted changes before the agent starts.
+    #[serde(default)]
+    pub on_dirty_worktree: OnDirty,
+}
+
+/// Metrics event recorded once the agent for a [`CodingAgentInitialRequest`] has spawned.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SpawnEvent {
+    pub executor: BaseCodingAgent,
+    /// Agent the profile really runs; differs from `executor` for Jbai profiles
+    pub effective_executor: BaseCodingAgent,
+    pub variant: Option<String>,
+    /// Number of caller tags; their keys and values stay out of analytics
+    pub tag_count: usize,
 }
 
 impl CodingAgentInitialRequest {
@@ -37,14 +113,267 @@ impl CodingAgentInitialRequest {
         self.executor_profile_id.executor
     }
 
-    pub fn effective_dir(&self, current_dir: &Path) -> std::path::PathBuf {
+    /// Agent the profile actually runs, see [`CodingAgent::effective_base_agent`]. Falls back to
+    /// [`Self::base_executor`] when the profile is unknown.
+    pub fn effective_base_agent(&self) -> BaseCodingAgent {
+        ExecutorConfigs::get_cached()
+            .get_coding_agent(&self.executor_profile_id)
+            .map_or_else(
+                || self.base_executor(),
+                |agent| agent.effective_base_agent(),
+            )
+    }
+
+    /// Model this request will run on: the per-attempt override, else the profile's model.
+    pub fn resolved_model(&self) -> Option<String> {
+        self.model_override.clone().or_else(|| {
+            ExecutorConfigs::get_cached()
+                .get_coding_agent(&self.executor_profile_id)
+                .and_then(|agent| agent.resolved_model())
+        })
+    }
+
+    /// This request retried on the profile's `fallback_model`, with the `ModelFallback` event
+    /// recording the switch, when the run failed after starting and its log in `msg_store`
+    /// blames the model. A request already on the fallback model is not retried again.
+    pub fn fallback_after_exit(&self, msg_store: &MsgStore) -> Option<(Self, AgentEvent)> {
+        let agent = ExecutorConfigs::get_cached().get_coding_agent(&self.executor_profile_id)?;
+        let fallback = agent.fallback_model()?;
+        if self.model_override.as_deref() == Some(fallback.as_str()) {
+            return None;
+        }
+        let detector = ModelErrorDetector::new(agent.entitlement_patterns(), self.resolved_model());
+        let event = fallback_after_exit(detector, msg_store, &fallback)?;
+        let retry = Self {
+            model_override: Some(fallback),
+            ..self.clone()
+        };
+        Some((retry, event))
+    }
+
+    /// `RunTags` event emitted at the start of the log stream, if the request has tags.
+    pub fn run_tags(&self) -> Option<AgentEvent> {
+        AgentEvent::run_tags(self.tags.as_ref())
+    }
+
+    pub fn spawn_event(&self) -> SpawnEvent {
+        SpawnEvent {
+            executor: self.executor_profile_id.executor,
+            effective_executor: self.effective_base_agent(),
+            variant: self.executor_profile_id.variant.clone(),
+            tag_count: self.tags.as_ref().map_or(0, HashMap::len),
+        }
+    }
+
+    pub fn with_working_dir(mut self, dir: impl Into<String>) -> Self {
+        self.working_dir = Some(dir.into());
+        self
+    }
+
+    /// `working_dir` joined onto `current_dir`. Fails with [`ExecutorError::InvalidWorkingDir`]
+    /// when `working_dir` is absolute or resolves outside `current_dir`.
+    pub fn effective_dir(&self, current_dir: &Path) -> Result<PathBuf, ExecutorError> {
         match &self.working_dir {
-            Some(rel_path) => current_dir.join(rel_path),
-            None => current_dir.to_path_buf(),
+            Some(rel_path) => contained_dir(current_dir, Path::new(rel_path)),
+            None => Ok(current_dir.to_path_buf()),
+        }
+    }
+
+    /// Like [`Self::effective_dir`], with `~` and env vars in `working_dir` expanded first. An
+    /// expansion must still stay inside `current_dir`.
+    pub fn resolve_effective_dir(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<PathBuf, ExecutorError> {
+        match &self.working_dir {
+            Some(rel_path) => contained_dir(current_dir, &env.expand_working_dir(rel_path)?),
+            None => Ok(current_dir.to_path_buf()),
+        }
+    }
+
+    /// `env` with the profile's env and `isolated_home` applied, as the agent ran with, for the
+    /// `post_command`.
+    pub fn post_command_env(&self, env: &ExecutionEnv) -> ExecutionEnv {
+        let agent = ExecutorConfigs::get_cached().get_coding_agent(&self.executor_profile_id);
+        match agent.as_ref().and_then(CodingAgent::cmd_overrides) {
+            Some(cmd) => env.clone().with_profile(cmd),
+            None => env.clone(),
+        }
+    }
+
+    /// The prompt sent to the agent, preceded by any `context_manifest` excerpts.
+    pub async fn prompt_with_context(&self, effective_dir: &Path) -> Result<String, ExecutorError> {
+        match self.context_manifest.as_deref() {
+            Some(entries) if !entries.is_empty() => {
+                let context = render_context_manifest(effective_dir, entries).await?;
+                Ok(format!("{context}\n{}", self.prompt))
+            }
+            _ => Ok(self.prompt.clone()),
+        }
+    }
+
+    /// The profile's agent with this request's model, plan, temperature and turn settings applied,
+    /// plus the turn marker to count when the client can't enforce `max_turns` itself.
+    fn configured_agent(&self) -> Result<(CodingAgent, Option<fn(&str) -> bool>), ExecutorError> {
+        let executor_profile_id = &self.executor_profile_id;
+        let mut agent = ExecutorConfigs::get_cached()
+            .get_coding_agent(executor_profile_id)
+            .ok_or(ExecutorError::UnknownExecutorType(
+                executor_profile_id.to_string(),
+            ))?;
+
+        if let Some(model) = self.model_override.as_ref()
+            && !agent.set_model_override(model.clone())
+        {
+            tracing::warn!(
+                "model_override {model:?} ignored: {executor_profile_id} has no model setting"
+            );
+        }
+
+        if self.plan_only && !agent.apply_plan_only() {
+            return Err(ExecutorError::Unsupported(format!(
+                "plan_only is not supported by {executor_profile_id}"
+            )));
+        }
+
+        if let Some(temperature) = self.temperature {
+            if !TEMPERATURE_RANGE.contains(&temperature) {
+                return Err(ExecutorError::InvalidArg(format!(
+                    "temperature {temperature} is outside {}..={}",
+                    TEMPERATURE_RANGE.start(),
+                    TEMPERATURE_RANGE.end()
+                )));
+            }
+            if !agent.apply_temperature(temperature) {
+                return Err(ExecutorError::Unsupported(format!(
+                    "temperature is not supported by {executor_profile_id}"
+                )));
+            }
+        }
+
+        let turn_marker = match self.max_turns {
+            Some(max_turns) => match agent.apply_max_turns(max_turns) {
+                MaxTurnsSupport::Native => None,
+                MaxTurnsSupport::Counted(is_turn_marker) => Some(is_turn_marker),
+                MaxTurnsSupport::Unsupported => {
+                    return Err(ExecutorError::Unsupported(format!(
+                        "max_turns is not supported by {executor_profile_id}"
+                    )));
+                }
+            },
+            None => None,
+        };
+        Ok((agent, turn_marker))
+    }
+
+    /// Dry run of [`Executable::spawn`] with `env`: the program, arguments, env additions and
+    /// working directory the agent would be started with. Nothing is spawned or written,
+    /// including token files and `disable_mcp_servers` configs.
+    pub async fn resolve_command(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<ResolvedCommand, ExecutorError> {
+        let effective_dir = self.resolve_effective_dir(current_dir, env)?;
+        let (agent, _) = self.configured_agent()?;
+        agent.resolve_command(&effective_dir, env).await
+    }
+
+    /// Check everything `spawn` depends on without starting the agent, reporting every issue.
+    pub async fn validate(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<(), Vec<ValidationIssue>> {
+        let mut issues = Vec::new();
+
+        if self.prompt.trim().is_empty() {
+            issues.push(ValidationIssue::EmptyPrompt);
+        }
+
+        match self.resolve_effective_dir(current_dir, env) {
+            Ok(effective_dir) => {
+                let is_dir = tokio::fs::metadata(&effective_dir)
+                    .await
+                    .is_ok_and(|meta| meta.is_dir());
+                if !is_dir {
+                    issues.push(ValidationIssue::InvalidWorkingDir(
+                        effective_dir.display().to_string(),
+                    ));
+                }
+            }
+            Err(ExecutorError::InvalidWorkingDir(message)) => {
+                issues.push(ValidationIssue::InvalidWorkingDir(message));
+            }
+            Err(err) => issues.push(ValidationIssue::InvalidWorkingDir(err.to_string())),
+        }
+
+        let profile = self.executor_profile_id.to_string();
+        match ExecutorConfigs::get_cached().get_coding_agent(&self.executor_profile_id) {
+            None => issues.push(ValidationIssue::UnknownProfile(profile)),
+            Some(agent) => {
+                if let Some(model) = self.model_override.as_ref()
+                    && (model.trim().is_empty() || !agent.clone().set_model_override(model.clone()))
+                {
+                    issues.push(ValidationIssue::UnsupportedModel {
+                        model: model.clone(),
+                        profile: profile.clone(),
+                    });
+                }
+                if let CodingAgent::Jbai(jbai) = &agent
+                    && !jbai.has_token(env)
+                {
+                    issues.push(ValidationIssue::MissingToken(profile));
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
         }
     }
 }
 
+/// `working_dir` joined onto `current_dir`, refusing absolute paths, `..` traversal above
+/// `current_dir` and symlinks that lead out of it.
+pub(crate) fn contained_dir(
+    current_dir: &Path,
+    working_dir: &Path,
+) -> Result<PathBuf, ExecutorError> {
+    let escapes = || {
+        ExecutorError::InvalidWorkingDir(format!(
+            "{}: resolves outside {}",
+            working_dir.display(),
+            current_dir.display()
+        ))
+    };
+    let mut depth = 0usize;
+    for component in working_dir.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir => depth = depth.checked_sub(1).ok_or_else(escapes)?,
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(ExecutorError::InvalidWorkingDir(format!(
+                    "{}: must be relative to the workspace",
+                    working_dir.display()
+                )));
+            }
+        }
+    }
+
+    let joined = current_dir.join(working_dir);
+    if let (Ok(root), Ok(resolved)) = (current_dir.canonicalize(), joined.canonicalize())
+        && !resolved.starts_with(&root)
+    {
+        return Err(escapes());
+    }
+    Ok(joined)
+}
+
 #[async_trait]
 impl Executable for CodingAgentInitialRequest {
     #[cfg_attr(feature = "qa-mode", allow(unused_variables))]
@@ -54,34 +383,379 @@ impl Executable for CodingAgentInitialRequest {
         approvals: Arc<dyn ExecutorApprovalService>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        let effective_dir = self.effective_dir(current_dir);
+        let effective_dir = self.resolve_effective_dir(current_dir, env)?;
+        let dirty = check_worktree(self.on_dirty_worktree, &effective_dir).await?;
+        let prompt = self.prompt_with_context(&effective_dir).await?;
 
         #[cfg(feature = "qa-mode")]
         {
             tracing::info!("QA mode: using mock executor instead of real agent");
             let executor = crate::executors::qa_mock::QaMockExecutor;
-            return executor.spawn(&effective_dir, &self.prompt, env).await;
+            let mut spawned = executor.spawn(&effective_dir, &prompt, env).await?;
+            if let Some(event) = dirty {
+                spawned.events.insert(0, event);
+            }
+            return Ok(spawned);
         }
 
         #[cfg(not(feature = "qa-mode"))]
         {
-            let executor_profile_id = self.executor_profile_id.clone();
-            let mut agent = ExecutorConfigs::get_cached()
-                .get_coding_agent(&executor_profile_id)
-                .ok_or(ExecutorError::UnknownExecutorType(
-                    executor_profile_id.to_string(),
-                ))?;
-
-            if let Some(model) = self.model_override.as_ref() {
-                if let CodingAgent::Jbai(mut jbai) = agent {
-                    jbai.model = Some(model.clone());
-                    agent = CodingAgent::Jbai(jbai);
+            let executor_profile_id = &self.executor_profile_id;
+            let (mut agent, turn_marker) = self.configured_agent()?;
+            agent.check_prompt_secrets(&prompt, secret_scan::scanner().as_ref())?;
+
+            if let Some(disabled) = self
+                .disable_mcp_servers
+                .as_deref()
+                .filter(|d| !d.is_empty())
+            {
+                let path = temp_mcp_config_path();
+                if !agent.apply_mcp_config_file(&path) {
+                    return Err(ExecutorError::Unsupported(format!(
+                        "disable_mcp_servers is not supported by {executor_profile_id}"
+                    )));
                 }
+                write_mcp_config_without(&agent, disabled, &path).await?;
             }
 
             agent.use_approvals(approvals.clone());
+            agent.ensure_disk_space(&effective_dir, available_space)?;
+            agent.create_isolated_home().await?;
+            let recording = agent.start_recording(env).await?;
+
+            let fallback_model = agent.fallback_model();
+            let mut spawned = spawn_with_fallback(fallback_model.as_deref(), |model| {
+                let mut agent = agent.clone();
+                if let Some(model) = model {
+                    agent.set_model_override(model);
+                }
+                let (effective_dir, prompt) = (&effective_dir, &prompt);
+                async move {
+                    let mut spawned = agent.spawn(effective_dir, prompt, env).await?;
+                    check_early_exit(&mut spawned, EARLY_EXIT_WINDOW)
+                        .await
+                        .map_err(|err| agent.classify_spawn_error(err))?;
+                    Ok(spawned)
+                }
+            })
+            .await?;
+            if let (Some(max_turns), Some(is_turn_marker)) = (self.max_turns, turn_marker) {
+                enforce_turn_limit(&mut spawned, max_turns, is_turn_marker)?;
+            }
+            if let Some(limit) = agent.cmd_overrides().and_then(|cmd| cmd.max_wallclock()) {
+                enforce_wallclock(&mut spawned, limit);
+            }
+            if let Some(proxy) = recording {
+                proxy.attach(&mut spawned);
+            }
+            if let Some(event) = dirty {
+                spawned.events.insert(0, event);
+            }
+            Ok(spawned)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(prompt: &str, executor_profile_id: ExecutorProfileId) -> CodingAgentInitialRequest {
+        CodingAgentInitialRequest {
+            prompt: prompt.to_string(),
+            executor_profile_id,
+            model_override: None,
+            working_dir: None,
+            max_turns: None,
+            context_manifest: None,
+            plan_only: false,
+            temperature: None,
+            post_command: None,
+            tags: None,
+            disable_mcp_servers: None,
+            auto_commit: None,
+            on_dirty_worktree: OnDirty::Allow,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_validate_accepts_valid_request() {
+        let request = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        );
+        let result = request
+            .validate(&std::env::temp_dir(), &ExecutionEnv::new())
+            .await;
+        assert_eq!(result, Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_validate_reports_every_issue() {
+        let mut request = request("  \n", ExecutorProfileId::new(BaseCodingAgent::Amp));
+        request.model_override = Some("gpt-5".to_string());
+        request.working_dir = Some(format!("missing-{}", uuid::Uuid::new_v4()));
+
+        let issues = request
+            .validate(&std::env::temp_dir(), &ExecutionEnv::new())
+            .await
+            .unwrap_err();
+
+        assert_eq!(issues.len(), 3, "{issues:?}");
+        assert!(issues.contains(&ValidationIssue::EmptyPrompt));
+        assert!(
+            issues
+                .iter()
+                .any(|issue| matches!(issue, ValidationIssue::InvalidWorkingDir(_)))
+        );
+        assert!(
+            issues
+                .iter()
+                .any(|issue| matches!(issue, ValidationIssue::UnsupportedModel { .. }))
+        );
+    }
 
-            agent.spawn(&effective_dir, &self.prompt, env).await
+    #[tokio::test]
+    async fn test_validate_reports_unknown_profile() {
+        let request = request(
+            "",
+            ExecutorProfileId::with_variant(BaseCodingAgent::ClaudeCode, "NOPE".to_string()),
+        );
+        let issues = request
+            .validate(&std::env::temp_dir(), &ExecutionEnv::new())
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::EmptyPrompt,
+                ValidationIssue::UnknownProfile(request.executor_profile_id.to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tags_flow_into_log_stream_and_are_counted_in_spawn_event() {
+        let mut request = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        );
+        request.tags = Some(HashMap::from([
+            ("ticket".to_string(), "VK-42".to_string()),
+            ("user".to_string(), "u-7".to_string()),
+        ]));
+
+        let msg_store = workspace_utils::msg_store::MsgStore::new();
+        let entry = request.run_tags().unwrap().into_normalized_entry();
+        msg_store.push_patch(crate::logs::utils::ConversationPatch::add_normalized_entry(
+            0, entry,
+        ));
+        let logged = serde_json::to_string(&msg_store.get_history()).unwrap();
+        assert!(logged.contains("run_tags"), "{logged}");
+        assert!(
+            logged.contains("VK-42") && logged.contains("u-7"),
+            "{logged}"
+        );
+
+        let metrics = serde_json::to_value(request.spawn_event()).unwrap();
+        assert_eq!(metrics["effective_executor"], "CLAUDE_CODE");
+        assert_eq!(metrics["tag_count"], 2);
+        let metrics = metrics.to_string();
+        assert!(
+            !metrics.contains("VK-42") && !metrics.contains("u-7"),
+            "{metrics}"
+        );
+    }
+
+    #[test]
+    fn test_untagged_request_emits_no_run_tags() {
+        let mut request = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        );
+        assert_eq!(request.run_tags(), None);
+        request.tags = Some(HashMap::new());
+        assert_eq!(request.run_tags(), None);
+        assert_eq!(request.spawn_event().tag_count, 0);
+    }
+
+    #[test]
+    fn test_effective_dir_allows_nested_subdir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("crates/server")).unwrap();
+        let request = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        );
+
+        assert_eq!(
+            request.effective_dir(root.path()).unwrap(),
+            root.path().to_path_buf()
+        );
+        let nested = request.with_working_dir("crates/./server/../server");
+        assert_eq!(
+            nested.effective_dir(root.path()).unwrap(),
+            root.path().join("crates/./server/../server")
+        );
+    }
+
+    #[test]
+    fn test_effective_dir_rejects_absolute_path() {
+        let root = tempfile::tempdir().unwrap();
+        let request = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        )
+        .with_working_dir("/etc");
+
+        assert!(matches!(
+            request.effective_dir(root.path()),
+            Err(ExecutorError::InvalidWorkingDir(_))
+        ));
+    }
+
+    #[test]
+    fn test_effective_dir_rejects_parent_traversal() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("repo")).unwrap();
+        let with_dir = |dir: &str| {
+            request(
+                "Fix the bug",
+                ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+            )
+            .with_working_dir(dir)
+        };
+
+        for dir in ["../../etc", "repo/../..", ".."] {
+            assert!(
+                matches!(
+                    with_dir(dir).effective_dir(root.path()),
+                    Err(ExecutorError::InvalidWorkingDir(_))
+                ),
+                "{dir} should be rejected"
+            );
+        }
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink("/etc", root.path().join("escape")).unwrap();
+            assert!(with_dir("escape").effective_dir(root.path()).is_err());
         }
     }
+
+    #[tokio::test]
+    async fn test_resolve_command_for_jbai_codex() {
+        let mut request = request(
+            "Fix the bug",
+            ExecutorProfileId::with_variant(BaseCodingAgent::Jbai, "CODEX".to_string()),
+        );
+        request.model_override = Some("gpt-5.1-codex".to_string());
+        request.working_dir = Some("crates/server".to_string());
+        let workspace = std::env::temp_dir().join(format!("resolve-{}", uuid::Uuid::new_v4()));
+
+        let resolved = request
+            .resolve_command(&workspace, &ExecutionEnv::new())
+            .await
+            .unwrap();
+
+        assert_eq!(resolved.program, "jbai-codex");
+        assert_eq!(resolved.args[0], "app-server");
+        let model = resolved
+            .args
+            .windows(2)
+            .find(|pair| pair[0] == "-c")
+            .map(|pair| pair[1].as_str());
+        assert_eq!(model, Some(r#"model="gpt-5.1-codex""#));
+        assert_eq!(resolved.working_dir, workspace.join("crates/server"));
+        assert_eq!(resolved.env.get("NO_COLOR").map(String::as_str), Some("1"));
+        // A dry run neither creates the directory nor writes credentials
+        assert!(!workspace.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_command_expands_working_dir_like_spawn() {
+        let mut request = request(
+            "Fix the bug",
+            ExecutorProfileId::with_variant(BaseCodingAgent::Jbai, "CODEX".to_string()),
+        );
+        request.working_dir = Some("$REPO/server".to_string());
+        let workspace = std::env::temp_dir().join(format!("resolve-{}", uuid::Uuid::new_v4()));
+        let mut env = ExecutionEnv::new();
+        env.insert("REPO", "crates");
+
+        let resolved = request.resolve_command(&workspace, &env).await.unwrap();
+
+        assert_eq!(
+            resolved.working_dir,
+            request.resolve_effective_dir(&workspace, &env).unwrap()
+        );
+        assert_eq!(resolved.working_dir, workspace.join("crates/server"));
+    }
+
+    #[tokio::test]
+    async fn test_resolve_command_passes_temperature() {
+        let mut request = request(
+            "Fix the bug",
+            ExecutorProfileId::with_variant(BaseCodingAgent::Jbai, "CODEX".to_string()),
+        );
+        request.temperature = Some(0.4);
+
+        let resolved = request
+            .resolve_command(&std::env::temp_dir(), &ExecutionEnv::new())
+            .await
+            .unwrap();
+
+        assert!(
+            resolved
+                .args
+                .windows(2)
+                .any(|pair| pair[0] == "-c" && pair[1] == "model_temperature=0.4")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_temperature_out_of_range_or_unsupported() {
+        let mut out_of_range = request(
+            "Fix the bug",
+            ExecutorProfileId::with_variant(BaseCodingAgent::Jbai, "CODEX".to_string()),
+        );
+        for temperature in [-0.1, 2.5, f32::NAN] {
+            out_of_range.temperature = Some(temperature);
+            assert!(matches!(
+                out_of_range
+                    .resolve_command(&std::env::temp_dir(), &ExecutionEnv::new())
+                    .await,
+                Err(ExecutorError::InvalidArg(_))
+            ));
+        }
+
+        let mut unsupported = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+        );
+        unsupported.temperature = Some(0.4);
+        assert!(matches!(
+            unsupported
+                .resolve_command(&std::env::temp_dir(), &ExecutionEnv::new())
+                .await,
+            Err(ExecutorError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_effective_base_agent_of_jbai_profile() {
+        let wrapped = request(
+            "Fix the bug",
+            ExecutorProfileId::with_variant(BaseCodingAgent::Jbai, "CODEX".to_string()),
+        );
+        assert_eq!(wrapped.base_executor(), BaseCodingAgent::Jbai);
+        assert_eq!(wrapped.effective_base_agent(), BaseCodingAgent::Codex);
+
+        let native = request(
+            "Fix the bug",
+            ExecutorProfileId::new(BaseCodingAgent::Codex),
+        );
+        assert_eq!(native.base_executor(), BaseCodingAgent::Codex);
+        assert_eq!(native.effective_base_agent(), BaseCodingAgent::Codex);
+    }
 }
@@ -1,4 +1,4 @@
-use std::{path::Path, sync::Arc};
+use std::{path::Path, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use enum_dispatch::enum_dispatch;
@@ -12,8 +12,12 @@ use crate::{
         script::ScriptRequest,
     },
     approvals::ExecutorApprovalService,
+    command::CmdOverrides,
     env::ExecutionEnv,
-    executors::{BaseCodingAgent, ExecutorError, SpawnedChild},
+    executors::{BaseCodingAgent, CodingAgent, ExecutorError, SpawnedChild},
+    logs::utils::{ModelErrorDetector, QuestionDetector, TurnEndDetector},
+    profile::{ExecutorConfigs, ExecutorProfileId},
+    spawn_limit::{SPAWN_QUEUE_TIMEOUT, SpawnLimiter},
 };
 pub mod coding_agent_follow_up;
 pub mod coding_agent_initial;
@@ -69,6 +73,94 @@ impl ExecutorAction {
             ExecutorActionType::ScriptRequest(_) => None,
         }
     }
+
+    /// Model the coding agent in this action resolves to, if any.
+    pub fn resolved_model(&self) -> Option<String> {
+        match self.typ() {
+            ExecutorActionType::CodingAgentInitialRequest(request) => request.resolved_model(),
+            ExecutorActionType::CodingAgentFollowUpRequest(request) => request.resolved_model(),
+            ExecutorActionType::ReviewRequest(_) | ExecutorActionType::ScriptRequest(_) => None,
+        }
+    }
+
+    /// Detector for the client refusing this action's model later in the run, if it runs a
+    /// coding agent.
+    pub fn model_error_detector(&self) -> Option<ModelErrorDetector> {
+        let agent = self.coding_agent()?;
+        Some(ModelErrorDetector::new(
+            agent.entitlement_patterns(),
+            self.resolved_model(),
+        ))
+    }
+
+    /// Detector for the events in the coding agent's final message, if this action runs one.
+    pub fn turn_end_detector(&self) -> Option<TurnEndDetector> {
+        let agent = self.coding_agent()?;
+        Some(TurnEndDetector::new(
+            QuestionDetector::new().with_patterns(agent.question_patterns()),
+        ))
+    }
+
+    /// The coding agent's profile, if this action runs one.
+    fn coding_agent(&self) -> Option<CodingAgent> {
+        let profile_id: &ExecutorProfileId = match self.typ() {
+            ExecutorActionType::CodingAgentInitialRequest(request) => &request.executor_profile_id,
+            ExecutorActionType::CodingAgentFollowUpRequest(request) => &request.executor_profile_id,
+            ExecutorActionType::ReviewRequest(request) => &request.executor_profile_id,
+            ExecutorActionType::ScriptRequest(_) => return None,
+        };
+        ExecutorConfigs::get_cached().get_coding_agent(profile_id)
+    }
+
+    /// Command overrides of the coding agent's profile, if this action runs one.
+    fn cmd_overrides(&self) -> Option<CmdOverrides> {
+        self.coding_agent()?.cmd_overrides().cloned()
+    }
+
+    /// Form in which a session id reported by this action's agent, spawned with `env`, is stored
+    /// for follow-ups.
+    pub fn storable_session_id(&self, session_id: &str, env: &ExecutionEnv) -> String {
+        match self.coding_agent() {
+            Some(agent) => agent.storable_session_id(session_id, env),
+            None => session_id.to_string(),
+        }
+    }
+
+    /// Directory the coding agent's profile asks run logs to be spilled into, if any.
+    pub fn spill_to_disk(&self) -> Option<std::path::PathBuf> {
+        self.cmd_overrides()?.spill_to_disk
+    }
+
+    /// How often the coding agent's profile asks for progress checkpoints, if at all.
+    pub fn checkpoint_interval(&self) -> Option<std::time::Duration> {
+        self.cmd_overrides()?.checkpoint_interval()
+    }
+
+    /// Spawn this action, abandoning the spawn after `timeout` when one is given. A coding agent
+    /// first queues for a slot of `limiter`, bounded by [`SPAWN_QUEUE_TIMEOUT`] rather than
+    /// `timeout`, and hands it back on [`SpawnedChild::spawn_permit`]; a failed spawn frees it.
+    pub async fn spawn_limited(
+        &self,
+        limiter: &SpawnLimiter,
+        current_dir: &Path,
+        approvals: Arc<dyn ExecutorApprovalService>,
+        env: &ExecutionEnv,
+        timeout: Option<Duration>,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let spawn_permit = match self.base_executor() {
+            Some(_) => Some(limiter.acquire_within(SPAWN_QUEUE_TIMEOUT).await?),
+            None => None,
+        };
+        let spawn = self.typ.spawn(current_dir, approvals, env);
+        let mut spawned = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, spawn)
+                .await
+                .map_err(|_| ExecutorError::Timeout(timeout))??,
+            None => spawn.await?,
+        };
+        spawned.spawn_permit = spawn_permit;
+        Ok(spawned)
+    }
 }
 
 #[async_trait]
@@ -80,6 +172,22 @@ pub trait Executable {
         approvals: Arc<dyn ExecutorApprovalService>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError>;
+
+    /// [`Self::spawn`], abandoned with [`ExecutorError::Timeout`] if setup (credential and MCP
+    /// config writes, process spawn, early-exit check) takes longer than `timeout`. Abandoning
+    /// drops any child spawned so far, and executors spawn with `kill_on_drop`, so nothing
+    /// outlives the call.
+    async fn spawn_with_timeout(
+        &self,
+        current_dir: &Path,
+        approvals: Arc<dyn ExecutorApprovalService>,
+        env: &ExecutionEnv,
+        timeout: Duration,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        tokio::time::timeout(timeout, self.spawn(current_dir, approvals, env))
+            .await
+            .map_err(|_| ExecutorError::Timeout(timeout))?
+    }
 }
 
 #[async_trait]
@@ -90,6 +198,98 @@ impl Executable for ExecutorAction {
         approvals: Arc<dyn ExecutorApprovalService>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        self.typ.spawn(current_dir, approvals, env).await
+        self.spawn_limited(SpawnLimiter::global(), current_dir, approvals, env, None)
+            .await
+    }
+
+    async fn spawn_with_timeout(
+        &self,
+        current_dir: &Path,
+        approvals: Arc<dyn ExecutorApprovalService>,
+        env: &ExecutionEnv,
+        timeout: Duration,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_limited(
+            SpawnLimiter::global(),
+            current_dir,
+            approvals,
+            env,
+            Some(timeout),
+        )
+        .await
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use command_group::AsyncCommandGroup;
+
+    use super::*;
+    use crate::approvals::NoopExecutorApprovalService;
+
+    /// Starts a long-running child, then stalls before handing it back.
+    struct SlowSetup {
+        pid: Arc<std::sync::Mutex<Option<u32>>>,
+    }
+
+    #[async_trait]
+    impl Executable for SlowSetup {
+        async fn spawn(
+            &self,
+            _current_dir: &Path,
+            _approvals: Arc<dyn ExecutorApprovalService>,
+            _env: &ExecutionEnv,
+        ) -> Result<SpawnedChild, ExecutorError> {
+            let mut child = tokio::process::Command::new("sleep")
+                .arg("30")
+                .kill_on_drop(true)
+                .group_spawn()?;
+            *self.pid.lock().unwrap() = child.inner().id();
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            Ok(child.into())
+        }
+    }
+
+    /// Whether `pid` is still running; a zombie waiting to be reaped counts as gone.
+    fn is_running(pid: u32) -> bool {
+        std::fs::read_to_string(format!("/proc/{pid}/stat"))
+            .ok()
+            .and_then(|stat| {
+                let state = stat.rsplit_once(") ")?.1.chars().next()?;
+                Some(state != 'Z')
+            })
+            .unwrap_or(false)
+    }
+
+    #[tokio::test]
+    async fn test_spawn_timeout_kills_partial_child() {
+        let stub = SlowSetup {
+            pid: Arc::default(),
+        };
+
+        let result = stub
+            .spawn_with_timeout(
+                &std::env::temp_dir(),
+                Arc::new(NoopExecutorApprovalService),
+                &ExecutionEnv::new(),
+                Duration::from_millis(200),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ExecutorError::Timeout(timeout)) if timeout == Duration::from_millis(200)
+        ));
+        let pid = stub.pid.lock().unwrap().expect("child was spawned");
+        if std::path::Path::new("/proc").exists() {
+            let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+            while is_running(pid) && tokio::time::Instant::now() < deadline {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+            assert!(
+                !is_running(pid),
+                "partially spawned child {pid} outlived the timeout"
+            );
+        }
     }
 }
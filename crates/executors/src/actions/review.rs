@@ -1,4 +1,7 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -6,7 +9,7 @@ use ts_rs::TS;
 use uuid::Uuid;
 
 use crate::{
-    actions::Executable,
+    actions::{Executable, coding_agent_initial::contained_dir},
     approvals::ExecutorApprovalService,
     env::ExecutionEnv,
     executors::{BaseCodingAgent, ExecutorError, SpawnedChild, StandardCodingAgentExecutor},
@@ -38,10 +41,24 @@ impl ReviewRequest {
         self.executor_profile_id.executor
     }
 
-    pub fn effective_dir(&self, current_dir: &Path) -> std::path::PathBuf {
+    /// `working_dir` joined onto `current_dir`, contained the same way as
+    /// [`CodingAgentInitialRequest::effective_dir`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::effective_dir).
+    pub fn effective_dir(&self, current_dir: &Path) -> Result<PathBuf, ExecutorError> {
+        match &self.working_dir {
+            Some(rel_path) => contained_dir(current_dir, Path::new(rel_path)),
+            None => Ok(current_dir.to_path_buf()),
+        }
+    }
+
+    /// Like [`Self::effective_dir`], with `~` and env vars in `working_dir` expanded first.
+    pub fn resolve_effective_dir(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<PathBuf, ExecutorError> {
         match &self.working_dir {
-            Some(rel_path) => current_dir.join(rel_path),
-            None => current_dir.to_path_buf(),
+            Some(rel_path) => contained_dir(current_dir, &env.expand_working_dir(rel_path)?),
+            None => Ok(current_dir.to_path_buf()),
         }
     }
 }
@@ -54,11 +71,7 @@ impl Executable for ReviewRequest {
         approvals: Arc<dyn ExecutorApprovalService>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        // Use working_dir if specified, otherwise use current_dir
-        let effective_dir = match &self.working_dir {
-            Some(rel_path) => current_dir.join(rel_path),
-            None => current_dir.to_path_buf(),
-        };
+        let effective_dir = self.resolve_effective_dir(current_dir, env)?;
 
         let executor_profile_id = self.executor_profile_id.clone();
         let mut agent = ExecutorConfigs::get_cached()
@@ -68,6 +81,7 @@ impl Executable for ReviewRequest {
             ))?;
 
         agent.use_approvals(approvals.clone());
+        agent.create_isolated_home().await?;
 
         agent
             .spawn_review(
@@ -0,0 +1,212 @@
+//! Commit of the agent's changes once it exits.
+//!
+//! [`CodingAgentInitialRequest::auto_commit`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::auto_commit)
+//! stages everything in the agent's effective directory and commits it with a templated message.
+//! The commit is reported as an [`AgentEvent::GitOp`] entry.
+
+use std::{path::Path, process::Stdio};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    executors::ExecutorError,
+    logs::{
+        AgentEvent,
+        utils::{ConversationPatch, EntryIndexProvider},
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+pub struct AutoCommit {
+    /// Commit message; `{executor}`, `{exit_code}` and `{prompt}` (its first line) are filled in.
+    pub message_template: String,
+    /// Only commit when the agent (and any post-command) exited 0.
+    #[serde(default = "default_only_on_success")]
+    pub only_on_success: bool,
+}
+
+fn default_only_on_success() -> bool {
+    true
+}
+
+impl AutoCommit {
+    /// The commit message for a run of `executor` on `prompt` that exited with `exit_code`.
+    pub fn render_message(&self, executor: &str, prompt: &str, exit_code: Option<i32>) -> String {
+        let exit_code = exit_code.map_or_else(|| "none".to_string(), |code| code.to_string());
+        self.message_template
+            .replace("{executor}", executor)
+            .replace("{exit_code}", &exit_code)
+            .replace("{prompt}", prompt.lines().next().unwrap_or_default().trim())
+    }
+
+    pub fn should_commit(&self, exit_code: Option<i32>) -> bool {
+        !self.only_on_success || exit_code == Some(0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AutoCommitResult {
+    pub commit: String,
+    pub message: String,
+}
+
+impl AutoCommitResult {
+    /// Patch adding the `GitOp` entry after the existing entries in `msg_store`.
+    pub fn into_patch(self, msg_store: &MsgStore) -> json_patch::Patch {
+        let event = AgentEvent::GitOp {
+            operation: "commit".to_string(),
+            commit: self.commit,
+            message: self.message,
+        };
+        ConversationPatch::add_normalized_entry(
+            EntryIndexProvider::start_from(msg_store).next(),
+            event.into_normalized_entry(),
+        )
+    }
+}
+
+/// Stage and commit all changes in `dir` with `message` after a run that exited with
+/// `exit_code`. Returns `None` when `config` skips this run or there is nothing to commit.
+pub async fn run_auto_commit(
+    config: &AutoCommit,
+    dir: &Path,
+    message: &str,
+    exit_code: Option<i32>,
+) -> Result<Option<AutoCommitResult>, ExecutorError> {
+    if !config.should_commit(exit_code) {
+        return Ok(None);
+    }
+    if git(dir, &["status", "--porcelain"])
+        .await?
+        .trim()
+        .is_empty()
+    {
+        return Ok(None);
+    }
+    git(dir, &["add", "-A"]).await?;
+    git(dir, &["commit", "--quiet", "-m", message]).await?;
+    let commit = git(dir, &["rev-parse", "HEAD"]).await?.trim().to_string();
+    Ok(Some(AutoCommitResult {
+        commit,
+        message: message.to_string(),
+    }))
+}
+
+pub(crate) async fn git(dir: &Path, args: &[&str]) -> Result<String, ExecutorError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(ExecutorError::Io)?;
+    if !output.status.success() {
+        return Err(ExecutorError::Io(std::io::Error::other(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::logs::{NormalizedEntryType, utils::patch::extract_normalized_entry_from_patch};
+
+    fn temp_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.name", "Test"],
+            &["config", "user.email", "test@example.com"],
+            &["commit", "--quiet", "--allow-empty", "-m", "initial"],
+        ] {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        dir
+    }
+
+    fn auto_commit(only_on_success: bool) -> AutoCommit {
+        AutoCommit {
+            message_template: "{executor}: {prompt}".to_string(),
+            only_on_success,
+        }
+    }
+
+    fn head_message(dir: &Path) -> String {
+        let output = std::process::Command::new("git")
+            .args(["log", "-1", "--format=%s"])
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_commit_on_success() {
+        let repo = temp_repo();
+        std::fs::write(repo.path().join("fix.rs"), "fn fixed() {}\n").unwrap();
+        let config = auto_commit(true);
+
+        let message = config.render_message("CLAUDE_CODE", "Fix the watcher\nDetails...", Some(0));
+        let result = run_auto_commit(&config, repo.path(), &message, Some(0))
+            .await
+            .unwrap()
+            .expect("changes committed");
+
+        assert_eq!(result.message, "CLAUDE_CODE: Fix the watcher");
+        assert_eq!(head_message(repo.path()), "CLAUDE_CODE: Fix the watcher");
+        assert_eq!(result.commit.len(), 40);
+
+        let (_, entry) =
+            extract_normalized_entry_from_patch(&result.clone().into_patch(&MsgStore::new()))
+                .unwrap();
+        assert!(matches!(
+            entry.entry_type,
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::GitOp { commit, .. }
+            } if commit == result.commit
+        ));
+
+        // A clean tree has nothing to commit
+        assert_eq!(
+            run_auto_commit(&config, repo.path(), &message, Some(0))
+                .await
+                .unwrap(),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_no_commit_on_failure() {
+        let repo = temp_repo();
+        std::fs::write(repo.path().join("half-done.rs"), "fn broken(\n").unwrap();
+
+        let config = auto_commit(true);
+
+        for exit_code in [Some(1), None] {
+            let message = config.render_message("CODEX", "Refactor", exit_code);
+            let result = run_auto_commit(&config, repo.path(), &message, exit_code).await;
+            assert_eq!(result.unwrap(), None);
+        }
+        assert_eq!(head_message(repo.path()), "initial");
+
+        // Without only_on_success the partial work is still committed
+        let config = auto_commit(false);
+        let message = config.render_message("CODEX", "Refactor", Some(1));
+        let result = run_auto_commit(&config, repo.path(), &message, Some(1)).await;
+        assert!(result.unwrap().is_some());
+        assert_eq!(head_message(repo.path()), "CODEX: Refactor");
+    }
+}
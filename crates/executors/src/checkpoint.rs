@@ -0,0 +1,163 @@
+//! Periodic progress checkpoints for long-running agents.
+//!
+//! When a profile sets `checkpoint_interval_secs`, the agent's session id and the index of the
+//! latest normalized entry are written to a small JSON file on that interval, so a run that is
+//! cut short by a crash or restart can be resumed from its session.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+use crate::{
+    clock::{Clock, SystemClock},
+    logs::utils::EntryIndexProvider,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Checkpoint {
+    /// The agent's own session id, once it has reported one
+    pub session_id: Option<String>,
+    /// Index of the latest normalized entry, if any were produced
+    pub last_entry_index: Option<usize>,
+    pub written_at: DateTime<Utc>,
+}
+
+impl Checkpoint {
+    /// Current progress recorded in `msg_store`, stamped with `clock`'s time.
+    pub fn from_store(msg_store: &MsgStore, clock: &dyn Clock) -> Self {
+        let session_id = msg_store
+            .get_history()
+            .into_iter()
+            .rev()
+            .find_map(|msg| match msg {
+                LogMsg::SessionId(id) => Some(id),
+                _ => None,
+            });
+        Self {
+            session_id,
+            last_entry_index: EntryIndexProvider::start_from(msg_store)
+                .current()
+                .checked_sub(1),
+            written_at: clock.now().into(),
+        }
+    }
+
+    pub fn read(path: &Path) -> std::io::Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        serde_json::from_str(&raw).map_err(std::io::Error::other)
+    }
+
+    /// Replace the checkpoint at `path` without ever leaving a partially written file.
+    pub fn write(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(
+            &tmp,
+            serde_json::to_vec(self).map_err(std::io::Error::other)?,
+        )?;
+        std::fs::rename(&tmp, path)
+    }
+}
+
+/// Write a checkpoint for `msg_store` to `path` every `interval` until the run finishes, with a
+/// final write once it has.
+pub fn spawn_checkpoint_writer(
+    msg_store: Arc<MsgStore>,
+    path: PathBuf,
+    interval: Duration,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately; nothing has happened yet
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            let finished = msg_store
+                .get_history()
+                .iter()
+                .any(|msg| matches!(msg, LogMsg::Finished));
+            if let Err(e) = Checkpoint::from_store(&msg_store, &SystemClock).write(&path) {
+                tracing::warn!("Failed to write checkpoint {}: {e}", path.display());
+            }
+            if finished {
+                break;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::{NormalizedEntry, NormalizedEntryType, utils::ConversationPatch};
+
+    fn entry() -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: "working".to_string(),
+            metadata: None,
+        }
+    }
+
+    async fn wait_for_checkpoint(path: &Path, session_id: &str) -> Checkpoint {
+        for _ in 0..100 {
+            if let Ok(checkpoint) = Checkpoint::read(path)
+                && checkpoint.session_id.as_deref() == Some(session_id)
+            {
+                return checkpoint;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("no checkpoint for {session_id} at {}", path.display());
+    }
+
+    #[test]
+    fn test_checkpoint_stamped_with_clock() {
+        let clock = crate::clock::FakeClock::default();
+        clock.advance(Duration::from_secs(90));
+        let msg_store = MsgStore::new();
+
+        let checkpoint = Checkpoint::from_store(&msg_store, &clock);
+        assert_eq!(checkpoint.written_at.timestamp(), 90);
+        assert_eq!(checkpoint.session_id, None);
+        assert_eq!(checkpoint.last_entry_index, None);
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_written_at_interval_with_latest_session_id() {
+        let path = std::env::temp_dir()
+            .join(format!("checkpoint-{}", uuid::Uuid::new_v4()))
+            .join("run.json");
+        let msg_store = Arc::new(MsgStore::new());
+        msg_store.push_session_id("session-1".to_string());
+        msg_store.push_patch(ConversationPatch::add_normalized_entry(0, entry()));
+
+        let writer =
+            spawn_checkpoint_writer(msg_store.clone(), path.clone(), Duration::from_millis(300));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!path.exists(), "written before the interval elapsed");
+
+        let checkpoint = wait_for_checkpoint(&path, "session-1").await;
+        assert_eq!(checkpoint.last_entry_index, Some(0));
+
+        msg_store.push_session_id("session-2".to_string());
+        msg_store.push_patch(ConversationPatch::add_normalized_entry(1, entry()));
+        msg_store.push_finished();
+        writer.await.unwrap();
+
+        let checkpoint = Checkpoint::read(&path).unwrap();
+        assert_eq!(checkpoint.session_id.as_deref(), Some("session-2"));
+        assert_eq!(checkpoint.last_entry_index, Some(1));
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
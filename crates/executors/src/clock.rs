@@ -0,0 +1,123 @@
+//! Source of wall-clock time, injectable so time-based behaviour can be tested deterministically.
+
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+pub trait Clock: Debug + Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock; used everywhere outside of tests.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that stands still until moved. Clones share the same time.
+#[derive(Debug, Clone)]
+pub struct FakeClock {
+    now: Arc<Mutex<SystemTime>>,
+}
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new(SystemTime::UNIX_EPOCH)
+    }
+}
+
+impl FakeClock {
+    pub fn new(start: SystemTime) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(start)),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+
+    pub fn set(&self, to: SystemTime) {
+        *self.now.lock().unwrap() = to;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> SystemTime {
+        *self.now.lock().unwrap()
+    }
+}
+
+/// Tracks when a periodic beat is due on a [`Clock`]. Beats are scheduled from the previous
+/// beat's due time, so a late poll does not shift the cadence.
+#[derive(Debug)]
+pub struct Heartbeat {
+    clock: Arc<dyn Clock>,
+    interval: Duration,
+    next: SystemTime,
+}
+
+impl Heartbeat {
+    /// The first beat is due one `interval` from now.
+    pub fn new(clock: Arc<dyn Clock>, interval: Duration) -> Self {
+        let next = clock.now() + interval;
+        Self {
+            clock,
+            interval,
+            next,
+        }
+    }
+
+    /// Return the time of the beat that has come due, if one has. Beats missed between polls
+    /// are coalesced into the latest one.
+    pub fn poll(&mut self) -> Option<SystemTime> {
+        let now = self.clock.now();
+        if now < self.next {
+            return None;
+        }
+        let mut due = self.next;
+        while due + self.interval <= now {
+            due += self.interval;
+        }
+        self.next = due + self.interval;
+        Some(due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_cadence_with_fake_clock() {
+        let clock = FakeClock::default();
+        let start = clock.now();
+        let interval = Duration::from_secs(30);
+        let mut heartbeat = Heartbeat::new(Arc::new(clock.clone()), interval);
+
+        assert_eq!(heartbeat.poll(), None);
+        clock.advance(Duration::from_secs(29));
+        assert_eq!(heartbeat.poll(), None);
+
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(heartbeat.poll(), Some(start + interval));
+        assert_eq!(heartbeat.poll(), None);
+
+        // A late poll keeps the original cadence rather than restarting from the poll time
+        clock.advance(Duration::from_secs(45));
+        assert_eq!(heartbeat.poll(), Some(start + interval * 2));
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(heartbeat.poll(), Some(start + interval * 3));
+
+        // Beats missed entirely collapse into the latest one
+        clock.advance(Duration::from_secs(100));
+        assert_eq!(heartbeat.poll(), Some(start + interval * 6));
+        assert_eq!(heartbeat.poll(), None);
+    }
+}
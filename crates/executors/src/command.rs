@@ -1,12 +1,17 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
+use derivative::Derivative;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use ts_rs::TS;
 use workspace_utils::shell::resolve_executable_path;
 
-use crate::executors::ExecutorError;
+use crate::{env::redact_vars, executors::ExecutorError};
 
 #[derive(Debug, Error)]
 pub enum CommandBuildError {
@@ -40,7 +45,47 @@ impl CommandParts {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema, Default)]
+/// What spawning an agent would run, resolved without starting it or touching the filesystem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ResolvedCommand {
+    /// Program as configured, not yet looked up on `PATH`
+    pub program: String,
+    pub args: Vec<String>,
+    /// Variables set on top of the inherited environment
+    pub env: HashMap<String, String>,
+    pub working_dir: PathBuf,
+}
+
+impl ResolvedCommand {
+    /// `parts` run in `working_dir` with the profile env of `cmd`, including the `HOME` an
+    /// `isolated_home` sets.
+    pub fn new(parts: CommandParts, cmd: &CmdOverrides, working_dir: &Path) -> Self {
+        let mut env = cmd.env.clone().unwrap_or_default();
+        if let Some(home) = cmd.isolated_home.as_ref() {
+            let home = home.to_string_lossy().to_string();
+            #[cfg(windows)]
+            env.insert("USERPROFILE".to_string(), home.clone());
+            env.insert("HOME".to_string(), home);
+        }
+        Self {
+            program: parts.program,
+            args: parts.args,
+            env,
+            working_dir: working_dir.to_path_buf(),
+        }
+    }
+
+    /// Add a variable the executor sets itself, unless the profile env already sets it.
+    pub fn with_default_env(mut self, key: &str, value: &str) -> Self {
+        self.env
+            .entry(key.to_string())
+            .or_insert_with(|| value.to_string());
+        self
+    }
+}
+
+#[derive(Derivative, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+#[derivative(Debug)]
 pub struct CmdOverrides {
     #[schemars(
         title = "Base Command Override",
@@ -59,9 +104,162 @@ pub struct CmdOverrides {
         description = "Environment variables to set when running the executor"
     )]
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[derivative(Debug(format_with = "fmt_redacted_env"))]
     pub env: Option<HashMap<String, String>>,
+    #[schemars(
+        title = "Normalize Newlines",
+        description = "Convert CRLF and CR line endings in the prompt to LF before running"
+    )]
+    #[serde(default = "default_normalize_newlines")]
+    pub normalize_newlines: bool,
+    #[schemars(
+        title = "Isolated Home",
+        description = "Run the executor with HOME set to this directory instead of the user's home"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub isolated_home: Option<PathBuf>,
+    #[schemars(
+        title = "Minimum Free Disk Space",
+        description = "Refuse to start when the working directory's filesystem has fewer free bytes than this"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_bytes: Option<u64>,
+    #[schemars(
+        title = "Spill Logs To Disk",
+        description = "Directory to stream run logs into, keeping only a recent tail in memory"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spill_to_disk: Option<PathBuf>,
+    #[schemars(
+        title = "Record Directory",
+        description = "Route model provider traffic through a local proxy that records each exchange into this directory"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub record_dir: Option<PathBuf>,
+    #[schemars(
+        title = "Replay Directory",
+        description = "Answer model provider requests from the exchanges recorded in this directory, without network access"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_dir: Option<PathBuf>,
+    #[schemars(
+        title = "Coalesce Streamed Deltas",
+        description = "Buffer streamed message fragments and update the log once per message instead of once per token"
+    )]
+    #[serde(default = "default_coalesce_deltas")]
+    pub coalesce_deltas: bool,
+    #[schemars(
+        title = "Fallback Model",
+        description = "Model to retry the initial run with once when the selected model is unavailable or unknown"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_model: Option<String>,
+    #[schemars(
+        title = "Checkpoint Interval (seconds)",
+        description = "Periodically save the agent's session id and progress so a crashed run can be resumed"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub checkpoint_interval_secs: Option<u64>,
+    #[schemars(
+        title = "Max Session Turns",
+        description = "Refuse further follow-ups once a session has had this many, not counting the initial request; a new session starts the count again"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_session_turns: Option<u32>,
+    #[schemars(
+        title = "Skip Duplicate Prompts",
+        description = "Refuse a follow-up whose prompt is identical to the previous turn's, e.g. from an accidental double submit"
+    )]
+    #[serde(default)]
+    pub dedup_identical_prompts: bool,
+    // Whole seconds like the other profile durations; `max_wallclock()` gives the `Duration`
+    #[schemars(
+        title = "Max Wall-Clock Time (seconds)",
+        description = "Stop the agent once it has run this long, even while it is still producing output"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_wallclock_secs: Option<u64>,
+    #[schemars(
+        title = "Block Secrets in Prompts",
+        description = "Refuse to start the agent when the prompt contains something that looks like a credential, e.g. an AWS access key"
+    )]
+    #[serde(default)]
+    pub block_prompt_secrets: bool,
+}
+
+fn default_normalize_newlines() -> bool {
+    true
+}
+
+fn default_coalesce_deltas() -> bool {
+    true
 }
 
+/// `Debug` for [`CmdOverrides::env`] with credential values masked.
+fn fmt_redacted_env(
+    env: &Option<HashMap<String, String>>,
+    f: &mut std::fmt::Formatter<'_>,
+) -> std::fmt::Result {
+    std::fmt::Debug::fmt(&env.as_ref().map(redact_vars), f)
+}
+
+impl Default for CmdOverrides {
+    fn default() -> Self {
+        Self {
+            base_command_override: None,
+            additional_params: None,
+            env: None,
+            normalize_newlines: default_normalize_newlines(),
+            isolated_home: None,
+            min_free_bytes: None,
+            spill_to_disk: None,
+            record_dir: None,
+            replay_dir: None,
+            coalesce_deltas: default_coalesce_deltas(),
+            fallback_model: None,
+            checkpoint_interval_secs: None,
+            max_session_turns: None,
+            dedup_identical_prompts: false,
+            max_wallclock_secs: None,
+            block_prompt_secrets: false,
+        }
+    }
+}
+
+impl CmdOverrides {
+    /// Home directory the executor sees: `isolated_home` when set, otherwise the user's home.
+    pub fn home_dir(&self) -> Option<PathBuf> {
+        self.isolated_home.clone().or_else(dirs::home_dir)
+    }
+
+    /// How often to checkpoint progress, if enabled with a nonzero `checkpoint_interval_secs`.
+    pub fn checkpoint_interval(&self) -> Option<Duration> {
+        self.checkpoint_interval_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Total runtime allowed per spawn, if capped with a nonzero `max_wallclock_secs`.
+    pub fn max_wallclock(&self) -> Option<Duration> {
+        self.max_wallclock_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Prompt as it should be passed to the CLI, honouring `normalize_newlines`.
+    pub fn normalize_prompt(&self, prompt: &str) -> String {
+        if self.normalize_newlines {
+            prompt.replace("\r\n", "\n").replace('\r', "\n")
+        } else {
+            prompt.to_string()
+        }
+    }
+}
+
+/// Builds the argv for an executor CLI.
+///
+/// Prompts are never part of the built command: executors deliver them over stdin or through the
+/// client's protocol messages, so a prompt such as `--help` can never be parsed as a flag.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
 pub struct CommandBuilder {
     /// Base executable command (e.g., "npx -y @anthropic-ai/claude-code@latest")
@@ -187,3 +385,39 @@ pub fn apply_overrides(
         Ok(builder)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(normalize_newlines: bool) -> CmdOverrides {
+        CmdOverrides {
+            normalize_newlines,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_normalize_newlines_defaults_to_true() {
+        assert!(CmdOverrides::default().normalize_newlines);
+        let parsed: CmdOverrides = serde_json::from_str("{}").unwrap();
+        assert!(parsed.normalize_newlines);
+    }
+
+    #[test]
+    fn test_crlf_prompt() {
+        let prompt = "first line\r\nsecond line\r\n";
+        assert_eq!(
+            overrides(true).normalize_prompt(prompt),
+            "first line\nsecond line\n"
+        );
+        assert_eq!(overrides(false).normalize_prompt(prompt), prompt);
+    }
+
+    #[test]
+    fn test_mixed_line_endings_prompt() {
+        let prompt = "a\r\nb\rc\nd";
+        assert_eq!(overrides(true).normalize_prompt(prompt), "a\nb\nc\nd");
+        assert_eq!(overrides(false).normalize_prompt(prompt), prompt);
+    }
+}
@@ -0,0 +1,204 @@
+//! Structured multi-file context for initial prompts.
+//!
+//! A manifest lists worktree files (optionally narrowed to a line range) that are rendered as
+//! labeled excerpts ahead of the user's prompt, so every executor sees the same context block.
+
+use std::path::{Component, Path};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::executors::ExecutorError;
+
+/// Largest excerpt taken from a single entry; longer excerpts are truncated.
+pub const MAX_EXCERPT_BYTES: usize = 32 * 1024;
+/// Largest rendered context block accepted for one prompt.
+pub const MAX_MANIFEST_BYTES: usize = 128 * 1024;
+
+/// A worktree file, or a 1-based inclusive line range within it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+pub struct FileRange {
+    /// Path relative to the agent's working directory.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u32>,
+}
+
+impl FileRange {
+    fn label(&self) -> String {
+        match (self.start, self.end) {
+            (None, None) => self.path.clone(),
+            (start, end) => format!(
+                "{} (lines {}-{})",
+                self.path,
+                start.unwrap_or(1),
+                end.map_or_else(|| "end".to_string(), |end| end.to_string())
+            ),
+        }
+    }
+}
+
+fn invalid(entry: &FileRange, reason: &str) -> ExecutorError {
+    ExecutorError::InvalidContextManifest(format!("{}: {reason}", entry.path))
+}
+
+/// Read every entry from `root` and render them as labeled excerpts.
+///
+/// Entries must stay inside `root` (no absolute paths, `..` escapes or symlinks out), and the
+/// rendered block must fit in [`MAX_MANIFEST_BYTES`].
+pub async fn render_context_manifest(
+    root: &Path,
+    entries: &[FileRange],
+) -> Result<String, ExecutorError> {
+    let root = tokio::fs::canonicalize(root)
+        .await
+        .map_err(ExecutorError::Io)?;
+    let mut rendered = String::from("Context files:\n");
+
+    for entry in entries {
+        let relative = Path::new(&entry.path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| matches!(c, Component::ParentDir | Component::Prefix(_)))
+        {
+            return Err(invalid(entry, "path must be relative to the worktree"));
+        }
+        let path = tokio::fs::canonicalize(root.join(relative))
+            .await
+            .map_err(|e| invalid(entry, &e.to_string()))?;
+        if !path.starts_with(&root) {
+            return Err(invalid(entry, "path resolves outside the worktree"));
+        }
+
+        let contents = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| invalid(entry, &e.to_string()))?;
+        let mut excerpt = match (entry.start, entry.end) {
+            (None, None) => contents,
+            (start, end) => {
+                let start = start.unwrap_or(1);
+                if start == 0 || end.is_some_and(|end| end < start) {
+                    return Err(invalid(entry, "invalid line range"));
+                }
+                let take = end.map_or(usize::MAX, |end| (end - start + 1) as usize);
+                contents
+                    .lines()
+                    .skip(start as usize - 1)
+                    .take(take)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+        };
+        if excerpt.len() > MAX_EXCERPT_BYTES {
+            let mut end = MAX_EXCERPT_BYTES;
+            while !excerpt.is_char_boundary(end) {
+                end -= 1;
+            }
+            excerpt.truncate(end);
+            excerpt.push_str("\n[truncated]");
+        }
+
+        rendered.push_str(&format!(
+            "\n--- {} ---\n{}\n",
+            entry.label(),
+            excerpt.trim_end_matches('\n')
+        ));
+        if rendered.len() > MAX_MANIFEST_BYTES {
+            return Err(ExecutorError::InvalidContextManifest(format!(
+                "context exceeds {MAX_MANIFEST_BYTES} bytes"
+            )));
+        }
+    }
+
+    Ok(rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_worktree() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("context-manifest-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("README.md"), "# Title\nIntro\n").unwrap();
+        std::fs::write(
+            dir.join("src/lib.rs"),
+            "line 1\nline 2\nline 3\nline 4\nline 5\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn entry(path: &str, start: Option<u32>, end: Option<u32>) -> FileRange {
+        FileRange {
+            path: path.to_string(),
+            start,
+            end,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_full_file_entry() {
+        let dir = temp_worktree();
+
+        let rendered = render_context_manifest(&dir, &[entry("README.md", None, None)])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rendered,
+            "Context files:\n\n--- README.md ---\n# Title\nIntro\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_line_range_entry() {
+        let dir = temp_worktree();
+
+        let rendered = render_context_manifest(&dir, &[entry("src/lib.rs", Some(2), Some(4))])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            rendered,
+            "Context files:\n\n--- src/lib.rs (lines 2-4) ---\nline 2\nline 3\nline 4\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_containment_violation_is_rejected() {
+        let dir = temp_worktree();
+        let outside = dir.with_extension("outside");
+        std::fs::write(&outside, "secret").unwrap();
+
+        for path in [
+            format!("../{}", outside.file_name().unwrap().to_string_lossy()),
+            outside.to_string_lossy().to_string(),
+        ] {
+            let err = render_context_manifest(&dir, &[entry(&path, None, None)])
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(err, ExecutorError::InvalidContextManifest(_)),
+                "{err:?}"
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&outside, dir.join("link")).unwrap();
+            let err = render_context_manifest(&dir, &[entry("link", None, None)])
+                .await
+                .unwrap_err();
+            assert!(matches!(err, ExecutorError::InvalidContextManifest(_)));
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+        let _ = std::fs::remove_file(&outside);
+    }
+}
@@ -0,0 +1,130 @@
+//! Checking the worktree for uncommitted changes before an agent starts.
+//!
+//! [`CodingAgentInitialRequest::on_dirty_worktree`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::on_dirty_worktree)
+//! decides what happens when `git status` in the effective directory is not clean: nothing, an
+//! [`AgentEvent::WorktreeDirty`] entry ahead of the agent's output, or refusing to spawn.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{auto_commit::git, executors::ExecutorError, logs::AgentEvent};
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum OnDirty {
+    /// Spawn without looking at the worktree
+    #[default]
+    Allow,
+    /// Spawn, recording the changed paths as a `WorktreeDirty` event
+    Warn,
+    /// Refuse to spawn with [`ExecutorError::DirtyWorktree`]
+    Reject,
+}
+
+/// Paths with uncommitted changes (including untracked files) in `dir`, as `git status` lists
+/// them.
+pub async fn dirty_paths(dir: &Path) -> Result<Vec<String>, ExecutorError> {
+    let status = git(dir, &["status", "--porcelain", "--untracked-files=all"]).await?;
+    Ok(status
+        .lines()
+        .filter_map(|line| line.get(3..))
+        .map(|path| path.to_string())
+        .collect())
+}
+
+/// Apply `policy` to `dir`: the event to record when it is dirty under [`OnDirty::Warn`], or an
+/// error under [`OnDirty::Reject`]. A directory `git status` can't read is only an error under
+/// [`OnDirty::Reject`].
+pub async fn check_worktree(
+    policy: OnDirty,
+    dir: &Path,
+) -> Result<Option<AgentEvent>, ExecutorError> {
+    let paths = match policy {
+        OnDirty::Allow => return Ok(None),
+        OnDirty::Warn => match dirty_paths(dir).await {
+            Ok(paths) => paths,
+            Err(e) => {
+                tracing::warn!(
+                    "Could not check {} for uncommitted changes: {e}",
+                    dir.display()
+                );
+                return Ok(None);
+            }
+        },
+        OnDirty::Reject => dirty_paths(dir).await?,
+    };
+    if paths.is_empty() {
+        return Ok(None);
+    }
+    match policy {
+        OnDirty::Reject => Err(ExecutorError::DirtyWorktree { paths }),
+        _ => Ok(Some(AgentEvent::WorktreeDirty { paths })),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn temp_repo() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.name", "Test"],
+            &["config", "user.email", "test@example.com"],
+            &["commit", "--quiet", "--allow-empty", "-m", "initial"],
+        ] {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .status()
+                .unwrap();
+            assert!(status.success());
+        }
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_clean_worktree_passes_every_policy() {
+        let repo = temp_repo();
+
+        for policy in [OnDirty::Allow, OnDirty::Warn, OnDirty::Reject] {
+            assert_eq!(check_worktree(policy, repo.path()).await.unwrap(), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dirty_worktree_per_policy() {
+        let repo = temp_repo();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/half-done.rs"), "fn broken(\n").unwrap();
+
+        assert_eq!(
+            check_worktree(OnDirty::Allow, repo.path()).await.unwrap(),
+            None
+        );
+        assert_eq!(
+            check_worktree(OnDirty::Warn, repo.path()).await.unwrap(),
+            Some(AgentEvent::WorktreeDirty {
+                paths: vec!["src/half-done.rs".to_string()],
+            })
+        );
+        assert!(matches!(
+            check_worktree(OnDirty::Reject, repo.path()).await,
+            Err(ExecutorError::DirtyWorktree { paths }) if paths == ["src/half-done.rs"]
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_worktree_only_fails_reject() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(
+            check_worktree(OnDirty::Warn, dir.path()).await.unwrap(),
+            None
+        );
+        assert!(check_worktree(OnDirty::Reject, dir.path()).await.is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Free disk space guard applied before an executor is spawned.
+//!
+//! Agents that clone or build can fill a disk; operators can set `min_free_bytes` to refuse to
+//! start when the filesystem holding the working directory is already low.
+
+use std::path::Path;
+
+use crate::executors::ExecutorError;
+
+/// Reports the bytes available to unprivileged users on the filesystem containing a path.
+pub type DiskSpaceProbe = fn(&Path) -> std::io::Result<u64>;
+
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    let stat = nix::sys::statvfs::statvfs(path).map_err(std::io::Error::from)?;
+    Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_space(_path: &Path) -> std::io::Result<u64> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "disk space probing is not supported on this platform",
+    ))
+}
+
+/// Fail with [`ExecutorError::InsufficientDiskSpace`] when `dir` has less than `required` bytes
+/// free. A probe failure is logged and does not block the spawn.
+pub fn ensure_free_space(
+    dir: &Path,
+    required: u64,
+    probe: DiskSpaceProbe,
+) -> Result<(), ExecutorError> {
+    match probe(dir) {
+        Ok(available) if available < required => Err(ExecutorError::InsufficientDiskSpace {
+            available,
+            required,
+        }),
+        Ok(_) => Ok(()),
+        Err(err) => {
+            tracing::warn!(
+                "Could not determine free disk space for {}: {err}",
+                dir.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    #[test]
+    fn test_below_threshold_is_rejected() {
+        let result = ensure_free_space(Path::new("/work"), 10 * GIB, |_| Ok(2 * GIB));
+        assert!(matches!(
+            result,
+            Err(ExecutorError::InsufficientDiskSpace {
+                available,
+                required,
+            }) if available == 2 * GIB && required == 10 * GIB
+        ));
+    }
+
+    #[test]
+    fn test_above_threshold_is_allowed() {
+        assert!(ensure_free_space(Path::new("/work"), GIB, |_| Ok(5 * GIB)).is_ok());
+        assert!(ensure_free_space(Path::new("/work"), GIB, |_| Ok(GIB)).is_ok());
+    }
+
+    #[test]
+    fn test_probe_failure_does_not_block() {
+        let result = ensure_free_space(Path::new("/work"), GIB, |_| {
+            Err(std::io::Error::other("probe failed"))
+        });
+        assert!(result.is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_available_space_reads_real_filesystem() {
+        assert!(available_space(&std::env::temp_dir()).unwrap() > 0);
+    }
+}
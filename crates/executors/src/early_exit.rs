@@ -0,0 +1,342 @@
+//! Early-exit detection for freshly spawned agents.
+//!
+//! Captures a bounded prefix of the child's stderr so a CLI that fails immediately (bad flags,
+//! missing auth, unknown model) surfaces as an actionable [`ExecutorError::SpawnFailed`] rather
+//! than a bare exit code buried in the logs.
+
+use std::{sync::LazyLock, time::Duration};
+
+use futures::{StreamExt, stream::BoxStream};
+use regex::Regex;
+use tokio::time::Instant;
+
+use crate::{
+    executors::{ExecutorError, SpawnedChild},
+    secret_scan,
+    stdout_dup::duplicate_stderr,
+};
+
+/// Maximum number of stderr bytes kept for error classification.
+pub const STDERR_PREFIX_LIMIT: usize = 4 * 1024;
+/// How long after spawn an exit still counts as "early".
+pub const EARLY_EXIT_WINDOW: Duration = Duration::from_millis(250);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(25);
+/// Grace period for the stderr mirror to catch up once the child has exited.
+const STDERR_DRAIN_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Plan/entitlement wording shared by most clients; per-client lists extend this.
+static GENERIC_ENTITLEMENT_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    compile_patterns(&[
+        r"(?i)model (?:[`'\x22]?(?P<model>[\w./:-]+)[`'\x22]? )?is not available (?:on|for|with) your (?:plan|tier|subscription|account)",
+    ])
+});
+
+/// Wording clients use when the selected model name is not recognised at all.
+static GENERIC_UNKNOWN_MODEL_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    compile_patterns(&[
+        r"(?i)(?:unknown|invalid|unrecognized) model(?: name)?[:\s]+[`'\x22]?(?P<model>[\w./:-]+)",
+        r"(?i)model [`'\x22]?(?P<model>[\w./:-]+)[`'\x22]? (?:does not exist|was not found|not found)",
+    ])
+});
+
+/// Compile a client's built-in error signatures; an optional `model` group names the model.
+pub fn compile_patterns(patterns: &[&str]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .map(|pattern| Regex::new(pattern).expect("valid model error pattern"))
+        .collect()
+}
+
+/// The model an entitlement failure in `text` is about: the `model` group of the matching
+/// pattern, then `selected_model`. `None` when neither the generic nor the client `patterns`
+/// match.
+pub fn entitlement_failure(
+    text: &str,
+    patterns: &[Regex],
+    selected_model: Option<&str>,
+) -> Option<String> {
+    blamed_model(
+        GENERIC_ENTITLEMENT_PATTERNS.iter().chain(patterns),
+        text,
+        selected_model,
+    )
+}
+
+/// Like [`entitlement_failure`], for output saying the model does not exist.
+pub fn unknown_model_failure(text: &str, selected_model: Option<&str>) -> Option<String> {
+    blamed_model(GENERIC_UNKNOWN_MODEL_PATTERNS.iter(), text, selected_model)
+}
+
+fn blamed_model<'a>(
+    mut patterns: impl Iterator<Item = &'a Regex>,
+    text: &str,
+    selected_model: Option<&str>,
+) -> Option<String> {
+    let named = patterns.find_map(|pattern| {
+        pattern
+            .captures(text)
+            .map(|caps| caps.name("model").map(|m| m.as_str().to_string()))
+    })?;
+    Some(
+        named
+            .or_else(|| selected_model.map(str::to_string))
+            .unwrap_or_else(|| "selected model".to_string()),
+    )
+}
+
+/// Turn a [`ExecutorError::SpawnFailed`] whose stderr matches one of `patterns` into
+/// [`ExecutorError::ModelNotEntitled`], naming the model like [`entitlement_failure`]. Any
+/// other error is returned unchanged.
+pub fn classify_entitlement(
+    err: ExecutorError,
+    patterns: &[Regex],
+    selected_model: Option<String>,
+) -> ExecutorError {
+    let ExecutorError::SpawnFailed { stderr, .. } = &err else {
+        return err;
+    };
+    match entitlement_failure(stderr, patterns, selected_model.as_deref()) {
+        Some(model) => ExecutorError::ModelNotEntitled { model },
+        None => err,
+    }
+}
+
+/// Turn a [`ExecutorError::SpawnFailed`] whose stderr says the model does not exist into
+/// [`ExecutorError::UnknownModel`], naming the model like [`classify_entitlement`].
+pub fn classify_unknown_model(err: ExecutorError, selected_model: Option<String>) -> ExecutorError {
+    let ExecutorError::SpawnFailed { stderr, .. } = &err else {
+        return err;
+    };
+    match unknown_model_failure(stderr, selected_model.as_deref()) {
+        Some(model) => ExecutorError::UnknownModel { model },
+        None => err,
+    }
+}
+
+/// Wait up to `window` for the child to exit. A nonzero exit inside the window becomes
+/// [`ExecutorError::SpawnFailed`] carrying the redacted stderr prefix; a child that is still
+/// running (or exited cleanly) is left untouched.
+pub async fn check_early_exit(
+    spawned: &mut SpawnedChild,
+    window: Duration,
+) -> Result<(), ExecutorError> {
+    // Executors that do not pipe stderr still get the exit check, just without a prefix
+    let stderr =
+        duplicate_stderr(&mut spawned.child).unwrap_or_else(|_| futures::stream::empty().boxed());
+    let mut capture = tokio::spawn(capture_prefix(stderr, STDERR_PREFIX_LIMIT));
+
+    let deadline = Instant::now() + window;
+    let status = loop {
+        if let Some(status) = spawned.child.try_wait().map_err(ExecutorError::Io)? {
+            break Some(status);
+        }
+        if Instant::now() >= deadline {
+            break None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    };
+
+    match status {
+        Some(status) if !status.success() => {
+            let prefix = tokio::time::timeout(STDERR_DRAIN_TIMEOUT, &mut capture)
+                .await
+                .ok()
+                .and_then(Result::ok)
+                .unwrap_or_default();
+            capture.abort();
+            Err(ExecutorError::SpawnFailed {
+                status: status.to_string(),
+                stderr: redact_secrets(prefix.trim()),
+            })
+        }
+        _ => {
+            capture.abort();
+            Ok(())
+        }
+    }
+}
+
+/// Collect stderr until `limit` bytes are buffered or the stream ends.
+async fn capture_prefix(
+    mut stderr: BoxStream<'static, std::io::Result<String>>,
+    limit: usize,
+) -> String {
+    let mut prefix = String::new();
+    while prefix.len() < limit {
+        match stderr.next().await {
+            Some(Ok(chunk)) => prefix.push_str(&chunk),
+            Some(Err(_)) | None => break,
+        }
+    }
+    if prefix.len() > limit {
+        let mut end = limit;
+        while !prefix.is_char_boundary(end) {
+            end -= 1;
+        }
+        prefix.truncate(end);
+    }
+    prefix
+}
+
+/// Mask values that look like credentials before they end up in an error message.
+fn redact_secrets(text: &str) -> String {
+    secret_scan::mask(text, &secret_scan::scanner().scan(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_secrets() {
+        assert_eq!(
+            redact_secrets("Error: JBAI_TOKEN=jb4f8a91c2d7e6b350 rejected"),
+            "Error: JBAI_TOKEN=*** rejected"
+        );
+        assert_eq!(
+            redact_secrets("Authorization: Bearer eyJhbGciOi.x.y"),
+            "Authorization: Bearer ***"
+        );
+        assert_eq!(
+            redact_secrets("invalid key sk-abcdef1234567890"),
+            "invalid key ***"
+        );
+        assert_eq!(redact_secrets("unknown flag --foo"), "unknown flag --foo");
+    }
+
+    #[tokio::test]
+    async fn test_capture_prefix_is_bounded() {
+        let chunks: Vec<std::io::Result<String>> = vec![
+            Ok("a".repeat(3000)),
+            Ok("b".repeat(3000)),
+            Ok("c".repeat(10)),
+        ];
+        let prefix =
+            capture_prefix(futures::stream::iter(chunks).boxed(), STDERR_PREFIX_LIMIT).await;
+        assert_eq!(prefix.len(), STDERR_PREFIX_LIMIT);
+        assert!(!prefix.contains('c'));
+    }
+
+    fn spawn_failed(stderr: &str) -> ExecutorError {
+        ExecutorError::SpawnFailed {
+            status: "exit status: 1".to_string(),
+            stderr: stderr.to_string(),
+        }
+    }
+
+    fn entitled_model(err: ExecutorError) -> String {
+        match err {
+            ExecutorError::ModelNotEntitled { model } => model,
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_classify_claude_entitlement() {
+        use crate::executors::claude::CLAUDE_ENTITLEMENT_PATTERNS;
+
+        let err = spawn_failed(
+            "API Error: There's an issue with the selected model (claude-opus-4-1). It may not \
+             exist or you may not have access to it. Run /model to pick a different model.",
+        );
+        let err = classify_entitlement(err, &CLAUDE_ENTITLEMENT_PATTERNS, None);
+        assert_eq!(entitled_model(err), "claude-opus-4-1");
+    }
+
+    #[test]
+    fn test_classify_codex_entitlement() {
+        use crate::executors::codex::CODEX_ENTITLEMENT_PATTERNS;
+
+        let err = spawn_failed(
+            "ERROR: {\"detail\":\"The 'gpt-5-pro' model is not supported when using Codex with a \
+             ChatGPT account.\"}",
+        );
+        let err = classify_entitlement(err, &CODEX_ENTITLEMENT_PATTERNS, None);
+        assert_eq!(entitled_model(err), "gpt-5-pro");
+
+        let err = spawn_failed("Project `proj_x` does not have access to model `o3-pro`");
+        let err = classify_entitlement(err, &CODEX_ENTITLEMENT_PATTERNS, None);
+        assert_eq!(entitled_model(err), "o3-pro");
+    }
+
+    #[test]
+    fn test_classify_generic_entitlement_falls_back_to_selected_model() {
+        let err = spawn_failed("Error: model is not available for your plan");
+        let err = classify_entitlement(err, &[], Some("sonnet".to_string()));
+        assert_eq!(entitled_model(err), "sonnet");
+    }
+
+    #[test]
+    fn test_classify_leaves_other_errors_untouched() {
+        use crate::executors::claude::CLAUDE_ENTITLEMENT_PATTERNS;
+
+        let err = classify_entitlement(
+            spawn_failed("unknown flag --foo"),
+            &CLAUDE_ENTITLEMENT_PATTERNS,
+            Some("sonnet".to_string()),
+        );
+        assert!(matches!(err, ExecutorError::SpawnFailed { .. }));
+    }
+
+    #[test]
+    fn test_classify_unknown_model() {
+        let err = classify_unknown_model(spawn_failed("Error: unknown model: gpt-9"), None);
+        assert!(matches!(err, ExecutorError::UnknownModel { model } if model == "gpt-9"));
+
+        let err = classify_unknown_model(
+            spawn_failed("model 'sonnet-9' does not exist"),
+            Some("sonnet".to_string()),
+        );
+        assert!(matches!(err, ExecutorError::UnknownModel { model } if model == "sonnet-9"));
+
+        let err = classify_unknown_model(spawn_failed("unknown flag --foo"), None);
+        assert!(matches!(err, ExecutorError::SpawnFailed { .. }));
+    }
+
+    #[cfg(unix)]
+    fn spawn_shell(script: &str) -> SpawnedChild {
+        use std::process::Stdio;
+
+        use command_group::AsyncCommandGroup;
+
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg(script)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command.group_spawn().unwrap().into()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_early_nonzero_exit_reports_stderr() {
+        let mut spawned = spawn_shell("echo 'auth failed: token=jb4f8a91c2d7e6b350' >&2; exit 3");
+
+        let err = check_early_exit(&mut spawned, Duration::from_secs(5))
+            .await
+            .unwrap_err();
+
+        match err {
+            ExecutorError::SpawnFailed { status, stderr } => {
+                assert!(status.contains('3'), "{status}");
+                assert_eq!(stderr, "auth failed: token=***");
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_running_child_passes() {
+        let mut spawned = spawn_shell("sleep 5");
+
+        check_early_exit(&mut spawned, Duration::from_millis(100))
+            .await
+            .unwrap();
+
+        assert!(spawned.child.inner().stderr.is_some());
+        let _ = spawned.child.kill().await;
+    }
+}
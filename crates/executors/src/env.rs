@@ -1,15 +1,48 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, path::PathBuf, sync::LazyLock};
 
+use regex::{Captures, Regex};
 use tokio::process::Command;
 
-use crate::command::CmdOverrides;
+use crate::{
+    command::CmdOverrides,
+    executors::ExecutorError,
+    secret_scan::{REDACTED, is_secret_key},
+};
+
+static VAR_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\$(?:\{([A-Za-z_][A-Za-z0-9_]*)\}|([A-Za-z_][A-Za-z0-9_]*))").unwrap()
+});
+
+/// `vars` with the value of every secret key replaced by [`REDACTED`].
+pub fn redact_vars(vars: &HashMap<String, String>) -> HashMap<String, String> {
+    vars.iter()
+        .map(|(key, value)| {
+            let value = if is_secret_key(key) {
+                REDACTED.to_string()
+            } else {
+                value.clone()
+            };
+            (key.clone(), value)
+        })
+        .collect()
+}
 
 /// Environment variables to inject into executor processes
-#[derive(Debug, Clone, Default)]
+///
+/// `Debug` output masks secret values, see [`ExecutionEnv::redacted`].
+#[derive(Clone, Default)]
 pub struct ExecutionEnv {
     pub vars: HashMap<String, String>,
 }
 
+impl fmt::Debug for ExecutionEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExecutionEnv")
+            .field("vars", &redact_vars(&self.vars))
+            .finish()
+    }
+}
+
 impl ExecutionEnv {
     pub fn new() -> Self {
         Self {
@@ -35,12 +68,24 @@ impl ExecutionEnv {
     }
 
     /// Return a new env with profile env from CmdOverrides merged in.
+    ///
+    /// An `isolated_home` takes precedence over any `HOME` in the profile env. The directory
+    /// itself is created at spawn, see [`crate::executors::CodingAgent::create_isolated_home`].
     pub fn with_profile(self, cmd: &CmdOverrides) -> Self {
-        if let Some(ref profile_env) = cmd.env {
+        let mut env = if let Some(ref profile_env) = cmd.env {
             self.with_overrides(profile_env)
         } else {
             self
+        };
+
+        if let Some(home) = cmd.isolated_home.as_ref() {
+            let home = home.to_string_lossy().to_string();
+            #[cfg(windows)]
+            env.insert("USERPROFILE", home.clone());
+            env.insert("HOME", home);
         }
+
+        env
     }
 
     /// Apply all environment variables to a Command
@@ -50,9 +95,59 @@ impl ExecutionEnv {
         }
     }
 
+    /// A copy safe to log: values of `*_TOKEN`, `*_KEY`, `*_SECRET` and the other
+    /// [`is_secret_key`] vars are replaced with [`REDACTED`].
+    pub fn redacted(&self) -> ExecutionEnv {
+        ExecutionEnv {
+            vars: redact_vars(&self.vars),
+        }
+    }
+
     pub fn contains_key(&self, key: &str) -> bool {
         self.vars.contains_key(key)
     }
+
+    /// Value the child would see for `key`: this env first, then the inherited process env.
+    pub(crate) fn lookup(&self, key: &str) -> Option<String> {
+        self.vars
+            .get(key)
+            .cloned()
+            .or_else(|| std::env::var(key).ok())
+    }
+
+    /// Expand a leading `~` and `$VAR`/`${VAR}` references in a configured working directory.
+    /// A variable that is not set fails with [`ExecutorError::InvalidWorkingDir`].
+    pub fn expand_working_dir(&self, raw: &str) -> Result<PathBuf, ExecutorError> {
+        let mut missing = None;
+        let expanded = VAR_PATTERN.replace_all(raw, |caps: &Captures| {
+            let name = caps.get(1).or_else(|| caps.get(2)).unwrap().as_str();
+            self.lookup(name).unwrap_or_else(|| {
+                missing.get_or_insert_with(|| name.to_string());
+                String::new()
+            })
+        });
+        if let Some(name) = missing {
+            return Err(ExecutorError::InvalidWorkingDir(format!(
+                "{raw}: environment variable `{name}` is not set"
+            )));
+        }
+
+        let Some(rest) = expanded.strip_prefix('~') else {
+            return Ok(PathBuf::from(expanded.as_ref()));
+        };
+        if !rest.is_empty() && !rest.starts_with(['/', '\\']) {
+            // `~user` forms are not supported; keep the value literal
+            return Ok(PathBuf::from(expanded.as_ref()));
+        }
+        let home = self
+            .lookup("HOME")
+            .map(PathBuf::from)
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| {
+                ExecutorError::InvalidWorkingDir(format!("{raw}: home directory is unknown"))
+            })?;
+        Ok(home.join(rest.trim_start_matches(['/', '\\'])))
+    }
 }
 
 #[cfg(test)]
@@ -75,4 +170,110 @@ mod tests {
         assert_eq!(merged.vars.get("FOO").unwrap(), "profile"); // overrides
         assert_eq!(merged.vars.get("BAR").unwrap(), "profile");
     }
+
+    #[test]
+    fn redacted_masks_secret_values() {
+        let mut env = ExecutionEnv::new();
+        env.insert("JBAI_TOKEN", "eyJhbGciOi.secret");
+        env.insert("openai_api_key", "sk-123");
+        env.insert("VK_PROJECT_NAME", "vibe-kanban");
+        env.insert("KEYBOARD_LAYOUT", "us");
+
+        let redacted = env.redacted();
+
+        assert_eq!(redacted.vars["JBAI_TOKEN"], REDACTED);
+        assert_eq!(redacted.vars["openai_api_key"], REDACTED);
+        assert_eq!(redacted.vars["VK_PROJECT_NAME"], "vibe-kanban");
+        assert_eq!(redacted.vars["KEYBOARD_LAYOUT"], "us");
+        // The original is untouched
+        assert_eq!(env.vars["JBAI_TOKEN"], "eyJhbGciOi.secret");
+    }
+
+    #[test]
+    fn debug_output_never_contains_secrets() {
+        let mut env = ExecutionEnv::new();
+        env.insert("JBAI_TOKEN", "eyJhbGciOi.secret");
+        env.insert("FOO", "bar");
+        let cmd = CmdOverrides {
+            env: Some(env.vars.clone()),
+            ..Default::default()
+        };
+
+        for output in [format!("{env:?}"), format!("{cmd:?}")] {
+            assert!(!output.contains("eyJhbGciOi.secret"), "{output}");
+            assert!(output.contains(r#""JBAI_TOKEN": "***""#), "{output}");
+            assert!(output.contains(r#""FOO": "bar""#), "{output}");
+        }
+    }
+
+    #[test]
+    fn expand_working_dir_tilde() {
+        let mut env = ExecutionEnv::new();
+        env.insert("HOME", "/home/agent");
+
+        assert_eq!(
+            env.expand_working_dir("~/sub").unwrap(),
+            PathBuf::from("/home/agent/sub")
+        );
+        assert_eq!(
+            env.expand_working_dir("~").unwrap(),
+            PathBuf::from("/home/agent")
+        );
+        assert_eq!(
+            env.expand_working_dir("sub/~").unwrap(),
+            PathBuf::from("sub/~")
+        );
+    }
+
+    #[test]
+    fn expand_working_dir_known_var() {
+        let mut env = ExecutionEnv::new();
+        env.insert("PROJECT", "repo-a");
+
+        assert_eq!(
+            env.expand_working_dir("$PROJECT/sub").unwrap(),
+            PathBuf::from("repo-a/sub")
+        );
+        assert_eq!(
+            env.expand_working_dir("${PROJECT}_build").unwrap(),
+            PathBuf::from("repo-a_build")
+        );
+    }
+
+    #[test]
+    fn expand_working_dir_unknown_var_errors() {
+        let env = ExecutionEnv::new();
+        let name = format!("VK_MISSING_{}", uuid::Uuid::new_v4().simple());
+
+        let err = env.expand_working_dir(&format!("${name}/sub")).unwrap_err();
+
+        match err {
+            ExecutorError::InvalidWorkingDir(message) => assert!(message.contains(&name)),
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn isolated_home_sets_home() {
+        let home = std::env::temp_dir().join(format!("isolated-home-{}", uuid::Uuid::new_v4()));
+        let mut profile_env = HashMap::new();
+        profile_env.insert("HOME".to_string(), "/from/profile".to_string());
+        let cmd = CmdOverrides {
+            env: Some(profile_env),
+            isolated_home: Some(home.clone()),
+            ..Default::default()
+        };
+
+        let env = ExecutionEnv::default().with_profile(&cmd);
+
+        assert_eq!(
+            env.vars.get("HOME").unwrap(),
+            &home.to_string_lossy().to_string()
+        );
+        #[cfg(windows)]
+        assert_eq!(
+            env.vars.get("USERPROFILE").unwrap(),
+            &home.to_string_lossy().to_string()
+        );
+    }
 }
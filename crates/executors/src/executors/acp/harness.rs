@@ -113,6 +113,8 @@ impl AcpAgentHarness {
             child,
             exit_signal: Some(exit_rx),
             interrupt_sender: None,
+            events: Vec::new(),
+            spawn_permit: None,
         })
     }
 
@@ -163,6 +165,8 @@ impl AcpAgentHarness {
             child,
             exit_signal: Some(exit_rx),
             interrupt_sender: None,
+            events: Vec::new(),
+            spawn_permit: None,
         })
     }
 
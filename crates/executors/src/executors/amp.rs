@@ -33,9 +33,13 @@ pub struct Amp {
 }
 
 impl Amp {
+    pub fn base_command() -> &'static str {
+        "npx -y @sourcegraph/amp@0.0.1764777697-g907e30"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @sourcegraph/amp@0.0.1764777697-g907e30")
-            .params(["--execute", "--stream-json"]);
+        let mut builder =
+            CommandBuilder::new(Self::base_command()).params(["--execute", "--stream-json"]);
         if self.dangerously_allow_all.unwrap_or(false) {
             builder = builder.extend_params(["--dangerously-allow-all"]);
         }
@@ -45,6 +49,10 @@ impl Amp {
 
 #[async_trait]
 impl StandardCodingAgentExecutor for Amp {
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -54,7 +62,9 @@ impl StandardCodingAgentExecutor for Amp {
         let command_parts = self.build_command_builder()?.build_initial()?;
         let (executable_path, args) = command_parts.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(executable_path);
         command
@@ -127,7 +137,9 @@ impl StandardCodingAgentExecutor for Amp {
         ])?;
         let (continue_program, continue_args) = continue_line.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(continue_program);
         command
@@ -162,6 +174,7 @@ impl StandardCodingAgentExecutor for Amp {
             current_dir,
             entry_index_provider.clone(),
             HistoryStrategy::AmpResume,
+            self.cmd.coalesce_deltas,
         );
 
         // Process stderr logs using the standard stderr processor
@@ -170,6 +183,8 @@ impl StandardCodingAgentExecutor for Amp {
 
     // MCP configuration methods
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".config").join("amp").join("settings.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".config").join("amp").join("settings.json"))
     }
 }
@@ -3,11 +3,18 @@ pub mod client;
 pub mod protocol;
 pub mod types;
 
-use std::{collections::HashMap, path::Path, process::Stdio, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::{Arc, LazyLock},
+    time::SystemTime,
+};
 
 use async_trait::async_trait;
 use command_group::AsyncCommandGroup;
 use futures::StreamExt;
+use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::process::Command;
@@ -24,22 +31,32 @@ use self::{
 };
 use crate::{
     approvals::ExecutorApprovalService,
-    command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
+    clock::{Clock, SystemClock},
+    command::{
+        CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, ResolvedCommand,
+        apply_overrides,
+    },
+    early_exit::compile_patterns,
     env::ExecutionEnv,
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
-        codex::client::LogWriter,
+        AppendPrompt, AvailabilityInfo, ExecutorError, MaxTurnsSupport, SpawnedChild,
+        StandardCodingAgentExecutor, codex::client::LogWriter, file_mtime_timestamp,
     },
     logs::{
-        ActionType, FileChange, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
-        TodoItem, ToolStatus,
-        stderr_processor::normalize_stderr_logs,
-        utils::{EntryIndexProvider, patch::ConversationPatch},
+        ActionType, AgentEvent, AgentPhase, DeltaTiming, FileChange, NormalizedEntry,
+        NormalizedEntryError, NormalizedEntryType, TodoItem, TokenUsage, ToolStatus,
+        stderr_processor::normalize_stderr_logs_with_detectors,
+        utils::{
+            CwdTracker, DeprecationDetector, EntryIndexProvider, LintSummary, McpFailureDetector,
+            PrettyJsonBuffer, RepeatedActionDetector, TestSummary, detect_merge_conflict,
+            parse_binary_output_marker, parse_unified_diffs, patch::ConversationPatch,
+            scratchpad_update,
+        },
     },
     stdout_dup::create_stdout_pipe_writer,
 };
 
-fn base_command(claude_code_router: bool) -> &'static str {
+pub(crate) fn base_command(claude_code_router: bool) -> &'static str {
     if claude_code_router {
         "npx -y @musistudio/claude-code-router@1.0.66 code"
     } else {
@@ -47,6 +64,24 @@ fn base_command(claude_code_router: bool) -> &'static str {
     }
 }
 
+/// Claude Code prints flag and setting deprecations as `Warning: ... deprecated ...`
+const CLAUDE_DEPRECATION_PATTERNS: &[&str] = &[r"(?i)^warning:\s*(?P<msg>.*\bdeprecated\b.*)$"];
+
+/// Claude Code tends to close a turn by offering follow-up work, e.g. "Want me to add tests?"
+pub const CLAUDE_QUESTION_PATTERNS: &[&str] =
+    &[r"(?i)^(?:want me to|shall i|should i go ahead)\b.*\?$"];
+
+/// Claude Code logs MCP connection errors as `MCP server "docs" Connection failed: ...`
+const CLAUDE_MCP_FAILURE_PATTERNS: &[&str] =
+    &[r#"MCP server "(?P<name>[^"]+)":? Connection failed:\s*(?P<error>.+)$"#];
+
+/// Claude Code reports a model outside the account's plan as an "issue with the selected model"
+pub static CLAUDE_ENTITLEMENT_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    compile_patterns(&[
+        r"(?i)issue with the selected model \((?P<model>[^)]+)\)\. It may not exist or you may not have access to it",
+    ])
+});
+
 use derivative::Derivative;
 
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
@@ -68,6 +103,14 @@ pub struct ClaudeCode {
     pub disable_api_key: Option<bool>,
     #[serde(flatten)]
     pub cmd: CmdOverrides,
+    /// Per-request turn limit passed through `--max-turns`
+    #[serde(skip)]
+    #[ts(skip)]
+    pub max_turns: Option<u32>,
+    /// Per-request MCP config passed through `--mcp-config`, replacing the user's servers
+    #[serde(skip)]
+    #[ts(skip)]
+    pub mcp_config_file: Option<PathBuf>,
 
     #[serde(skip)]
     #[ts(skip)]
@@ -90,6 +133,8 @@ impl ClaudeCode {
             dangerously_skip_permissions: None,
             disable_api_key: None,
             cmd,
+            max_turns: None,
+            mcp_config_file: None,
             approvals_service: None,
         }
     }
@@ -125,6 +170,16 @@ impl ClaudeCode {
         if let Some(model) = &self.model {
             builder = builder.extend_params(["--model", model]);
         }
+        if let Some(max_turns) = self.max_turns {
+            builder = builder.extend_params(["--max-turns".to_string(), max_turns.to_string()]);
+        }
+        if let Some(mcp_config_file) = &self.mcp_config_file {
+            builder = builder.extend_params([
+                "--mcp-config".to_string(),
+                mcp_config_file.to_string_lossy().to_string(),
+                "--strict-mcp-config".to_string(),
+            ]);
+        }
         builder = builder.extend_params([
             "--verbose",
             "--output-format=stream-json",
@@ -181,6 +236,29 @@ impl StandardCodingAgentExecutor for ClaudeCode {
         self.approvals_service = Some(approvals);
     }
 
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
+    fn apply_max_turns(&mut self, max_turns: u32) -> MaxTurnsSupport {
+        self.max_turns = Some(max_turns);
+        MaxTurnsSupport::Native
+    }
+
+    fn apply_plan_only(&mut self) -> bool {
+        self.plan = Some(true);
+        true
+    }
+
+    fn apply_mcp_config_file(&mut self, path: &Path) -> bool {
+        self.mcp_config_file = Some(path.to_path_buf());
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -210,6 +288,15 @@ impl StandardCodingAgentExecutor for ClaudeCode {
             .await
     }
 
+    async fn resolve_command(
+        &self,
+        current_dir: &Path,
+        _env: &ExecutionEnv,
+    ) -> Result<ResolvedCommand, ExecutorError> {
+        let command_parts = self.build_command_builder().await?.build_initial()?;
+        Ok(ResolvedCommand::new(command_parts, &self.cmd, current_dir))
+    }
+
     fn normalize_logs(&self, msg_store: Arc<MsgStore>, current_dir: &Path) {
         let entry_index_provider = EntryIndexProvider::start_from(&msg_store);
 
@@ -219,29 +306,32 @@ impl StandardCodingAgentExecutor for ClaudeCode {
             current_dir,
             entry_index_provider.clone(),
             HistoryStrategy::Default,
+            self.cmd.coalesce_deltas,
         );
 
         // Process stderr logs using the standard stderr processor
-        normalize_stderr_logs(msg_store, entry_index_provider);
+        normalize_stderr_logs_with_detectors(
+            msg_store,
+            entry_index_provider,
+            DeprecationDetector::new().with_patterns(CLAUDE_DEPRECATION_PATTERNS),
+            McpFailureDetector::new().with_patterns(CLAUDE_MCP_FAILURE_PATTERNS),
+        );
     }
 
     // MCP configuration methods
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".claude.json"))
+        self.cmd.home_dir().map(|home| home.join(".claude.json"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
-        let auth_file_path = dirs::home_dir().map(|home| home.join(".claude.json"));
+        let auth_file_path = self.cmd.home_dir().map(|home| home.join(".claude.json"));
 
         if let Some(path) = auth_file_path
-            && let Some(timestamp) = std::fs::metadata(&path)
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs() as i64)
+            && let Some(timestamp) = file_mtime_timestamp(&path)
         {
             return AvailabilityInfo::LoginDetected {
                 last_auth_timestamp: timestamp,
+                expires_at: None,
             };
         }
         AvailabilityInfo::NotFound
@@ -257,7 +347,9 @@ impl ClaudeCode {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let (program_path, args) = command_parts.into_resolved().await?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(program_path);
         command
@@ -329,6 +421,8 @@ impl ClaudeCode {
             child,
             exit_signal: None,
             interrupt_sender: Some(interrupt_tx),
+            events: Vec::new(),
+            spawn_permit: None,
         })
     }
 }
@@ -350,6 +444,16 @@ pub struct ClaudeLogProcessor {
     strategy: HistoryStrategy,
     streaming_messages: HashMap<String, StreamingMessageState>,
     streaming_message_id: Option<String>,
+    // Buffer streamed deltas and only update entries at content block and message boundaries
+    coalesce_deltas: bool,
+    // Effective working directory, followed through `cd` in shell commands
+    cwd: CwdTracker,
+    // Plan/act phase, driven by the init permission mode and ExitPlanMode approvals
+    phase: Option<AgentPhase>,
+    // Streaks of identical tool calls
+    repeated_tool_calls: RepeatedActionDetector,
+    // Read time of stream events, for the gaps between uncoalesced deltas
+    clock: Arc<dyn Clock>,
 }
 
 impl ClaudeLogProcessor {
@@ -364,16 +468,33 @@ impl ClaudeLogProcessor {
             tool_map: HashMap::new(),
             strategy,
             streaming_messages: HashMap::new(),
+            coalesce_deltas: false,
             streaming_message_id: None,
+            cwd: CwdTracker::new(),
+            phase: None,
+            repeated_tool_calls: RepeatedActionDetector::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Record the current phase, returning an event when it changed. Executing is the implicit
+    /// starting phase, so it is only announced when leaving Planning.
+    fn observe_phase(&mut self, phase: AgentPhase) -> Option<AgentEvent> {
+        let changed = match self.phase {
+            Some(current) => current != phase,
+            None => phase == AgentPhase::Planning,
+        };
+        self.phase = Some(phase);
+        changed.then_some(AgentEvent::PhaseChanged { phase })
+    }
+
     /// Process raw logs and convert them to normalized entries with patches
     pub fn process_logs(
         msg_store: Arc<MsgStore>,
         current_dir: &Path,
         entry_index_provider: EntryIndexProvider,
         strategy: HistoryStrategy,
+        coalesce_deltas: bool,
     ) {
         let current_dir_clone = current_dir.to_owned();
         tokio::spawn(async move {
@@ -382,6 +503,8 @@ impl ClaudeLogProcessor {
             let worktree_path = current_dir_clone.to_string_lossy().to_string();
             let mut session_id_extracted = false;
             let mut processor = Self::new_with_strategy(strategy);
+            processor.coalesce_deltas = coalesce_deltas;
+            let mut pretty_json = PrettyJsonBuffer::new();
 
             while let Some(Ok(msg)) = stream.next().await {
                 let chunk = match msg {
@@ -415,7 +538,32 @@ impl ClaudeLogProcessor {
                         continue;
                     }
 
-                    match serde_json::from_str::<ClaudeJson>(trimmed) {
+                    // Pretty-printed JSON blobs span several lines; parse them once complete
+                    if let Some(partial) = pretty_json.interrupted_by(trimmed) {
+                        let entry = NormalizedEntry {
+                            timestamp: None,
+                            entry_type: NormalizedEntryType::SystemMessage,
+                            content: partial.trim().to_string(),
+                            metadata: None,
+                        };
+                        msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                            entry_index_provider.next(),
+                            entry,
+                        ));
+                    }
+                    let candidate = if pretty_json.is_collecting() {
+                        match pretty_json.push_line(trimmed) {
+                            Some(blob) => blob,
+                            None => continue,
+                        }
+                    } else if PrettyJsonBuffer::starts_blob(trimmed) {
+                        pretty_json.push_line(trimmed);
+                        continue;
+                    } else {
+                        trimmed.to_string()
+                    };
+
+                    match serde_json::from_str::<ClaudeJson>(&candidate) {
                         Ok(claude_json) => {
                             // Extract session ID if present
                             if !session_id_extracted
@@ -435,12 +583,17 @@ impl ClaudeLogProcessor {
                             }
                         }
                         Err(_) => {
+                            // `detect_binary_output` reports marker lines for every client
+                            if parse_binary_output_marker(&candidate).is_some() {
+                                continue;
+                            }
+
                             // Handle non-JSON output as raw system message
-                            if !trimmed.is_empty() {
+                            if !candidate.trim().is_empty() {
                                 let entry = NormalizedEntry {
                                     timestamp: None,
                                     entry_type: NormalizedEntryType::SystemMessage,
-                                    content: trimmed.to_string(),
+                                    content: candidate.trim().to_string(),
                                     metadata: None,
                                 };
 
@@ -457,6 +610,11 @@ impl ClaudeLogProcessor {
                 buffer = buffer.rsplit('\n').next().unwrap_or("").to_owned();
             }
 
+            // Surface an unterminated pretty-printed blob as-is
+            if pretty_json.is_collecting() {
+                buffer = pretty_json.take() + &buffer;
+            }
+
             // Handle any remaining content in buffer
             if !buffer.trim().is_empty() {
                 let entry = NormalizedEntry {
@@ -772,8 +930,41 @@ impl ClaudeLogProcessor {
             ClaudeJson::System {
                 subtype,
                 api_key_source,
+                permission_mode,
+                mcp_servers,
                 ..
             } => {
+                for server in mcp_servers.iter().flatten() {
+                    if server.status == "failed" {
+                        let event = AgentEvent::McpServerFailed {
+                            name: server.name.clone(),
+                            error: "failed to connect".to_string(),
+                        };
+                        let idx = entry_index_provider.next();
+                        patches.push(ConversationPatch::add_normalized_entry(
+                            idx,
+                            event.into_normalized_entry(),
+                        ));
+                    }
+                }
+
+                if subtype.as_deref() == Some("init")
+                    && let Some(mode) = permission_mode.as_deref()
+                {
+                    let phase = if mode == "plan" {
+                        AgentPhase::Planning
+                    } else {
+                        AgentPhase::Executing
+                    };
+                    if let Some(event) = self.observe_phase(phase) {
+                        let idx = entry_index_provider.next();
+                        patches.push(ConversationPatch::add_normalized_entry(
+                            idx,
+                            event.into_normalized_entry(),
+                        ));
+                    }
+                }
+
                 // emit billing warning if required
                 if let Some(warning) = Self::warn_if_unmanaged_key(api_key_source) {
                     let idx = entry_index_provider.next();
@@ -832,12 +1023,43 @@ impl ClaudeLogProcessor {
                     match item {
                         ClaudeContentItem::ToolUse { id, tool_data } => {
                             let tool_name = tool_data.get_name().to_string();
-                            let action_type = Self::extract_action_type(tool_data, worktree_path);
+                            let mut action_type =
+                                Self::extract_action_type(tool_data, worktree_path);
+                            if let Some(raw_path) = tool_data.file_path()
+                                && Path::new(raw_path).is_relative()
+                                && let ActionType::FileRead { path }
+                                | ActionType::FileEdit { path, .. } = &mut action_type
+                            {
+                                *path = self.cwd.resolve(raw_path);
+                            }
+                            let cwd_change = match tool_data {
+                                ClaudeToolData::Bash { command, .. }
+                                    if !self.tool_map.contains_key(id) =>
+                                {
+                                    self.cwd.observe_command(command, worktree_path)
+                                }
+                                _ => None,
+                            };
                             let content_text = Self::generate_concise_content(
                                 tool_data,
                                 &action_type,
                                 worktree_path,
                             );
+                            let repeated = if self.tool_map.contains_key(id) {
+                                None
+                            } else {
+                                let fingerprint = format!(
+                                    "{tool_name} {}",
+                                    serde_json::to_string(tool_data).unwrap_or_default()
+                                );
+                                self.repeated_tool_calls
+                                    .observe(&format!("{tool_name}: {content_text}"), &fingerprint)
+                            };
+                            let scratchpad = if self.tool_map.contains_key(id) {
+                                None
+                            } else {
+                                scratchpad_update(&action_type)
+                            };
 
                             // Create metadata with tool_call_id for approval matching
                             let mut metadata =
@@ -876,6 +1098,20 @@ impl ClaudeLogProcessor {
                                 ConversationPatch::replace(id_num, entry)
                             };
                             patches.push(patch);
+
+                            if let Some(path) = cwd_change {
+                                let entry = AgentEvent::CwdChanged { path }.into_normalized_entry();
+                                let idx = entry_index_provider.next();
+                                patches.push(ConversationPatch::add_normalized_entry(idx, entry));
+                            }
+
+                            for event in repeated.into_iter().chain(scratchpad) {
+                                let idx = entry_index_provider.next();
+                                patches.push(ConversationPatch::add_normalized_entry(
+                                    idx,
+                                    event.into_normalized_entry(),
+                                ));
+                            }
                         }
                         ClaudeContentItem::Text { .. } | ClaudeContentItem::Thinking { .. } => {
                             if let Some(entry) = Self::content_item_to_normalized_entry(
@@ -893,6 +1129,16 @@ impl ClaudeLogProcessor {
                                 };
                                 patches.push(patch);
                             }
+
+                            if let ClaudeContentItem::Text { text } = item {
+                                for diff in parse_unified_diffs(text) {
+                                    let idx = entry_index_provider.next();
+                                    patches.push(ConversationPatch::add_normalized_entry(
+                                        idx,
+                                        diff.into_event().into_normalized_entry(),
+                                    ));
+                                }
+                            }
                         }
                         ClaudeContentItem::ToolResult { .. } => {}
                     }
@@ -962,6 +1208,10 @@ impl ClaudeLogProcessor {
                             } else {
                                 content.to_string()
                             };
+                            let test_summary = TestSummary::parse(&content_str);
+                            let lint_summaries = LintSummary::parse(&content_str);
+                            let diffs = parse_unified_diffs(&content_str);
+                            let conflict = detect_merge_conflict(&content_str);
 
                             let result = if let Ok(result) =
                                 serde_json::from_str::<AmpBashResult>(&content_str)
@@ -1003,6 +1253,24 @@ impl ClaudeLogProcessor {
                                 metadata: None,
                             };
                             patches.push(ConversationPatch::replace(info.entry_index, entry));
+
+                            let events = diffs
+                                .into_iter()
+                                .map(|diff| diff.into_event())
+                                .chain(test_summary.map(|summary| summary.into_event()))
+                                .chain(
+                                    lint_summaries
+                                        .into_iter()
+                                        .map(|summary| summary.into_event()),
+                                )
+                                .chain(conflict);
+                            for event in events {
+                                let idx = entry_index_provider.next();
+                                patches.push(ConversationPatch::add_normalized_entry(
+                                    idx,
+                                    event.into_normalized_entry(),
+                                ));
+                            }
                         } else if matches!(
                             info.tool_data,
                             ClaudeToolData::Unknown { .. }
@@ -1098,7 +1366,7 @@ impl ClaudeLogProcessor {
                         if let Some(message_id) = message.id.clone() {
                             self.streaming_messages.insert(
                                 message_id.clone(),
-                                StreamingMessageState::new(message.role.clone()),
+                                StreamingMessageState::new(message.role.clone(), self.clock.now()),
                             );
                             self.streaming_message_id = Some(message_id);
                         } else {
@@ -1121,6 +1389,8 @@ impl ClaudeLogProcessor {
                     }
                 }
                 ClaudeStreamEvent::ContentBlockDelta { index, delta } => {
+                    let coalesce = self.coalesce_deltas;
+                    let read_at = self.clock.now();
                     if let Some(state) = self
                         .streaming_message_id
                         .as_ref()
@@ -1128,6 +1398,23 @@ impl ClaudeLogProcessor {
                         && let Some(patch) = state.apply_content_block_delta(
                             *index,
                             delta,
+                            coalesce,
+                            read_at,
+                            worktree_path,
+                            entry_index_provider,
+                        )
+                    {
+                        patches.push(patch);
+                    }
+                }
+                ClaudeStreamEvent::ContentBlockStop { index } => {
+                    if let Some(state) = self
+                        .streaming_message_id
+                        .as_ref()
+                        .and_then(|id| self.streaming_messages.get_mut(id))
+                        && let Some(patch) = state.flush_content_block(
+                            *index,
+                            None,
                             worktree_path,
                             entry_index_provider,
                         )
@@ -1135,16 +1422,38 @@ impl ClaudeLogProcessor {
                         patches.push(patch);
                     }
                 }
-                ClaudeStreamEvent::ContentBlockStop { .. } => {}
                 ClaudeStreamEvent::MessageDelta { .. } => {}
                 ClaudeStreamEvent::MessageStop => {
-                    if let Some(message_id) = self.streaming_message_id.take() {
-                        let _ = self.streaming_messages.remove(&message_id);
+                    if let Some(message_id) = self.streaming_message_id.take()
+                        && let Some(mut state) = self.streaming_messages.remove(&message_id)
+                    {
+                        patches.extend(state.flush(worktree_path, entry_index_provider));
                     }
                 }
                 ClaudeStreamEvent::Unknown => {}
             },
-            ClaudeJson::Result { is_error, .. } => {
+            ClaudeJson::Result {
+                is_error,
+                usage,
+                total_cost_usd,
+                ..
+            } => {
+                if usage.is_some() || total_cost_usd.is_some() {
+                    let usage = usage
+                        .as_ref()
+                        .map(ClaudeUsage::token_usage)
+                        .unwrap_or_default();
+                    let event = AgentEvent::Usage {
+                        usage,
+                        cost_usd: *total_cost_usd,
+                    };
+                    let idx = entry_index_provider.next();
+                    patches.push(ConversationPatch::add_normalized_entry(
+                        idx,
+                        event.into_normalized_entry(),
+                    ));
+                }
+
                 if matches!(self.strategy, HistoryStrategy::AmpResume) && is_error.unwrap_or(false)
                 {
                     let entry = NormalizedEntry {
@@ -1197,6 +1506,18 @@ impl ClaudeLogProcessor {
                     let idx = entry_index_provider.next();
                     patches.push(ConversationPatch::add_normalized_entry(idx, entry));
                 }
+
+                // An approved plan is the hand-off from planning to execution
+                if tool_name == "ExitPlanMode"
+                    && matches!(approval_status, ApprovalStatus::Approved)
+                    && let Some(event) = self.observe_phase(AgentPhase::Executing)
+                {
+                    let idx = entry_index_provider.next();
+                    patches.push(ConversationPatch::add_normalized_entry(
+                        idx,
+                        event.into_normalized_entry(),
+                    ));
+                }
             }
             ClaudeJson::Unknown { data } => {
                 let entry = NormalizedEntry {
@@ -1336,13 +1657,16 @@ fn extract_model_name(
 struct StreamingMessageState {
     role: String,
     contents: HashMap<usize, StreamingContentState>,
+    // When the message started or its latest delta was read
+    last_read_at: SystemTime,
 }
 
 impl StreamingMessageState {
-    fn new(role: String) -> Self {
+    fn new(role: String, started_at: SystemTime) -> Self {
         Self {
             role,
             contents: HashMap::new(),
+            last_read_at: started_at,
         }
     }
 
@@ -1352,10 +1676,13 @@ impl StreamingMessageState {
         }
     }
 
+    /// Buffer `delta`; unless `coalesce` is set, also return the updated entry right away.
     fn apply_content_block_delta(
         &mut self,
         index: usize,
         delta: &ClaudeContentBlockDelta,
+        coalesce: bool,
+        read_at: SystemTime,
         worktree_path: &str,
         entry_index_provider: &EntryIndexProvider,
     ) -> Option<json_patch::Patch> {
@@ -1366,13 +1693,39 @@ impl StreamingMessageState {
 
         let entry_state = self.contents.get_mut(&index)?;
         entry_state.apply_content_delta(delta);
+        entry_state.pending = true;
+        let timing = DeltaTiming::between(self.last_read_at, read_at);
+        self.last_read_at = read_at;
+        if coalesce {
+            return None;
+        }
+        self.flush_content_block(index, Some(timing), worktree_path, entry_index_provider)
+    }
+
+    /// Add or replace the entry for content block `index` if it has deltas not yet emitted,
+    /// recording `timing` on it when the flush is for a single delta.
+    fn flush_content_block(
+        &mut self,
+        index: usize,
+        timing: Option<DeltaTiming>,
+        worktree_path: &str,
+        entry_index_provider: &EntryIndexProvider,
+    ) -> Option<json_patch::Patch> {
+        let entry_state = self
+            .contents
+            .get_mut(&index)
+            .filter(|state| state.pending)?;
+        entry_state.pending = false;
 
         let content_item = entry_state.to_content_item();
-        let entry = ClaudeLogProcessor::content_item_to_normalized_entry(
+        let mut entry = ClaudeLogProcessor::content_item_to_normalized_entry(
             &content_item,
             &self.role,
             worktree_path,
         )?;
+        if let Some(timing) = timing {
+            timing.attach(&mut entry);
+        }
 
         if let Some(existing_index) = entry_state.entry_index {
             Some(ConversationPatch::replace(existing_index, entry))
@@ -1383,6 +1736,22 @@ impl StreamingMessageState {
         }
     }
 
+    /// Flush every content block with pending deltas, in block order.
+    fn flush(
+        &mut self,
+        worktree_path: &str,
+        entry_index_provider: &EntryIndexProvider,
+    ) -> Vec<json_patch::Patch> {
+        let mut indices: Vec<usize> = self.contents.keys().copied().collect();
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .filter_map(|index| {
+                self.flush_content_block(index, None, worktree_path, entry_index_provider)
+            })
+            .collect()
+    }
+
     fn content_entry_index(&self, content_index: usize) -> Option<usize> {
         self.contents
             .get(&content_index)
@@ -1400,6 +1769,8 @@ struct StreamingContentState {
     kind: StreamingContentKind,
     buffer: String,
     entry_index: Option<usize>,
+    // Buffer holds deltas not yet reflected in the entry
+    pending: bool,
 }
 
 impl StreamingContentState {
@@ -1409,11 +1780,13 @@ impl StreamingContentState {
                 kind: StreamingContentKind::Text,
                 buffer: text,
                 entry_index: None,
+                pending: false,
             }),
             ClaudeContentItem::Thinking { thinking } => Some(Self {
                 kind: StreamingContentKind::Thinking,
                 buffer: thinking,
                 entry_index: None,
+                pending: false,
             }),
             _ => None,
         }
@@ -1425,11 +1798,13 @@ impl StreamingContentState {
                 kind: StreamingContentKind::Text,
                 buffer: String::new(),
                 entry_index: None,
+                pending: false,
             }),
             ClaudeContentBlockDelta::ThinkingDelta { .. } => Some(Self {
                 kind: StreamingContentKind::Thinking,
                 buffer: String::new(),
                 entry_index: None,
+                pending: false,
             }),
             _ => None,
         }
@@ -1480,6 +1855,10 @@ pub enum ClaudeJson {
         model: Option<String>,
         #[serde(default, rename = "apiKeySource")]
         api_key_source: Option<String>,
+        #[serde(default, rename = "permissionMode")]
+        permission_mode: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        mcp_servers: Option<Vec<ClaudeMcpServerStatus>>,
     },
     Assistant {
         message: ClaudeMessage,
@@ -1524,6 +1903,10 @@ pub enum ClaudeJson {
         num_turns: Option<u32>,
         #[serde(default, alias = "sessionId")]
         session_id: Option<String>,
+        #[serde(default)]
+        usage: Option<ClaudeUsage>,
+        #[serde(default, alias = "totalCostUsd")]
+        total_cost_usd: Option<f64>,
     },
     ApprovalResponse {
         call_id: String,
@@ -1643,6 +2026,20 @@ pub struct ClaudeUsage {
     pub service_tier: Option<String>,
 }
 
+impl ClaudeUsage {
+    fn token_usage(&self) -> TokenUsage {
+        let input = [
+            self.input_tokens,
+            self.cache_creation_input_tokens,
+            self.cache_read_input_tokens,
+        ];
+        TokenUsage {
+            input_tokens: input.into_iter().flatten().sum(),
+            output_tokens: self.output_tokens.unwrap_or_default(),
+        }
+    }
+}
+
 /// Structured tool data for Claude tools based on real samples
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 #[serde(tag = "name", content = "input")]
@@ -1777,6 +2174,13 @@ pub enum ClaudeToolData {
     },
 }
 
+/// Connection state of a configured MCP server, reported in the init message
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ClaudeMcpServerStatus {
+    pub name: String,
+    pub status: String,
+}
+
 // Helper structs for parsing tool_result content and generic tool input
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 struct ClaudeToolResultTextItem {
@@ -1825,6 +2229,17 @@ pub struct ClaudeEditItem {
 }
 
 impl ClaudeToolData {
+    /// Raw file path for tools that operate on a single file
+    pub fn file_path(&self) -> Option<&str> {
+        match self {
+            ClaudeToolData::Read { file_path }
+            | ClaudeToolData::Edit { file_path, .. }
+            | ClaudeToolData::MultiEdit { file_path, .. }
+            | ClaudeToolData::Write { file_path, .. } => Some(file_path),
+            _ => None,
+        }
+    }
+
     pub fn get_name(&self) -> &str {
         match self {
             ClaudeToolData::TodoWrite { .. } => "TodoWrite",
@@ -1881,6 +2296,38 @@ mod tests {
         normalize_helper(&mut processor, json, worktree)
     }
 
+    /// Normalize `messages` into a store with the shared entry events installed, as the container
+    /// does.
+    fn claude_store(messages: &[&str], worktree: &str) -> workspace_utils::msg_store::MsgStore {
+        let store = workspace_utils::msg_store::MsgStore::new();
+        crate::logs::utils::detect_entry_events(&store, std::path::Path::new(worktree));
+        let provider = EntryIndexProvider::start_from(&store);
+        let mut processor = ClaudeLogProcessor::new();
+        for message in messages {
+            let json: ClaudeJson = serde_json::from_str(message).unwrap();
+            for patch in processor.normalize_entries(&json, worktree, &provider) {
+                store.push_patch(patch);
+            }
+        }
+        store
+    }
+
+    /// The entries [`claude_store`] stores for `messages`, in order.
+    fn normalize_into_store(messages: &[&str], worktree: &str) -> Vec<NormalizedEntry> {
+        let store = claude_store(messages, worktree);
+        let entries: std::collections::BTreeMap<usize, NormalizedEntry> = store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                workspace_utils::log_msg::LogMsg::JsonPatch(patch) => {
+                    extract_normalized_entry_from_patch(patch)
+                }
+                _ => None,
+            })
+            .collect();
+        entries.into_values().collect()
+    }
+
     #[test]
     fn test_claude_json_parsing() {
         let system_json =
@@ -2047,7 +2494,22 @@ mod tests {
                 base_command_override: None,
                 additional_params: None,
                 env: None,
+                normalize_newlines: true,
+                isolated_home: None,
+                min_free_bytes: None,
+                spill_to_disk: None,
+                record_dir: None,
+                replay_dir: None,
+                coalesce_deltas: true,
+                fallback_model: None,
+                checkpoint_interval_secs: None,
+                max_session_turns: None,
+                dedup_identical_prompts: false,
+                max_wallclock_secs: None,
+                block_prompt_secrets: false,
             },
+            max_turns: None,
+            mcp_config_file: None,
             approvals_service: None,
             disable_api_key: None,
         };
@@ -2371,4 +2833,627 @@ mod tests {
 
         // ToolResult entry is ignored - no third entry
     }
+
+    #[tokio::test]
+    async fn test_multi_line_pretty_json_parsed_once() {
+        use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+        let msg_store = Arc::new(MsgStore::new());
+        msg_store.push_stdout(
+            r#"{
+  "type": "assistant",
+  "message": {
+    "role": "assistant",
+    "content": [
+      {
+        "type": "text",
+        "text": "Pretty {braces} inside"
+      }
+    ]
+  }
+}
+"#
+            .to_string(),
+        );
+        msg_store.push_finished();
+
+        ClaudeLogProcessor::process_logs(
+            msg_store.clone(),
+            Path::new("/tmp/test-worktree"),
+            EntryIndexProvider::test_new(),
+            HistoryStrategy::Default,
+            true,
+        );
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let entries: Vec<NormalizedEntry> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .map(|(_, entry)| entry)
+            .collect();
+
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            entries[0].entry_type,
+            NormalizedEntryType::AssistantMessage
+        ));
+        assert_eq!(entries[0].content, "Pretty {braces} inside");
+    }
+
+    #[tokio::test]
+    async fn test_truncated_line_does_not_swallow_following_events() {
+        use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+        let msg_store = Arc::new(MsgStore::new());
+        msg_store.push_stdout(
+            r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"te
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"First"}]}}
+{
+  "type": "assistant",
+{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"Second"}]}}
+"#
+            .to_string(),
+        );
+        msg_store.push_finished();
+
+        ClaudeLogProcessor::process_logs(
+            msg_store.clone(),
+            Path::new("/tmp/test-worktree"),
+            EntryIndexProvider::test_new(),
+            HistoryStrategy::Default,
+            true,
+        );
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let entries: Vec<NormalizedEntry> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .map(|(_, entry)| entry)
+            .collect();
+
+        assert_eq!(entries.len(), 4);
+        assert!(matches!(
+            entries[0].entry_type,
+            NormalizedEntryType::SystemMessage
+        ));
+        assert_eq!(entries[1].content, "First");
+        assert!(matches!(
+            entries[2].entry_type,
+            NormalizedEntryType::SystemMessage
+        ));
+        assert_eq!(entries[2].content, "{\n  \"type\": \"assistant\",");
+        assert_eq!(entries[3].content, "Second");
+    }
+
+    #[tokio::test]
+    async fn test_binary_stdout_reported_as_single_event() {
+        use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+        use crate::logs::utils::{BinaryOutputDetector, detect_binary_output};
+
+        let mut raw: Vec<u8> = (0..2048u32).map(|i| (i * 7919 % 251) as u8).collect();
+        raw.push(0);
+        let mut detector = BinaryOutputDetector::new();
+        let msg_store = Arc::new(MsgStore::new());
+        for chunk in raw.chunks(64) {
+            msg_store.push_stdout(detector.push(chunk));
+        }
+        msg_store.push_stdout(detector.finish());
+        msg_store.push_finished();
+
+        detect_binary_output(msg_store.clone());
+        ClaudeLogProcessor::process_logs(
+            msg_store.clone(),
+            Path::new("/tmp/test-worktree"),
+            EntryIndexProvider::start_from(&msg_store),
+            HistoryStrategy::Default,
+            true,
+        );
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let entries: Vec<NormalizedEntry> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .map(|(_, entry)| entry)
+            .collect();
+
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].entry_type,
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::BinaryOutput { bytes: 2049 }
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_max_turns_native_flag() {
+        let mut executor = ClaudeCode::new_with_overrides(
+            AppendPrompt::default(),
+            None,
+            crate::command::CmdOverrides::default(),
+        );
+        assert!(matches!(
+            executor.apply_max_turns(3),
+            MaxTurnsSupport::Native
+        ));
+
+        let builder = executor.build_command_builder().await.unwrap();
+        let params = builder.params.unwrap();
+        let flag = params.iter().position(|p| p == "--max-turns").unwrap();
+        assert_eq!(params[flag + 1], "3");
+    }
+
+    #[tokio::test]
+    async fn test_plan_only_uses_plan_permission_mode() {
+        let mut executor = ClaudeCode::new_with_overrides(
+            AppendPrompt::default(),
+            None,
+            crate::command::CmdOverrides::default(),
+        );
+        assert!(executor.apply_plan_only());
+        assert!(matches!(executor.permission_mode(), PermissionMode::Plan));
+
+        let params = executor
+            .build_command_builder()
+            .await
+            .unwrap()
+            .params
+            .unwrap();
+        assert!(params.contains(&"--permission-prompt-tool=stdio".to_string()));
+    }
+
+    #[test]
+    fn test_cd_then_relative_file_edit() {
+        let mut processor = ClaudeLogProcessor::new();
+
+        let bash_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cd crates/server && ls"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(bash_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert_eq!(entries.len(), 2);
+        match &entries[1].entry_type {
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::CwdChanged { path },
+            } => assert_eq!(path, "crates/server"),
+            other => panic!("Expected CwdChanged, got {other:?}"),
+        }
+
+        let edit_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"t2","name":"Edit","input":{"file_path":"src/main.rs","old_string":"a","new_string":"b"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(edit_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert_eq!(entries.len(), 1);
+        match &entries[0].entry_type {
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileEdit { path, .. },
+                ..
+            } => assert_eq!(path, "crates/server/src/main.rs"),
+            other => panic!("Expected FileEdit, got {other:?}"),
+        }
+
+        // Absolute paths are unaffected by the tracked directory
+        let abs_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"t3","name":"Read","input":{"file_path":"/tmp/work/README.md"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(abs_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        match &entries[0].entry_type {
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileRead { path },
+                ..
+            } => assert_eq!(path, "README.md"),
+            other => panic!("Expected FileRead, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_plan_mode_phase_transitions() {
+        let mut processor = ClaudeLogProcessor::new();
+        let phase_events = |entries: Vec<NormalizedEntry>| -> Vec<AgentPhase> {
+            entries
+                .into_iter()
+                .filter_map(|entry| match entry.entry_type {
+                    NormalizedEntryType::AgentEvent {
+                        event: AgentEvent::PhaseChanged { phase },
+                    } => Some(phase),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let init_json = r#"{"type":"system","subtype":"init","session_id":"s1","cwd":"/tmp/work","tools":[],"model":"claude-sonnet-4","permissionMode":"plan"}"#;
+        let parsed: ClaudeJson = serde_json::from_str(init_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert_eq!(phase_events(entries), vec![AgentPhase::Planning]);
+
+        let plan_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"p1","name":"ExitPlanMode","input":{"plan":"1. Do it"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(plan_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert!(phase_events(entries).is_empty());
+
+        let approval_json = r#"{"type":"approval_response","call_id":"p1","tool_name":"ExitPlanMode","approval_status":{"status":"approved"}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(approval_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].content, "Phase changed to Executing");
+        assert_eq!(phase_events(entries), vec![AgentPhase::Executing]);
+    }
+
+    #[test]
+    fn test_non_plan_session_emits_no_phase_events() {
+        let mut processor = ClaudeLogProcessor::new();
+        let init_json = r#"{"type":"system","subtype":"init","session_id":"s1","permissionMode":"bypassPermissions"}"#;
+        let parsed: ClaudeJson = serde_json::from_str(init_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert!(entries.is_empty());
+
+        let denied_json = r#"{"type":"approval_response","call_id":"p1","tool_name":"ExitPlanMode","approval_status":{"status":"denied","reason":"not yet"}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(denied_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        assert!(
+            entries
+                .iter()
+                .all(|entry| !matches!(entry.entry_type, NormalizedEntryType::AgentEvent { .. }))
+        );
+    }
+
+    #[test]
+    fn test_claude_deprecation_notice_reported_once() {
+        let mut detector = DeprecationDetector::new().with_patterns(CLAUDE_DEPRECATION_PATTERNS);
+        let chunk = "Warning: --output-format=stream-json without --verbose is deprecated\n";
+
+        assert_eq!(
+            detector.process_chunk(chunk),
+            vec!["--output-format=stream-json without --verbose is deprecated".to_string()]
+        );
+        assert!(detector.process_chunk(chunk).is_empty());
+    }
+
+    #[test]
+    fn test_proposed_command_correlates_with_tool_call() {
+        let text_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"text","text":"Let me check the tests.\nAbout to run: `cargo test -p executors`"}
+        ]}}"#;
+        let bash_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"cargo test -p executors"}}
+        ]}}"#;
+        let entries = normalize_into_store(&[text_json, bash_json], "/tmp/work");
+
+        let proposals: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| match &entry.entry_type {
+                NormalizedEntryType::AgentEvent {
+                    event: event @ AgentEvent::ProposedCommand { .. },
+                } => Some(event),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            proposals,
+            vec![&AgentEvent::ProposedCommand {
+                command: "cargo test -p executors".to_string(),
+                tool_call_id: Some("toolu_1".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_result_usage_is_reported() {
+        let mut processor = ClaudeLogProcessor::new();
+        let result: ClaudeJson = serde_json::from_str(
+            r#"{"type":"result","subtype":"success","is_error":false,"total_cost_usd":0.0421,
+                "usage":{"input_tokens":12,"cache_read_input_tokens":3000,"output_tokens":450}}"#,
+        )
+        .unwrap();
+
+        let entries = normalize_helper(&mut processor, &result, "/tmp/work");
+        assert_eq!(entries.len(), 1);
+        match &entries[0].entry_type {
+            NormalizedEntryType::AgentEvent { event } => assert_eq!(
+                event,
+                &AgentEvent::Usage {
+                    usage: TokenUsage {
+                        input_tokens: 3012,
+                        output_tokens: 450,
+                    },
+                    cost_usd: Some(0.0421),
+                }
+            ),
+            other => panic!("Expected Usage, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_question_pending_on_turn_end() {
+        let detector = crate::logs::utils::TurnEndDetector::new(
+            crate::logs::utils::QuestionDetector::new().with_patterns(CLAUDE_QUESTION_PATTERNS),
+        );
+        let text_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"text","text":"I've added the migration.\n\nWant me to add tests for it?"}
+        ]}}"#;
+        let result_json = r#"{"type":"result","subtype":"success","is_error":false}"#;
+
+        let store = claude_store(&[text_json, result_json], "/tmp/work");
+        assert_eq!(
+            detector.scan(&store),
+            vec![AgentEvent::QuestionPending {
+                question: "Want me to add tests for it?".to_string(),
+            }]
+        );
+
+        // A tool call after the question means the agent moved on without waiting.
+        let tool_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"toolu_2","name":"Read","input":{"file_path":"README.md"}}
+        ]}}"#;
+        let store = claude_store(&[text_json, tool_json, result_json], "/tmp/work");
+        assert!(detector.scan(&store).is_empty());
+    }
+
+    #[test]
+    fn test_test_results_from_bash_output() {
+        let mut processor = ClaudeLogProcessor::new();
+
+        let bash_json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"toolu_3","name":"Bash","input":{"command":"cargo test"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(bash_json).unwrap();
+        normalize_helper(&mut processor, &parsed, "/tmp/work");
+
+        let result_json = r#"{"type":"user","message":{"role":"user","content":[
+            {"type":"tool_result","tool_use_id":"toolu_3","is_error":false,
+             "content":"running 4 tests\ntest result: ok. 3 passed; 0 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.02s"}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(result_json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+        let event = entries.iter().find_map(|entry| match &entry.entry_type {
+            NormalizedEntryType::AgentEvent { event } => Some(event.clone()),
+            _ => None,
+        });
+        assert_eq!(
+            event,
+            Some(AgentEvent::TestResults {
+                passed: Some(3),
+                failed: Some(0),
+                skipped: Some(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_failed_mcp_server_in_init() {
+        let init_json = r#"{"type":"system","subtype":"init","session_id":"abc123",
+            "mcp_servers":[{"name":"docs","status":"connected"},{"name":"playwright","status":"failed"}]}"#;
+        let parsed: ClaudeJson = serde_json::from_str(init_json).unwrap();
+        let entries = normalize(&parsed, "/tmp/work");
+
+        assert_eq!(entries.len(), 1);
+        match &entries[0].entry_type {
+            NormalizedEntryType::AgentEvent { event } => assert_eq!(
+                event,
+                &AgentEvent::McpServerFailed {
+                    name: "playwright".to_string(),
+                    error: "failed to connect".to_string(),
+                }
+            ),
+            other => panic!("Expected McpServerFailed, got {other:?}"),
+        }
+    }
+
+    fn stream_token_fragments(coalesce_deltas: bool) -> Vec<json_patch::Patch> {
+        let lines = [
+            r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","role":"assistant","content":[]}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_start","index":0,"content_block":{"type":"text","text":""}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hel"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"lo, "}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"world"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_stop","index":0}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":1,"delta":{"type":"thinking_delta","thinking":"Done"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"message_stop"}}"#,
+        ];
+        let mut processor = ClaudeLogProcessor::new();
+        processor.coalesce_deltas = coalesce_deltas;
+        let provider = EntryIndexProvider::test_new();
+        lines
+            .iter()
+            .flat_map(|line| {
+                let parsed: ClaudeJson = serde_json::from_str(line).unwrap();
+                processor.normalize_entries(&parsed, "/tmp/work", &provider)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_coalesced_token_fragments() {
+        let patches = stream_token_fragments(true);
+        let entries = patches_to_entries(&patches);
+
+        // One entry per content block, flushed at the block stop and the message stop
+        assert_eq!(patches.len(), 2);
+        assert!(matches!(
+            entries[0].entry_type,
+            NormalizedEntryType::AssistantMessage
+        ));
+        assert_eq!(entries[0].content, "Hello, world");
+        assert!(matches!(
+            entries[1].entry_type,
+            NormalizedEntryType::Thinking
+        ));
+        assert_eq!(entries[1].content, "Done");
+    }
+
+    #[test]
+    fn test_uncoalesced_token_fragments_update_per_delta() {
+        let patches = stream_token_fragments(false);
+        let entries = patches_to_entries(&patches);
+
+        assert_eq!(patches.len(), 4);
+        assert_eq!(entries[2].content, "Hello, world");
+        assert_eq!(entries[3].content, "Done");
+    }
+
+    #[test]
+    fn test_uncoalesced_deltas_carry_inter_token_timing() {
+        let lines = [
+            (
+                0,
+                r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","role":"assistant","content":[]}}}"#,
+            ),
+            (
+                30,
+                r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"Hel"}}}"#,
+            ),
+            (
+                120,
+                r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"lo, "}}}"#,
+            ),
+            (
+                5,
+                r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"world"}}}"#,
+            ),
+        ];
+        let clock = crate::clock::FakeClock::default();
+        let mut processor = ClaudeLogProcessor::new();
+        processor.coalesce_deltas = false;
+        processor.clock = Arc::new(clock.clone());
+        let provider = EntryIndexProvider::test_new();
+        let patches: Vec<json_patch::Patch> = lines
+            .iter()
+            .flat_map(|(gap_ms, line)| {
+                clock.advance(std::time::Duration::from_millis(*gap_ms));
+                let parsed: ClaudeJson = serde_json::from_str(line).unwrap();
+                processor.normalize_entries(&parsed, "/tmp/work", &provider)
+            })
+            .collect();
+
+        let timings: Vec<Option<u64>> = patches_to_entries(&patches)
+            .iter()
+            .map(|entry| DeltaTiming::from_entry(entry).map(|timing| timing.since_last_ms))
+            .collect();
+        assert_eq!(timings, vec![Some(30), Some(120), Some(5)]);
+
+        let store = MsgStore::new();
+        for patch in patches {
+            store.push_patch(patch);
+        }
+        let latency = crate::logs::RunSummary::from_msg_store(&store).latency;
+        assert_eq!(latency.samples, 3);
+        assert_eq!(latency.max_ms, 120);
+    }
+
+    #[test]
+    fn test_repeated_tool_call_fires_at_threshold() {
+        let mut processor = ClaudeLogProcessor::new();
+        let repeated_events: Vec<usize> = (1..=4)
+            .map(|n| {
+                let json = format!(
+                    r#"{{"type":"assistant","message":{{"role":"assistant","content":[
+                        {{"type":"tool_use","id":"t{n}","name":"Bash","input":{{"command":"cargo build"}}}}
+                    ]}}}}"#
+                );
+                let parsed: ClaudeJson = serde_json::from_str(&json).unwrap();
+                normalize_helper(&mut processor, &parsed, "/tmp/work")
+                    .iter()
+                    .filter(|entry| {
+                        matches!(
+                            &entry.entry_type,
+                            NormalizedEntryType::AgentEvent {
+                                event: AgentEvent::RepeatedAction { count: 3, .. }
+                            }
+                        )
+                    })
+                    .count()
+            })
+            .collect();
+
+        assert_eq!(repeated_events, vec![0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_file_reads_are_reported_once_per_path() {
+        let read = r#""name":"Read","input":{"file_path":"/tmp/work/src/main.rs"}"#;
+        let edit = r#""name":"Edit","input":{"file_path":"/tmp/work/src/main.rs","old_string":"a","new_string":"b"}"#;
+        let messages: Vec<String> = [("t1", read), ("t2", read), ("t3", edit)]
+            .into_iter()
+            .map(|(id, tool)| {
+                format!(
+                    r#"{{"type":"assistant","message":{{"role":"assistant","content":[
+                        {{"type":"tool_use","id":"{id}",{tool}}}
+                    ]}}}}"#
+                )
+            })
+            .collect();
+        let messages: Vec<&str> = messages.iter().map(String::as_str).collect();
+
+        let file_reads: Vec<String> = normalize_into_store(&messages, "/tmp/work")
+            .into_iter()
+            .filter_map(|entry| match entry.entry_type {
+                NormalizedEntryType::AgentEvent {
+                    event: AgentEvent::FileRead { path },
+                } => Some(path),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(file_reads, vec!["src/main.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_scratchpad_write_is_reported_alongside_edit() {
+        let mut processor = ClaudeLogProcessor::new();
+        let json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"t1","name":"Write","input":{"file_path":"/tmp/work/scratchpad.md","content":"- try the watcher fix next"}}
+        ]}}"#;
+        let parsed: ClaudeJson = serde_json::from_str(json).unwrap();
+        let entries = normalize_helper(&mut processor, &parsed, "/tmp/work");
+
+        assert!(matches!(
+            &entries[0].entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileEdit { path, .. },
+                ..
+            } if path == "scratchpad.md"
+        ));
+        assert!(entries.iter().any(|entry| matches!(
+            &entry.entry_type,
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::ScratchpadUpdate { path },
+            } if path == "scratchpad.md"
+        )));
+    }
+
+    #[test]
+    fn test_out_of_bounds_command_is_warned() {
+        let json = r#"{"type":"assistant","message":{"role":"assistant","content":[
+            {"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cat /tmp/work/src/main.rs ../other/.env"}}
+        ]}}"#;
+        let entries = normalize_into_store(&[json], "/tmp/work");
+
+        let warnings: Vec<_> = entries
+            .iter()
+            .filter_map(|entry| match &entry.entry_type {
+                NormalizedEntryType::AgentEvent {
+                    event: AgentEvent::OutOfBoundsAccess { path },
+                } => Some(path.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(warnings, vec!["../other/.env"]);
+    }
 }
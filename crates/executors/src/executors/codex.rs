@@ -7,7 +7,7 @@ use std::{
     collections::HashMap,
     env,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{Arc, LazyLock},
 };
 
 /// Returns the Codex home directory.
@@ -23,6 +23,21 @@ pub fn codex_home() -> Option<PathBuf> {
     dirs::home_dir().map(|home| home.join(".codex"))
 }
 
+/// Whether a raw stdout line ends one model turn. `task_started` is sent once per prompt, so it
+/// cannot count turns; Codex reports token usage after every model response instead, including
+/// each one that only requests tool calls.
+pub fn is_turn_marker(line: &str) -> bool {
+    line.contains(r#""type":"token_count""#)
+}
+
+/// Codex rejects models outside a ChatGPT plan, and the API rejects ones the key cannot use
+pub static CODEX_ENTITLEMENT_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    compile_patterns(&[
+        r"(?i)the '(?P<model>[^']+)' model is not supported when using Codex with a ChatGPT account",
+        r"(?i)does not have access to model [`'\x22]?(?P<model>[\w.:-]+)",
+    ])
+});
+
 use async_trait::async_trait;
 use codex_app_server_protocol::{NewConversationParams, ReviewTarget};
 use codex_protocol::{
@@ -30,6 +45,7 @@ use codex_protocol::{
 };
 use command_group::AsyncCommandGroup;
 use derivative::Derivative;
+use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -46,12 +62,18 @@ use self::{
 };
 use crate::{
     approvals::ExecutorApprovalService,
-    command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
+    command::{
+        CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, ResolvedCommand,
+        apply_overrides,
+    },
+    early_exit::compile_patterns,
     env::ExecutionEnv,
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, ExecutorExitResult, SpawnedChild,
+        AppendPrompt, AvailabilityInfo, BaseCodingAgent, ExecutorError, ExecutorExitResult,
+        MaxTurnsSupport, SessionSnapshot, SessionSnapshotFile, SpawnedChild,
         StandardCodingAgentExecutor,
         codex::{jsonrpc::ExitSignalSender, normalize_logs::Error},
+        file_mtime_timestamp,
     },
     stdout_dup::create_stdout_pipe_writer,
 };
@@ -155,6 +177,10 @@ pub struct Codex {
     pub developer_instructions: Option<String>,
     #[serde(flatten)]
     pub cmd: CmdOverrides,
+    /// Per-request sampling temperature passed through `-c model_temperature`
+    #[serde(skip)]
+    #[ts(skip)]
+    pub temperature: Option<f32>,
 
     #[serde(skip)]
     #[ts(skip)]
@@ -168,6 +194,30 @@ impl StandardCodingAgentExecutor for Codex {
         self.approvals = Some(approvals);
     }
 
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
+    fn apply_max_turns(&mut self, _max_turns: u32) -> MaxTurnsSupport {
+        MaxTurnsSupport::Counted(is_turn_marker)
+    }
+
+    // Codex has no plan mode; a read-only sandbox without escalation is its dry run
+    fn apply_plan_only(&mut self) -> bool {
+        self.sandbox = Some(SandboxMode::ReadOnly);
+        self.ask_for_approval = Some(AskForApproval::Never);
+        true
+    }
+
+    fn apply_temperature(&mut self, temperature: f32) -> bool {
+        self.temperature = Some(temperature);
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -175,7 +225,9 @@ impl StandardCodingAgentExecutor for Codex {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let command_parts = self.build_command_builder()?.build_initial()?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let action = CodexSessionAction::Chat {
             prompt: combined_prompt,
         };
@@ -191,7 +243,9 @@ impl StandardCodingAgentExecutor for Codex {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let command_parts = self.build_command_builder()?.build_follow_up(&[])?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let action = CodexSessionAction::Chat {
             prompt: combined_prompt,
         };
@@ -199,23 +253,33 @@ impl StandardCodingAgentExecutor for Codex {
             .await
     }
 
+    async fn resolve_command(
+        &self,
+        current_dir: &Path,
+        _env: &ExecutionEnv,
+    ) -> Result<ResolvedCommand, ExecutorError> {
+        let command_parts = self.build_command_builder()?.build_initial()?;
+        Ok(ResolvedCommand::new(command_parts, &self.cmd, current_dir)
+            .with_default_env("NODE_NO_WARNINGS", "1")
+            .with_default_env("NO_COLOR", "1")
+            .with_default_env("RUST_LOG", "error"))
+    }
+
     fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
         normalize_logs(msg_store, worktree_path);
     }
 
     fn default_mcp_config_path(&self) -> Option<PathBuf> {
-        codex_home().map(|home| home.join("config.toml"))
+        self.config_home().map(|home| home.join("config.toml"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
-        if let Some(timestamp) = codex_home()
-            .and_then(|home| std::fs::metadata(home.join("auth.json")).ok())
-            .and_then(|m| m.modified().ok())
-            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64)
+        if let Some(timestamp) =
+            codex_home().and_then(|home| file_mtime_timestamp(&home.join("auth.json")))
         {
             return AvailabilityInfo::LoginDetected {
                 last_auth_timestamp: timestamp,
+                expires_at: None,
             };
         }
 
@@ -244,7 +308,7 @@ impl StandardCodingAgentExecutor for Codex {
     ) -> Result<SpawnedChild, ExecutorError> {
         let command_parts = self.build_command_builder()?.build_initial()?;
         let review_target = ReviewTarget::Custom {
-            instructions: prompt.to_string(),
+            instructions: self.cmd.normalize_prompt(prompt),
         };
         let action = CodexSessionAction::Review {
             target: review_target,
@@ -252,9 +316,71 @@ impl StandardCodingAgentExecutor for Codex {
         self.spawn_inner(current_dir, command_parts, action, session_id, env)
             .await
     }
+
+    async fn export_session(
+        &self,
+        session_id: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SessionSnapshot, ExecutorError> {
+        let codex_home = self.session_home(env)?;
+        let (relative_path, contents) = SessionHandler::export_rollout(&codex_home, session_id)
+            .map_err(|e| ExecutorError::Io(std::io::Error::other(e.to_string())))?;
+        Ok(SessionSnapshot {
+            executor: BaseCodingAgent::Codex,
+            session_id: session_id.to_string(),
+            files: vec![SessionSnapshotFile {
+                relative_path,
+                contents,
+            }],
+        })
+    }
+
+    async fn import_session(
+        &self,
+        snapshot: &SessionSnapshot,
+        env: &ExecutionEnv,
+    ) -> Result<(), ExecutorError> {
+        if snapshot.executor != BaseCodingAgent::Codex {
+            return Err(ExecutorError::Unsupported(format!(
+                "cannot import a {} session into Codex",
+                snapshot.executor
+            )));
+        }
+        let codex_home = self.session_home(env)?;
+        for file in &snapshot.files {
+            SessionHandler::import_rollout(&codex_home, &file.relative_path, &file.contents)
+                .map_err(|e| ExecutorError::Io(std::io::Error::other(e.to_string())))?;
+        }
+        Ok(())
+    }
 }
 
 impl Codex {
+    /// Codex home for this profile, following `isolated_home` when set.
+    pub fn config_home(&self) -> Option<PathBuf> {
+        match self.cmd.isolated_home.as_ref() {
+            Some(home) => Some(home.join(".codex")),
+            None => codex_home(),
+        }
+    }
+
+    /// Codex home the spawned CLI will use, honouring a `CODEX_HOME` passed through the env.
+    fn session_home(&self, env: &ExecutionEnv) -> Result<PathBuf, ExecutorError> {
+        let from_env = self
+            .cmd
+            .env
+            .as_ref()
+            .and_then(|vars| vars.get("CODEX_HOME"))
+            .or_else(|| env.vars.get("CODEX_HOME"))
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from);
+        from_env.or_else(|| self.config_home()).ok_or_else(|| {
+            ExecutorError::Io(std::io::Error::other(
+                "Could not determine Codex home directory",
+            ))
+        })
+    }
+
     pub fn new_with_overrides(
         append_prompt: AppendPrompt,
         model: Option<String>,
@@ -276,6 +402,7 @@ impl Codex {
             compact_prompt: None,
             developer_instructions: None,
             cmd,
+            temperature: None,
             approvals: None,
         }
     }
@@ -290,6 +417,16 @@ impl Codex {
         if self.oss.unwrap_or(false) {
             builder = builder.extend_params(["--oss"]);
         }
+        // Also sent with the conversation params; on the command line it shows in `ps` and
+        // dry runs
+        if let Some(model) = &self.model {
+            let model = toml::Value::String(model.clone());
+            builder = builder.extend_params(["-c".to_string(), format!("model={model}")]);
+        }
+        if let Some(temperature) = self.temperature {
+            builder = builder
+                .extend_params(["-c".to_string(), format!("model_temperature={temperature}")]);
+        }
 
         apply_overrides(builder, &self.cmd)
     }
@@ -478,6 +615,8 @@ impl Codex {
             child,
             exit_signal: Some(exit_signal_rx),
             interrupt_sender: None,
+            events: Vec::new(),
+            spawn_permit: None,
         })
     }
 
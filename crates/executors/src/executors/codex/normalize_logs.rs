@@ -37,10 +37,13 @@ use crate::{
         ActionType, CommandExitStatus, CommandRunResult, FileChange, NormalizedEntry,
         NormalizedEntryError, NormalizedEntryType, TodoItem, ToolResult, ToolResultValueType,
         ToolStatus,
-        stderr_processor::normalize_stderr_logs,
+        stderr_processor::normalize_stderr_logs_with_detectors,
         utils::{
-            ConversationPatch, EntryIndexProvider,
+            ConversationPatch, DeprecationDetector, EntryIndexProvider, LintSummary,
+            McpFailureDetector, ProposedEditTracker, RepeatedActionDetector, TestSummary,
+            detect_merge_conflict, parse_unified_diffs,
             patch::{add_normalized_entry, replace_normalized_entry, upsert_normalized_entry},
+            scratchpad_update,
         },
     },
 };
@@ -216,6 +219,8 @@ struct LogState {
     patches: HashMap<String, PatchState>,
     web_searches: HashMap<String, WebSearchState>,
     token_usage_info: Option<TokenUsageInfo>,
+    repeated_commands: RepeatedActionDetector,
+    proposed_edits: ProposedEditTracker,
 }
 
 enum StreamingTextKind {
@@ -234,6 +239,8 @@ impl LogState {
             patches: HashMap::new(),
             web_searches: HashMap::new(),
             token_usage_info: None,
+            repeated_commands: RepeatedActionDetector::new(),
+            proposed_edits: ProposedEditTracker::new(),
         }
     }
 
@@ -357,9 +364,21 @@ fn format_todo_status(status: &StepStatus) -> String {
     .to_string()
 }
 
+/// Codex logs config deprecations through tracing, e.g. `WARN codex_core::config: ... deprecated`
+const CODEX_DEPRECATION_PATTERNS: &[&str] = &[r"\bWARN\s+\S+:\s*(?P<msg>.*(?i:deprecated).*)$"];
+
+/// Codex logs MCP startup errors as "MCP client for `docs` failed to start: ..."
+const CODEX_MCP_FAILURE_PATTERNS: &[&str] =
+    &[r"MCP client for `(?P<name>[^`]+)` failed to start: (?P<error>.+)$"];
+
 pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
     let entry_index = EntryIndexProvider::start_from(&msg_store);
-    normalize_stderr_logs(msg_store.clone(), entry_index.clone());
+    normalize_stderr_logs_with_detectors(
+        msg_store.clone(),
+        entry_index.clone(),
+        DeprecationDetector::new().with_patterns(CODEX_DEPRECATION_PATTERNS),
+        McpFailureDetector::new().with_patterns(CODEX_MCP_FAILURE_PATTERNS),
+    );
 
     let worktree_path_str = worktree_path.to_string_lossy().to_string();
     tokio::spawn(async move {
@@ -446,9 +465,17 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                 }
                 EventMsg::AgentMessage(AgentMessageEvent { message }) => {
                     state.thinking = None;
+                    let diffs = parse_unified_diffs(&message);
                     let (entry, index, is_new) = state.assistant_message(message);
                     upsert_normalized_entry(&msg_store, index, entry, is_new);
                     state.assistant = None;
+                    for diff in diffs {
+                        add_normalized_entry(
+                            &msg_store,
+                            &entry_index,
+                            diff.into_event().into_normalized_entry(),
+                        );
+                    }
                 }
                 EventMsg::AgentReasoning(AgentReasoningEvent { text }) => {
                     state.assistant = None;
@@ -524,6 +551,7 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                     }
 
                     for (path, file_changes) in normalized {
+                        let proposal = state.proposed_edits.propose(&call_id, &path, &file_changes);
                         let mut entry = PatchEntry {
                             index: None,
                             path,
@@ -539,6 +567,13 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                         );
                         entry.index = Some(index);
                         patch_state.entries.push(entry);
+                        if let Some(event) = proposal {
+                            add_normalized_entry(
+                                &msg_store,
+                                &entry_index,
+                                event.into_normalized_entry(),
+                            );
+                        }
                     }
                 }
                 EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
@@ -577,7 +612,18 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                         &entry_index,
                         command_state.to_normalized_entry(),
                     );
-                    command_state.index = Some(index)
+                    command_state.index = Some(index);
+                    let command_text = command_state.command.clone();
+                    if let Some(event) = state
+                        .repeated_commands
+                        .observe(&command_text, &command_text)
+                    {
+                        add_normalized_entry(
+                            &msg_store,
+                            &entry_index,
+                            event.into_normalized_entry(),
+                        );
+                    }
                 }
                 EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
                     call_id,
@@ -614,7 +660,7 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                     interaction_input: _,
                     stdout: _,
                     stderr: _,
-                    aggregated_output: _,
+                    aggregated_output,
                     exit_code,
                     duration: _,
                     formatted_output,
@@ -638,6 +684,26 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                             index,
                             command_state.to_normalized_entry(),
                         );
+                        let events = parse_unified_diffs(&aggregated_output)
+                            .into_iter()
+                            .map(|diff| diff.into_event())
+                            .chain(
+                                TestSummary::parse(&aggregated_output)
+                                    .map(|summary| summary.into_event()),
+                            )
+                            .chain(
+                                LintSummary::parse(&aggregated_output)
+                                    .into_iter()
+                                    .map(|summary| summary.into_event()),
+                            )
+                            .chain(detect_merge_conflict(&aggregated_output));
+                        for event in events {
+                            add_normalized_entry(
+                                &msg_store,
+                                &entry_index,
+                                event.into_normalized_entry(),
+                            );
+                        }
                     }
                 }
                 EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
@@ -838,6 +904,20 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                             ToolStatus::Failed
                         };
                         for mut entry in patch_state.entries {
+                            let action_type = ActionType::FileEdit {
+                                path: entry.path.clone(),
+                                changes: entry.changes.clone(),
+                            };
+                            if success && state.proposed_edits.applied(&call_id, &action_type) {
+                                tracing::debug!("Applied proposed edit to {}", entry.path);
+                            }
+                            if success && let Some(event) = scratchpad_update(&action_type) {
+                                add_normalized_entry(
+                                    &msg_store,
+                                    &entry_index,
+                                    event.into_normalized_entry(),
+                                );
+                            }
                             entry.status = status.clone();
                             let Some(index) = entry.index else {
                                 tracing::error!("missing entry index for existing patch entry");
@@ -850,6 +930,7 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                             );
                         }
                     }
+                    state.proposed_edits.discard(&call_id);
                 }
                 EventMsg::WebSearchBegin(WebSearchBeginEvent { call_id }) => {
                     state.assistant = None;
@@ -881,6 +962,9 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                     state.thinking = None;
                     let path_str = path.to_string_lossy().to_string();
                     let relative_path = make_path_relative(&path_str, &worktree_path_str);
+                    let action_type = ActionType::FileRead {
+                        path: relative_path.clone(),
+                    };
                     add_normalized_entry(
                         &msg_store,
                         &entry_index,
@@ -888,9 +972,7 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
                             timestamp: None,
                             entry_type: NormalizedEntryType::ToolUse {
                                 tool_name: "view_image".to_string(),
-                                action_type: ActionType::FileRead {
-                                    path: relative_path.clone(),
-                                },
+                                action_type,
                                 status: ToolStatus::Success,
                             },
                             content: relative_path.to_string(),
@@ -173,6 +173,66 @@ impl SessionHandler {
         }
     }
 
+    /// Read the rollout file for `session_id` under `codex_home`.
+    /// Returns (path relative to the sessions root, file contents).
+    pub fn export_rollout(
+        codex_home: &Path,
+        session_id: &str,
+    ) -> Result<(String, String), SessionError> {
+        let sessions_root = codex_home.join("sessions");
+        let path = Self::scan_directory(&sessions_root, session_id)?;
+        let contents = std::fs::read_to_string(&path).map_err(|e| {
+            SessionError::Io(format!("Failed to read rollout {}: {e}", path.display()))
+        })?;
+        let relative = path
+            .strip_prefix(&sessions_root)
+            .map_err(|e| SessionError::Io(e.to_string()))?
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok((relative, contents))
+    }
+
+    /// Write an exported rollout back under `codex_home`, keeping its relative location.
+    pub fn import_rollout(
+        codex_home: &Path,
+        relative_path: &str,
+        contents: &str,
+    ) -> Result<PathBuf, SessionError> {
+        let relative = Path::new(relative_path);
+        let is_rollout = relative
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with("rollout-") && name.ends_with(".jsonl"));
+        if !is_rollout
+            || relative
+                .components()
+                .any(|component| !matches!(component, std::path::Component::Normal(_)))
+        {
+            return Err(SessionError::Format(format!(
+                "Invalid rollout path in snapshot: {relative_path}"
+            )));
+        }
+
+        let destination = codex_home.join("sessions").join(relative);
+        if let Some(parent) = destination.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| {
+                SessionError::Io(format!(
+                    "Failed to create sessions directory {}: {e}",
+                    parent.display()
+                ))
+            })?;
+        }
+        std::fs::write(&destination, contents).map_err(|e| {
+            SessionError::Io(format!(
+                "Failed to write rollout {}: {e}",
+                destination.display()
+            ))
+        })?;
+        Ok(destination)
+    }
+
     fn sessions_root() -> Result<PathBuf, SessionError> {
         let codex_dir = codex_home().ok_or_else(|| {
             SessionError::Io("Could not determine Codex home directory".to_string())
@@ -245,3 +305,90 @@ impl SessionHandler {
         format!("rollout-{ts}-{new_id}.jsonl")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        command::CmdOverrides,
+        env::ExecutionEnv,
+        executors::{
+            AppendPrompt, BaseCodingAgent, ExecutorError, StandardCodingAgentExecutor, codex::Codex,
+        },
+    };
+
+    const SESSION_ID: &str = "0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b";
+
+    fn temp_codex_home() -> PathBuf {
+        std::env::temp_dir().join(format!("codex-session-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn env_for(codex_home: &Path) -> ExecutionEnv {
+        let mut env = ExecutionEnv::new();
+        env.insert("CODEX_HOME", codex_home.to_string_lossy());
+        env
+    }
+
+    #[tokio::test]
+    async fn test_session_snapshot_round_trip() {
+        let source = temp_codex_home();
+        let target = temp_codex_home();
+        let relative = format!("2025/01/02/rollout-2025-01-02T03-04-05-{SESSION_ID}.jsonl");
+        let rollout = format!(
+            "{{\"type\":\"session_meta\",\"payload\":{{\"id\":\"{SESSION_ID}\"}}}}\n{{\"type\":\"response_item\",\"payload\":{{}}}}\n"
+        );
+        let source_path = source.join("sessions").join(&relative);
+        std::fs::create_dir_all(source_path.parent().unwrap()).unwrap();
+        std::fs::write(&source_path, &rollout).unwrap();
+
+        let codex =
+            Codex::new_with_overrides(AppendPrompt::default(), None, CmdOverrides::default());
+        let snapshot = codex
+            .export_session(SESSION_ID, &env_for(&source))
+            .await
+            .unwrap();
+        assert_eq!(snapshot.executor, BaseCodingAgent::Codex);
+        assert_eq!(snapshot.files.len(), 1);
+        assert_eq!(snapshot.files[0].relative_path, relative);
+
+        // The snapshot survives a serialization hop between machines
+        let snapshot = serde_json::from_str(&serde_json::to_string(&snapshot).unwrap()).unwrap();
+        codex
+            .import_session(&snapshot, &env_for(&target))
+            .await
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(target.join("sessions").join(&relative)).unwrap(),
+            rollout
+        );
+        let reexported = codex
+            .export_session(SESSION_ID, &env_for(&target))
+            .await
+            .unwrap();
+        assert_eq!(reexported, snapshot);
+
+        let _ = std::fs::remove_dir_all(&source);
+        let _ = std::fs::remove_dir_all(&target);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_paths_outside_sessions() {
+        let target = temp_codex_home();
+        let codex =
+            Codex::new_with_overrides(AppendPrompt::default(), None, CmdOverrides::default());
+        let snapshot = crate::executors::SessionSnapshot {
+            executor: BaseCodingAgent::Codex,
+            session_id: SESSION_ID.to_string(),
+            files: vec![crate::executors::SessionSnapshotFile {
+                relative_path: format!("../rollout-{SESSION_ID}.jsonl"),
+                contents: String::new(),
+            }],
+        };
+
+        let result = codex.import_session(&snapshot, &env_for(&target)).await;
+
+        assert!(matches!(result, Err(ExecutorError::Io(_))));
+        assert!(!target.exists());
+    }
+}
@@ -55,8 +55,12 @@ pub struct Copilot {
 }
 
 impl Copilot {
+    pub fn base_command() -> &'static str {
+        "npx -y @github/copilot@0.0.375"
+    }
+
     fn build_command_builder(&self, log_dir: &str) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @github/copilot@0.0.375").params([
+        let mut builder = CommandBuilder::new(Self::base_command()).params([
             "--no-color",
             "--log-level",
             "debug",
@@ -98,6 +102,14 @@ impl Copilot {
 
 #[async_trait]
 impl StandardCodingAgentExecutor for Copilot {
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -110,7 +122,9 @@ impl StandardCodingAgentExecutor for Copilot {
             .build_initial()?;
         let (program_path, args) = command_parts.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(program_path);
         command
@@ -153,7 +167,9 @@ impl StandardCodingAgentExecutor for Copilot {
             .build_follow_up(&["--resume".to_string(), session_id.to_string()])?;
         let (program_path, args) = command_parts.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(program_path);
 
@@ -212,7 +228,9 @@ impl StandardCodingAgentExecutor for Copilot {
 
     // MCP configuration methods
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".copilot").join("mcp-config.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".copilot").join("mcp-config.json"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
@@ -22,10 +22,10 @@ use crate::{
         AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
     },
     logs::{
-        ActionType, FileChange, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
-        TodoItem, ToolStatus,
+        ActionType, AgentEvent, FileChange, NormalizedEntry, NormalizedEntryError,
+        NormalizedEntryType, TodoItem, ToolStatus,
         plain_text_processor::PlainTextLogProcessor,
-        utils::{ConversationPatch, EntryIndexProvider},
+        utils::{ConversationPatch, DeprecationDetector, EntryIndexProvider},
     },
 };
 
@@ -71,6 +71,14 @@ impl CursorAgent {
 
 #[async_trait]
 impl StandardCodingAgentExecutor for CursorAgent {
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -83,7 +91,9 @@ impl StandardCodingAgentExecutor for CursorAgent {
 
         let (executable_path, args) = command_parts.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(executable_path);
         command
@@ -122,7 +132,9 @@ impl StandardCodingAgentExecutor for CursorAgent {
             .build_follow_up(&["--resume".to_string(), session_id.to_string()])?;
         let (executable_path, args) = command_parts.into_resolved().await?;
 
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let mut command = Command::new(executable_path);
         command
@@ -150,11 +162,12 @@ impl StandardCodingAgentExecutor for CursorAgent {
     fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
         let entry_index_provider = EntryIndexProvider::start_from(&msg_store);
 
-        // Custom stderr processor for Cursor that detects login errors
+        // Custom stderr processor for Cursor that detects login errors and deprecations
         let msg_store_stderr = msg_store.clone();
         let entry_index_provider_stderr = entry_index_provider.clone();
         tokio::spawn(async move {
             let mut stderr = msg_store_stderr.stderr_chunked_stream();
+            let mut deprecations = DeprecationDetector::new();
             let mut processor = PlainTextLogProcessor::builder()
                 .normalized_entry_producer(Box::new(|content: String| {
                     let content = strip_ansi_escapes::strip_str(&content);
@@ -173,6 +186,7 @@ impl StandardCodingAgentExecutor for CursorAgent {
                 .build();
 
             while let Some(Ok(chunk)) = stderr.next().await {
+                let notices = deprecations.process_chunk(&chunk);
                 let content = strip_ansi_escapes::strip_str(&chunk);
                 if content.contains(CURSOR_AUTH_REQUIRED_MSG) {
                     let error_message = NormalizedEntry {
@@ -192,6 +206,11 @@ impl StandardCodingAgentExecutor for CursorAgent {
                         msg_store_stderr.push_patch(patch);
                     }
                 }
+                for message in notices {
+                    let entry = AgentEvent::Deprecation { message }.into_normalized_entry();
+                    let id = entry_index_provider_stderr.next();
+                    msg_store_stderr.push_patch(ConversationPatch::add_normalized_entry(id, entry));
+                }
             }
         });
 
@@ -487,7 +506,9 @@ impl StandardCodingAgentExecutor for CursorAgent {
     }
 
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".cursor").join("mcp.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".cursor").join("mcp.json"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
@@ -1217,6 +1238,7 @@ mod tests {
     use workspace_utils::msg_store::MsgStore;
 
     use super::*;
+    use crate::logs::utils::patch::extract_normalized_entry_from_patch;
 
     #[tokio::test]
     async fn test_cursor_streaming_patch_generation() {
@@ -1262,6 +1284,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_cursor_stderr_deprecation_reported_once() {
+        let executor = CursorAgent {
+            append_prompt: AppendPrompt::default(),
+            force: None,
+            model: None,
+            cmd: Default::default(),
+        };
+        let msg_store = Arc::new(MsgStore::new());
+        let notice = "(node:77) [DEP0005] DeprecationWarning: Buffer() is deprecated.\n";
+        msg_store.push_stderr(notice);
+        msg_store.push_stderr(notice);
+        msg_store.push_finished();
+
+        executor.normalize_logs(msg_store.clone(), Path::new("/tmp/test-worktree"));
+        tokio::time::sleep(tokio::time::Duration::from_millis(150)).await;
+
+        let deprecations: Vec<String> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                workspace_utils::log_msg::LogMsg::JsonPatch(patch) => {
+                    extract_normalized_entry_from_patch(patch)
+                }
+                _ => None,
+            })
+            .filter_map(|(_, entry)| match entry.entry_type {
+                NormalizedEntryType::AgentEvent {
+                    event: AgentEvent::Deprecation { message },
+                } => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(deprecations, vec!["Buffer() is deprecated.".to_string()]);
+    }
+
     #[test]
     fn test_session_id_extraction_from_system_line() {
         // System messages no longer extract session_id
@@ -83,10 +83,14 @@ pub struct Droid {
 }
 
 impl Droid {
+    pub fn base_command() -> &'static str {
+        "droid exec"
+    }
+
     pub fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
         use crate::command::{CommandBuilder, apply_overrides};
         let mut builder =
-            CommandBuilder::new("droid exec").params(["--output-format", "stream-json"]);
+            CommandBuilder::new(Self::base_command()).params(["--output-format", "stream-json"]);
         builder = match &self.autonomy {
             Autonomy::Normal => builder,
             Autonomy::Low => builder.extend_params(["--auto", "low"]),
@@ -139,6 +143,14 @@ async fn spawn_droid(
 
 #[async_trait]
 impl StandardCodingAgentExecutor for Droid {
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -146,7 +158,9 @@ impl StandardCodingAgentExecutor for Droid {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let droid_command = self.build_command_builder()?.build_initial()?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         spawn_droid(droid_command, &combined_prompt, current_dir, env, &self.cmd).await
     }
@@ -166,7 +180,9 @@ impl StandardCodingAgentExecutor for Droid {
         let continue_cmd = self
             .build_command_builder()?
             .build_follow_up(&["--session-id".to_string(), forked_session_id.clone()])?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         spawn_droid(continue_cmd, &combined_prompt, current_dir, env, &self.cmd).await
     }
@@ -180,6 +196,8 @@ impl StandardCodingAgentExecutor for Droid {
     }
 
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".factory").join("mcp.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".factory").join("mcp.json"))
     }
 }
@@ -12,11 +12,11 @@ use workspace_utils::{
 };
 
 use crate::logs::{
-    ActionType, CommandExitStatus, CommandRunResult, FileChange, NormalizedEntry,
+    ActionType, AgentEvent, CommandExitStatus, CommandRunResult, FileChange, NormalizedEntry,
     NormalizedEntryError, NormalizedEntryType, TodoItem, ToolResult, ToolStatus,
     plain_text_processor::PlainTextLogProcessor,
     utils::{
-        EntryIndexProvider,
+        DeprecationDetector, EntryIndexProvider,
         patch::{add_normalized_entry, replace_normalized_entry},
     },
 };
@@ -670,7 +670,9 @@ pub fn normalize_logs(
 
 fn normalize_stderr_logs(msg_store: Arc<MsgStore>, entry_index_provider: EntryIndexProvider) {
     tokio::spawn(async move {
+        let deprecation_index = entry_index_provider.clone();
         let mut stderr = msg_store.stderr_chunked_stream();
+        let mut deprecations = DeprecationDetector::new();
 
         let mut processor = PlainTextLogProcessor::builder()
             .normalized_entry_producer(Box::new(|content: String| NormalizedEntry {
@@ -695,9 +697,14 @@ fn normalize_stderr_logs(msg_store: Arc<MsgStore>, entry_index_provider: EntryIn
             .build();
 
         while let Some(Ok(chunk)) = stderr.next().await {
+            let notices = deprecations.process_chunk(&chunk);
             for patch in processor.process(chunk) {
                 msg_store.push_patch(patch);
             }
+            for message in notices {
+                let entry = AgentEvent::Deprecation { message }.into_normalized_entry();
+                add_normalized_entry(&msg_store, &deprecation_index, entry);
+            }
         }
     });
 }
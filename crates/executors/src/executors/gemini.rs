@@ -1,7 +1,11 @@
-use std::{path::Path, sync::Arc};
+use std::{
+    path::Path,
+    sync::{Arc, LazyLock},
+};
 
 use async_trait::async_trait;
 use derivative::Derivative;
+use regex::Regex;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
@@ -10,13 +14,22 @@ use workspace_utils::msg_store::MsgStore;
 pub use super::acp::AcpAgentHarness;
 use crate::{
     approvals::ExecutorApprovalService,
-    command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
+    command::{CmdOverrides, CommandBuildError, CommandBuilder, ResolvedCommand, apply_overrides},
+    early_exit::compile_patterns,
     env::ExecutionEnv,
     executors::{
         AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+        file_mtime_timestamp,
     },
 };
 
+/// Gemini CLI names the model when the current tier does not include it
+pub static GEMINI_ENTITLEMENT_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    compile_patterns(&[
+        r"(?i)does not (?:include|have) access to (?:model )?[`'\x22]?(?P<model>gemini[\w.-]*)",
+    ])
+});
+
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
 #[derivative(Debug, PartialEq)]
 pub struct Gemini {
@@ -35,8 +48,12 @@ pub struct Gemini {
 }
 
 impl Gemini {
+    pub fn base_command() -> &'static str {
+        "npx -y @google/gemini-cli@0.23.0"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @google/gemini-cli@0.23.0");
+        let mut builder = CommandBuilder::new(Self::base_command());
 
         if let Some(model) = &self.model {
             builder = builder.extend_params(["--model", model.as_str()]);
@@ -59,6 +76,14 @@ impl StandardCodingAgentExecutor for Gemini {
         self.approvals = Some(approvals);
     }
 
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -66,7 +91,9 @@ impl StandardCodingAgentExecutor for Gemini {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let harness = AcpAgentHarness::new();
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let gemini_command = self.build_command_builder()?.build_initial()?;
         let approvals = if self.yolo.unwrap_or(false) {
             None
@@ -93,7 +120,9 @@ impl StandardCodingAgentExecutor for Gemini {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let harness = AcpAgentHarness::new();
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let gemini_command = self.build_command_builder()?.build_follow_up(&[])?;
         let approvals = if self.yolo.unwrap_or(false) {
             None
@@ -113,23 +142,34 @@ impl StandardCodingAgentExecutor for Gemini {
             .await
     }
 
+    async fn resolve_command(
+        &self,
+        current_dir: &Path,
+        _env: &ExecutionEnv,
+    ) -> Result<ResolvedCommand, ExecutorError> {
+        let command_parts = self.build_command_builder()?.build_initial()?;
+        Ok(ResolvedCommand::new(command_parts, &self.cmd, current_dir)
+            .with_default_env("NPM_CONFIG_LOGLEVEL", "error")
+            .with_default_env("NODE_NO_WARNINGS", "1"))
+    }
+
     fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
         super::acp::normalize_logs(msg_store, worktree_path);
     }
 
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".gemini").join("settings.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".gemini").join("settings.json"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
         if let Some(timestamp) = dirs::home_dir()
-            .and_then(|home| std::fs::metadata(home.join(".gemini").join("oauth_creds.json")).ok())
-            .and_then(|m| m.modified().ok())
-            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
-            .map(|d| d.as_secs() as i64)
+            .and_then(|home| file_mtime_timestamp(&home.join(".gemini").join("oauth_creds.json")))
         {
             return AvailabilityInfo::LoginDetected {
                 last_auth_timestamp: timestamp,
+                expires_at: None,
             };
         }
 
@@ -11,7 +11,7 @@ use workspace_utils::msg_store::MsgStore;
 
 use crate::{
     approvals::ExecutorApprovalService,
-    command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
+    command::{CmdOverrides, CommandBuildError, CommandBuilder, ResolvedCommand, apply_overrides},
     env::ExecutionEnv,
     executors::{
         AppendPrompt, AvailabilityInfo, ExecutorError, ExecutorExitResult, SpawnedChild,
@@ -47,8 +47,12 @@ pub struct Opencode {
 }
 
 impl Opencode {
+    pub fn base_command() -> &'static str {
+        "npx -y opencode-ai@1.1.3"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let builder = CommandBuilder::new("npx -y opencode-ai@1.1.3")
+        let builder = CommandBuilder::new(Self::base_command())
             // Pass hostname/port as separate args so OpenCode treats them as explicitly set
             // (it checks `process.argv.includes(\"--port\")` / `\"--hostname\"`).
             .extend_params(["serve", "--hostname", "127.0.0.1", "--port", "0"]);
@@ -62,7 +66,9 @@ impl Opencode {
         resume_session: Option<&str>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
 
         let command_parts = self.build_command_builder()?.build_initial()?;
         let (program_path, args) = command_parts.into_resolved().await?;
@@ -132,6 +138,8 @@ impl Opencode {
             child,
             exit_signal: Some(exit_signal_rx),
             interrupt_sender: Some(interrupt_tx),
+            events: Vec::new(),
+            spawn_permit: None,
         })
     }
 }
@@ -194,6 +202,19 @@ impl StandardCodingAgentExecutor for Opencode {
         self.approvals = Some(approvals);
     }
 
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
+    fn resolved_model(&self) -> Option<String> {
+        self.model.clone()
+    }
+
+    fn apply_plan_only(&mut self) -> bool {
+        self.mode = Some("plan".to_string());
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -216,6 +237,21 @@ impl StandardCodingAgentExecutor for Opencode {
             .await
     }
 
+    async fn resolve_command(
+        &self,
+        current_dir: &Path,
+        _env: &ExecutionEnv,
+    ) -> Result<ResolvedCommand, ExecutorError> {
+        let command_parts = self.build_command_builder()?.build_initial()?;
+        let mut resolved = ResolvedCommand::new(command_parts, &self.cmd, current_dir)
+            .with_default_env("NODE_NO_WARNINGS", "1")
+            .with_default_env("NO_COLOR", "1");
+        for (key, value) in setup_approvals_env(self.auto_approve, &ExecutionEnv::new()).vars {
+            resolved = resolved.with_default_env(&key, &value);
+        }
+        Ok(resolved)
+    }
+
     fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
         normalize_logs::normalize_logs(msg_store, worktree_path);
     }
@@ -4,8 +4,13 @@
 //! 1. Performs random file operations (create, delete, modify)
 //! 2. Streams 10 mock log entries over 10 seconds
 //! 3. Outputs logs in ClaudeJson format for compatibility with existing log normalization
+//!
+//! Its behaviour can be steered through the execution env to exercise failure paths:
+//! - `QA_MOCK_EXIT_CODE`: exit status once the output is written (default 0)
+//! - `QA_MOCK_DELAY_MS`: pause after each output line (default 1000)
+//! - `QA_MOCK_STDOUT`: raw output printed instead of the canned ClaudeJson logs
 
-use std::{path::Path, process::Stdio, sync::Arc};
+use std::{path::Path, process::Stdio, sync::Arc, time::Duration};
 
 use async_trait::async_trait;
 use command_group::AsyncCommandGroup;
@@ -25,30 +30,85 @@ use crate::{
     logs::utils::EntryIndexProvider,
 };
 
+pub const EXIT_CODE_ENV: &str = "QA_MOCK_EXIT_CODE";
+pub const DELAY_MS_ENV: &str = "QA_MOCK_DELAY_MS";
+pub const STDOUT_ENV: &str = "QA_MOCK_STDOUT";
+
+const DEFAULT_LINE_DELAY: Duration = Duration::from_secs(1);
+
 /// Mock executor for QA testing
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, TS, JsonSchema)]
 pub struct QaMockExecutor;
 
+/// How a mock run behaves, as configured by the `QA_MOCK_*` variables.
+#[derive(Debug, Clone, PartialEq)]
+struct MockBehaviour {
+    exit_code: i32,
+    line_delay: Duration,
+    stdout: Option<String>,
+}
+
+impl MockBehaviour {
+    fn from_env(env: &ExecutionEnv) -> Result<Self, ExecutorError> {
+        let parse = |key: &str| -> Result<Option<i64>, ExecutorError> {
+            env.vars
+                .get(key)
+                .map(|value| {
+                    value.trim().parse::<i64>().map_err(|_| {
+                        ExecutorError::InvalidArg(format!(
+                            "{key} must be an integer, got {value:?}"
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        let exit_code = match parse(EXIT_CODE_ENV)? {
+            Some(code) => i32::try_from(code)
+                .ok()
+                .filter(|code| (0..=255).contains(code))
+                .ok_or_else(|| {
+                    ExecutorError::InvalidArg(format!("{EXIT_CODE_ENV} must be 0..=255"))
+                })?,
+            None => 0,
+        };
+        let line_delay = match parse(DELAY_MS_ENV)? {
+            Some(ms) => Duration::from_millis(u64::try_from(ms).map_err(|_| {
+                ExecutorError::InvalidArg(format!("{DELAY_MS_ENV} must not be negative"))
+            })?),
+            None => DEFAULT_LINE_DELAY,
+        };
+        Ok(Self {
+            exit_code,
+            line_delay,
+            stdout: env.vars.get(STDOUT_ENV).cloned(),
+        })
+    }
+}
+
 #[async_trait]
 impl StandardCodingAgentExecutor for QaMockExecutor {
     async fn spawn(
         &self,
         current_dir: &Path,
         prompt: &str,
-        _env: &ExecutionEnv,
+        env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         info!("QA Mock Executor: spawning mock execution");
+        let behaviour = MockBehaviour::from_env(env)?;
 
         // 1. Perform file operations before spawning the log output process
         perform_file_operations(current_dir).await;
 
         // 2. Generate mock logs and write to temp file to avoid shell escaping issues
-        let logs = generate_mock_logs(prompt);
+        let content = match &behaviour.stdout {
+            Some(stdout) if stdout.ends_with('\n') => stdout.clone(),
+            Some(stdout) => format!("{stdout}\n"),
+            // Write all logs to file, one per line
+            None => generate_mock_logs(prompt).join("\n") + "\n",
+        };
         let temp_dir = std::env::temp_dir();
         let log_file = temp_dir.join(format!("qa_mock_logs_{}.jsonl", uuid::Uuid::new_v4()));
 
-        // Write all logs to file, one per line
-        let content = logs.join("\n") + "\n";
         tokio::fs::write(&log_file, &content)
             .await
             .map_err(|e| ExecutorError::Io(std::io::Error::other(e)))?;
@@ -56,9 +116,11 @@ impl StandardCodingAgentExecutor for QaMockExecutor {
         // 3. Create shell script that reads file and outputs with delays
         // Using IFS= read -r to preserve exact content (no word splitting, no backslash interpretation)
         let script = format!(
-            r#"while IFS= read -r line; do echo "$line"; sleep 1; done < "{}"; rm -f "{}""#,
+            r#"while IFS= read -r line; do echo "$line"; sleep {}; done < "{}"; rm -f "{}"; exit {}"#,
+            behaviour.line_delay.as_secs_f64(),
             log_file.display(),
-            log_file.display()
+            log_file.display(),
+            behaviour.exit_code
         );
 
         let mut cmd = tokio::process::Command::new("sh");
@@ -92,6 +154,7 @@ impl StandardCodingAgentExecutor for QaMockExecutor {
             current_dir,
             entry_index_provider,
             crate::executors::claude::HistoryStrategy::Default,
+            true,
         );
     }
 
@@ -190,6 +253,8 @@ fn generate_mock_logs(prompt: &str) -> Vec<String> {
             tools: None,
             model: Some("qa-mock-executor".to_string()),
             api_key_source: Some("unknown".to_string()),
+            permission_mode: None,
+            mcp_servers: None,
         },
         // 2. Assistant thinking
         ClaudeJson::Assistant {
@@ -334,6 +399,8 @@ fn generate_mock_logs(prompt: &str) -> Vec<String> {
             error: None,
             num_turns: Some(3),
             session_id: Some(session_id),
+            usage: None,
+            total_cost_usd: None,
         },
     ];
 
@@ -382,6 +449,84 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    async fn run(vars: &[(&str, &str)]) -> (std::process::ExitStatus, String, Duration) {
+        use tokio::io::AsyncReadExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut env = ExecutionEnv::new();
+        for (key, value) in vars {
+            env.insert(*key, *value);
+        }
+        let started = std::time::Instant::now();
+        let mut spawned = QaMockExecutor
+            .spawn(dir.path(), "test prompt", &env)
+            .await
+            .unwrap();
+        let mut stdout = String::new();
+        spawned
+            .child
+            .inner()
+            .stdout
+            .take()
+            .unwrap()
+            .read_to_string(&mut stdout)
+            .await
+            .unwrap();
+        let status = spawned.child.wait().await.unwrap();
+        (status, stdout, started.elapsed())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_exit_code_knob() {
+        let (status, stdout, _) = run(&[(DELAY_MS_ENV, "0"), (EXIT_CODE_ENV, "3")]).await;
+        assert_eq!(status.code(), Some(3));
+        // The canned logs are still written before exiting
+        assert_eq!(stdout.lines().count(), 10);
+
+        let (status, _, _) = run(&[(DELAY_MS_ENV, "0")]).await;
+        assert!(status.success());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_stdout_knob() {
+        let (status, stdout, _) = run(&[
+            (DELAY_MS_ENV, "0"),
+            (STDOUT_ENV, "Error: rate limited\nplease retry"),
+        ])
+        .await;
+        assert!(status.success());
+        assert_eq!(stdout, "Error: rate limited\nplease retry\n");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_delay_knob() {
+        let (_, _, elapsed) = run(&[(DELAY_MS_ENV, "200"), (STDOUT_ENV, "one\ntwo")]).await;
+        assert!(elapsed >= Duration::from_millis(400), "took {elapsed:?}");
+
+        let (_, _, elapsed) = run(&[(DELAY_MS_ENV, "0"), (STDOUT_ENV, "one\ntwo")]).await;
+        assert!(elapsed < Duration::from_millis(400), "took {elapsed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_knobs_fail_the_spawn() {
+        for (key, value) in [
+            (EXIT_CODE_ENV, "boom"),
+            (EXIT_CODE_ENV, "300"),
+            (DELAY_MS_ENV, "-5"),
+        ] {
+            let mut env = ExecutionEnv::new();
+            env.insert(key, value);
+            assert!(matches!(
+                QaMockExecutor.spawn(Path::new("."), "prompt", &env).await,
+                Err(ExecutorError::InvalidArg(message)) if message.contains(key)
+            ));
+        }
+    }
+
     #[test]
     fn test_escape_special_characters() {
         let logs = generate_mock_logs("test with \"quotes\" and\nnewlines");
@@ -33,8 +33,12 @@ pub struct QwenCode {
 }
 
 impl QwenCode {
+    pub fn base_command() -> &'static str {
+        "npx -y @qwen-code/qwen-code@0.2.1"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @qwen-code/qwen-code@0.2.1");
+        let mut builder = CommandBuilder::new(Self::base_command());
 
         if self.yolo.unwrap_or(false) {
             builder = builder.extend_params(["--yolo"]);
@@ -50,6 +54,10 @@ impl StandardCodingAgentExecutor for QwenCode {
         self.approvals = Some(approvals);
     }
 
+    fn effective_append(&self) -> Option<String> {
+        self.append_prompt.effective()
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -57,7 +65,9 @@ impl StandardCodingAgentExecutor for QwenCode {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let qwen_command = self.build_command_builder()?.build_initial()?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let harness = AcpAgentHarness::with_session_namespace("qwen_sessions");
         let approvals = if self.yolo.unwrap_or(false) {
             None
@@ -84,7 +94,9 @@ impl StandardCodingAgentExecutor for QwenCode {
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
         let qwen_command = self.build_command_builder()?.build_follow_up(&[])?;
-        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let combined_prompt = self
+            .cmd
+            .normalize_prompt(&self.append_prompt.combine_prompt(prompt));
         let harness = AcpAgentHarness::with_session_namespace("qwen_sessions");
         let approvals = if self.yolo.unwrap_or(false) {
             None
@@ -110,7 +122,9 @@ impl StandardCodingAgentExecutor for QwenCode {
 
     // MCP configuration methods
     fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
-        dirs::home_dir().map(|home| home.join(".qwen").join("settings.json"))
+        self.cmd
+            .home_dir()
+            .map(|home| home.join(".qwen").join("settings.json"))
     }
 
     fn get_availability_info(&self) -> AvailabilityInfo {
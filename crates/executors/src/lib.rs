@@ -1,9 +1,25 @@
 pub mod actions;
 pub mod approvals;
+pub mod auto_commit;
+pub mod checkpoint;
+pub mod clock;
 pub mod command;
+pub mod context_manifest;
+pub mod dirty_worktree;
+pub mod disk_space;
+pub mod early_exit;
 pub mod env;
 pub mod executors;
 pub mod logs;
 pub mod mcp_config;
+pub mod model_fallback;
+pub mod post_command;
+pub mod preflight;
 pub mod profile;
+pub mod recording;
+pub mod secret_scan;
+pub mod spawn_limit;
+pub mod spawn_retry;
 pub mod stdout_dup;
+pub mod turn_limit;
+pub mod wallclock;
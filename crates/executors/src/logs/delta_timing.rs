@@ -0,0 +1,154 @@
+//! Inter-token latency of streamed output.
+//!
+//! With delta coalescing off, each streamed assistant delta updates its entry as soon as it is
+//! read. That update carries a [`DeltaTiming`] in the entry's metadata: the time since the
+//! previous delta of the same message was read, or since the message started for its first
+//! delta. The run's [`RunSummary`](crate::logs::RunSummary) buckets these gaps into a
+//! [`LatencyHistogram`].
+
+use std::time::SystemTime;
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::logs::NormalizedEntry;
+
+/// Metadata key holding an entry's [`DeltaTiming`]
+const METADATA_KEY: &str = "delta_timing";
+
+/// Upper bounds (inclusive) of the histogram buckets in ms; slower gaps fall in a final bucket.
+const BUCKET_BOUNDS_MS: [u64; 7] = [10, 25, 50, 100, 250, 500, 1000];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaTiming {
+    pub since_last_ms: u64,
+}
+
+impl DeltaTiming {
+    /// The gap between two reads; zero if the clock went backwards.
+    pub fn between(previous: SystemTime, now: SystemTime) -> Self {
+        let since_last = now.duration_since(previous).unwrap_or_default();
+        Self {
+            since_last_ms: u64::try_from(since_last.as_millis()).unwrap_or(u64::MAX),
+        }
+    }
+
+    /// Record this timing in `entry`'s metadata next to whatever is already there.
+    pub fn attach(self, entry: &mut NormalizedEntry) {
+        let metadata = entry
+            .metadata
+            .get_or_insert_with(|| serde_json::Value::Object(Default::default()));
+        if let (Some(fields), Ok(value)) = (metadata.as_object_mut(), serde_json::to_value(self)) {
+            fields.insert(METADATA_KEY.to_string(), value);
+        }
+    }
+
+    pub fn from_entry(entry: &NormalizedEntry) -> Option<Self> {
+        let value = entry.metadata.as_ref()?.get(METADATA_KEY)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LatencyBucket {
+    /// Inclusive upper bound in ms; unset for the last bucket, which holds every slower gap
+    pub max_ms: Option<u64>,
+    pub count: u32,
+}
+
+/// Distribution of the gaps between streamed deltas over a run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct LatencyHistogram {
+    /// Empty until the first gap is recorded
+    pub buckets: Vec<LatencyBucket>,
+    pub samples: u32,
+    pub max_ms: u64,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, timing: DeltaTiming) {
+        if self.buckets.is_empty() {
+            self.buckets = BUCKET_BOUNDS_MS
+                .iter()
+                .map(|bound| Some(*bound))
+                .chain([None])
+                .map(|max_ms| LatencyBucket { max_ms, count: 0 })
+                .collect();
+        }
+        let ms = timing.since_last_ms;
+        if let Some(bucket) = self
+            .buckets
+            .iter_mut()
+            .find(|bucket| bucket.max_ms.is_none_or(|max_ms| ms <= max_ms))
+        {
+            bucket.count += 1;
+        }
+        self.samples += 1;
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::logs::NormalizedEntryType;
+
+    #[test]
+    fn test_timing_round_trips_through_metadata() {
+        let mut entry = NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: "Hel".to_string(),
+            metadata: Some(serde_json::json!({ "type": "text", "text": "Hel" })),
+        };
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(100);
+        let timing = DeltaTiming::between(start, start + Duration::from_millis(42));
+
+        timing.attach(&mut entry);
+
+        assert_eq!(timing.since_last_ms, 42);
+        assert_eq!(DeltaTiming::from_entry(&entry), Some(timing));
+        assert_eq!(entry.metadata.as_ref().unwrap()["text"], "Hel");
+        assert_eq!(
+            DeltaTiming::between(start, start - Duration::from_secs(1)).since_last_ms,
+            0
+        );
+    }
+
+    #[test]
+    fn test_histogram_buckets_gaps() {
+        let mut histogram = LatencyHistogram::default();
+        for ms in [3, 10, 11, 400, 5000] {
+            histogram.record(DeltaTiming { since_last_ms: ms });
+        }
+
+        let counts: Vec<(Option<u64>, u32)> = histogram
+            .buckets
+            .iter()
+            .map(|bucket| (bucket.max_ms, bucket.count))
+            .collect();
+        assert_eq!(
+            counts,
+            vec![
+                (Some(10), 2),
+                (Some(25), 1),
+                (Some(50), 0),
+                (Some(100), 0),
+                (Some(250), 0),
+                (Some(500), 1),
+                (Some(1000), 0),
+                (None, 1),
+            ]
+        );
+        assert_eq!(histogram.samples, 5);
+        assert_eq!(histogram.max_ms, 5000);
+    }
+}
@@ -1,12 +1,23 @@
+use std::collections::{BTreeMap, HashMap};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 use workspace_utils::approvals::ApprovalStatus;
 
+use crate::executors::BaseCodingAgent;
+
+pub mod delta_timing;
 pub mod plain_text_processor;
+pub mod run_summary;
+pub mod sse;
 pub mod stderr_processor;
 pub mod utils;
 
+pub use delta_timing::{DeltaTiming, LatencyHistogram};
+pub use run_summary::RunSummary;
+pub use sse::{SseFrame, to_sse_stream};
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[serde(tag = "type", rename_all = "snake_case")]
 #[ts(export)]
@@ -95,6 +106,403 @@ pub enum NormalizedEntryType {
         execution_processes: usize,
         needs_setup: bool,
     },
+    AgentEvent {
+        event: AgentEvent,
+    },
+}
+
+/// Structured events detected in agent output that are neither messages nor tool calls
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq)]
+#[ts(export)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AgentEvent {
+    /// The agent changed its working directory; `path` is worktree-relative
+    CwdChanged { path: String },
+    /// A follow-up runs on a different model than the previous turn
+    ModelSwitched { from: String, to: String },
+    /// The agent moved between plan and act phases
+    PhaseChanged { phase: AgentPhase },
+    /// The client printed a deprecation notice
+    Deprecation { message: String },
+    /// The agent announced a shell command; `tool_call_id` links it to the tool call (and any
+    /// approval request) once that arrives
+    ProposedCommand {
+        command: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        tool_call_id: Option<String>,
+    },
+    /// The agent ended its turn with a question and is waiting on the user's reply
+    QuestionPending { question: String },
+    /// An MCP server failed to start or connect, so its tools are unavailable
+    McpServerFailed { name: String, error: String },
+    /// The agent printed a unified diff; one event per file
+    Diff { path: String, hunks: Vec<DiffHunk> },
+    /// The client reported the credit or token balance left on the account
+    CreditBalance { remaining: f64, unit: String },
+    /// A test run finished; counts the runner did not report are omitted
+    TestResults {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        passed: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        failed: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        skipped: Option<u32>,
+    },
+    /// A linter or formatter (clippy, eslint, ruff) finished; counts it did not report are
+    /// omitted
+    LintResults {
+        tool: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        warnings: Option<u32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        errors: Option<u32>,
+    },
+    /// The agent issued the same action `count` times in a row, suggesting a retry loop
+    RepeatedAction { action: String, count: u32 },
+    /// Output of the request's `post_command`, run in the working directory once the agent exited;
+    /// `exit_code` is unset when the command could not start or was killed by a signal
+    PostCommand {
+        command: String,
+        output: String,
+        exit_code: Option<i32>,
+    },
+    /// The agent process has exited; `post_command_exit_code` is set when a post-command ran
+    AgentFinished {
+        exit_code: Option<i32>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        post_command_exit_code: Option<i32>,
+    },
+    /// The requested model was unavailable at startup and the run was retried on the fallback model
+    ModelFallback { from: String, to: String },
+    /// The client refused `model` because the account's plan does not include it
+    ModelNotEntitled { model: String },
+    /// The client does not recognise `model`
+    UnknownModel { model: String },
+    /// The agent read a file; `path` is worktree-relative and reported once per stream
+    FileRead { path: String },
+    /// Descriptor of the session the run belongs to, emitted at stream start
+    SessionMeta { metadata: SessionMetadata },
+    /// Caller-supplied tags (ticket id, user id, ...) attached to the run, emitted at stream start
+    RunTags { tags: BTreeMap<String, String> },
+    /// Follow-up actions from the "Next steps" list the agent ended its turn with
+    SuggestedActions { items: Vec<String> },
+    /// A file edit awaiting approval before it is written; `tool_call_id` matches the `FileEdit`
+    /// tool call that applies it
+    ProposedEdit {
+        path: String,
+        diff: String,
+        tool_call_id: String,
+    },
+    /// The agent wrote a run of binary data, left out of the log; `bytes` is its size
+    BinaryOutput { bytes: u64 },
+    /// A git operation run on the agent's behalf after it exited, e.g. the request's `auto_commit`
+    GitOp {
+        operation: String,
+        commit: String,
+        message: String,
+    },
+    /// The agent asked the user to install or run something; `command` when it named one
+    SetupInstruction {
+        command: Option<String>,
+        text: String,
+    },
+    /// The assistant message at `entry_index` hedges about its own answer; `excerpt` is the
+    /// sentence that does
+    UncertaintyFlag { excerpt: String, entry_index: usize },
+    /// A command the agent ran hit merge conflicts; `files` are the conflicted paths it named
+    MergeConflict { files: Vec<String> },
+    /// The agent wrote to its scratchpad or working-memory file at `path`
+    ScratchpadUpdate { path: String },
+    /// Tokens (and cost, when the client reports it) used by one agent turn
+    Usage {
+        usage: TokenUsage,
+        cost_usd: Option<f64>,
+    },
+    /// A proposed command or edit refers to `path`, which lies outside the worktree
+    OutOfBoundsAccess { path: String },
+    /// Secrets of these `kinds` were masked in the preceding entry
+    SecretRedacted { kinds: Vec<String> },
+    /// The worktree had uncommitted changes in `paths` when the agent started
+    WorktreeDirty { paths: Vec<String> },
+    /// Roll-up of the whole run, added once the agent has exited
+    RunSummary {
+        edits: u32,
+        tool_calls: u32,
+        usage: TokenUsage,
+        cost_usd: Option<f64>,
+        status: RunStatus,
+        /// Gaps between streamed deltas; only recorded when delta coalescing is off
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        #[ts(optional)]
+        latency: Option<LatencyHistogram>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum AgentPhase {
+    Planning,
+    Executing,
+}
+
+/// Tokens used; `input_tokens` includes prompt-cache reads and writes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+impl std::ops::AddAssign for TokenUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+    }
+}
+
+/// How a run ended, as reported in [`AgentEvent::RunSummary`]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    Stopped,
+}
+
+/// Small, storable description of a coding agent session, kept apart from its full logs
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct SessionMetadata {
+    pub session_id: String,
+    pub agent: BaseCodingAgent,
+    pub model: Option<String>,
+    /// Command the client CLI is launched with
+    pub client: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub worktree: String,
+}
+
+/// One `@@` hunk of a unified diff; `lines` keep their ` `, `-` or `+` prefix
+#[derive(Debug, Clone, Serialize, Deserialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct DiffHunk {
+    pub header: String,
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub lines: Vec<String>,
+}
+
+impl AgentPhase {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Planning => "Planning",
+            Self::Executing => "Executing",
+        }
+    }
+}
+
+impl TokenUsage {
+    fn label(&self) -> String {
+        format!(
+            "{} input / {} output tokens",
+            self.input_tokens, self.output_tokens
+        )
+    }
+}
+
+impl RunStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+fn cost_label(cost_usd: Option<f64>) -> String {
+    cost_usd.map_or_else(String::new, |cost| format!(" (${cost:.4})"))
+}
+
+impl AgentEvent {
+    /// Event for a follow-up whose resolved model differs from the previous turn's.
+    pub fn model_switched(previous: Option<&str>, current: Option<&str>) -> Option<Self> {
+        match (previous, current) {
+            (Some(from), Some(to)) if from != to => Some(Self::ModelSwitched {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Event for a run's caller-supplied tags, if it has any.
+    pub fn run_tags(tags: Option<&HashMap<String, String>>) -> Option<Self> {
+        tags.filter(|tags| !tags.is_empty())
+            .map(|tags| Self::RunTags {
+                tags: tags
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect(),
+            })
+    }
+
+    /// Human-readable summary used as the entry content.
+    pub fn summary(&self) -> String {
+        match self {
+            Self::CwdChanged { path } => format!("Working directory changed to {path}"),
+            Self::ModelSwitched { from, to } => format!("Model switched from {from} to {to}"),
+            Self::PhaseChanged { phase } => format!("Phase changed to {}", phase.label()),
+            Self::Deprecation { message } => format!("Deprecation warning: {message}"),
+            Self::ProposedCommand { command, .. } => format!("Proposed command: {command}"),
+            Self::QuestionPending { question } => format!("Question pending: {question}"),
+            Self::McpServerFailed { name, error } => {
+                format!("MCP server {name} failed: {error}")
+            }
+            Self::Diff { path, hunks } => format!("Diff for {path} ({} hunks)", hunks.len()),
+            Self::CreditBalance { remaining, unit } => {
+                format!("Balance remaining: {remaining} {unit}")
+            }
+            Self::TestResults {
+                passed,
+                failed,
+                skipped,
+            } => {
+                let counts = [("passed", passed), ("failed", failed), ("skipped", skipped)]
+                    .into_iter()
+                    .filter_map(|(label, count)| count.map(|count| format!("{count} {label}")))
+                    .collect::<Vec<_>>();
+                format!("Test results: {}", counts.join(", "))
+            }
+            Self::LintResults {
+                tool,
+                warnings,
+                errors,
+            } => {
+                let counts = [("errors", errors), ("warnings", warnings)]
+                    .into_iter()
+                    .filter_map(|(label, count)| count.map(|count| format!("{count} {label}")))
+                    .collect::<Vec<_>>();
+                format!("Lint results ({tool}): {}", counts.join(", "))
+            }
+            Self::RepeatedAction { action, count } => {
+                format!("Repeated action {count} times: {action}")
+            }
+            Self::PostCommand {
+                command, exit_code, ..
+            } => match exit_code {
+                Some(code) => format!("Post-command `{command}` exited with code {code}"),
+                None => format!("Post-command `{command}` did not complete"),
+            },
+            Self::AgentFinished {
+                exit_code,
+                post_command_exit_code,
+            } => {
+                let code = |code: &Option<i32>| {
+                    code.map_or_else(|| "none".to_string(), |code| code.to_string())
+                };
+                match post_command_exit_code {
+                    Some(_) => format!(
+                        "Agent finished with exit code {}, post-command exit code {}",
+                        code(exit_code),
+                        code(post_command_exit_code)
+                    ),
+                    None => format!("Agent finished with exit code {}", code(exit_code)),
+                }
+            }
+            Self::ModelFallback { from, to } => {
+                format!("Model {from} unavailable, fell back to {to}")
+            }
+            Self::ModelNotEntitled { model } => {
+                format!("Model {model} is not available on this account's plan")
+            }
+            Self::UnknownModel { model } => format!("Unknown model {model}"),
+            Self::FileRead { path } => format!("Read file {path}"),
+            Self::SessionMeta { metadata } => match &metadata.model {
+                Some(model) => format!(
+                    "Session {} started with {} ({model})",
+                    metadata.session_id, metadata.agent
+                ),
+                None => format!(
+                    "Session {} started with {}",
+                    metadata.session_id, metadata.agent
+                ),
+            },
+            Self::RunTags { tags } => {
+                let tags = tags
+                    .iter()
+                    .map(|(key, value)| format!("{key}={value}"))
+                    .collect::<Vec<_>>();
+                format!("Run tags: {}", tags.join(", "))
+            }
+            Self::SuggestedActions { items } => {
+                format!("Suggested next steps: {}", items.join("; "))
+            }
+            Self::ProposedEdit { path, .. } => format!("Proposed edit to {path}"),
+            Self::BinaryOutput { bytes } => format!("Binary output omitted ({bytes} bytes)"),
+            Self::GitOp {
+                operation,
+                commit,
+                message,
+            } => {
+                let short = commit.get(..7).unwrap_or(commit);
+                format!("git {operation} {short}: {message}")
+            }
+            Self::SetupInstruction { command, text } => match command {
+                Some(command) => format!("Setup required: {command}"),
+                None => format!("Setup required: {text}"),
+            },
+            Self::UncertaintyFlag { excerpt, .. } => format!("Agent is uncertain: {excerpt}"),
+            Self::MergeConflict { files } if files.is_empty() => "Merge conflict".to_string(),
+            Self::MergeConflict { files } => format!("Merge conflict in {}", files.join(", ")),
+            Self::ScratchpadUpdate { path } => format!("Scratchpad updated: {path}"),
+            Self::Usage { usage, cost_usd } => {
+                format!("Used {}{}", usage.label(), cost_label(*cost_usd))
+            }
+            Self::OutOfBoundsAccess { path } => {
+                format!("Warning: access outside the worktree: {path}")
+            }
+            Self::SecretRedacted { kinds } => {
+                format!("Secret redacted from output: {}", kinds.join(", "))
+            }
+            Self::WorktreeDirty { paths } => {
+                format!("Worktree has uncommitted changes: {}", paths.join(", "))
+            }
+            Self::RunSummary {
+                edits,
+                tool_calls,
+                usage,
+                cost_usd,
+                status,
+                ..
+            } => format!(
+                "Run {}: {edits} edits, {tool_calls} tool calls, {}{}",
+                status.label(),
+                usage.label(),
+                cost_label(*cost_usd)
+            ),
+        }
+    }
+
+    pub fn into_normalized_entry(self) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            content: self.summary(),
+            entry_type: NormalizedEntryType::AgentEvent { event: self },
+            metadata: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -232,3 +640,39 @@ pub enum FileChange {
         has_line_numbers: bool,
     },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_model_follow_up_emits_nothing() {
+        assert_eq!(
+            AgentEvent::model_switched(Some("sonnet"), Some("sonnet")),
+            None
+        );
+        assert_eq!(AgentEvent::model_switched(None, Some("sonnet")), None);
+        assert_eq!(AgentEvent::model_switched(Some("sonnet"), None), None);
+    }
+
+    #[test]
+    fn test_changed_model_follow_up_emits_switch() {
+        let event = AgentEvent::model_switched(Some("sonnet"), Some("opus")).unwrap();
+        assert_eq!(
+            event,
+            AgentEvent::ModelSwitched {
+                from: "sonnet".to_string(),
+                to: "opus".to_string(),
+            }
+        );
+
+        let entry = event.into_normalized_entry();
+        assert_eq!(entry.content, "Model switched from sonnet to opus");
+        assert!(matches!(
+            entry.entry_type,
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::ModelSwitched { .. }
+            }
+        ));
+    }
+}
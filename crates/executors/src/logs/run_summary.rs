@@ -0,0 +1,215 @@
+//! End-of-run roll-up of a normalized log stream.
+//!
+//! Once the agent has exited, the entries in its [`MsgStore`] are replayed (later patches to an
+//! entry replace earlier ones) and aggregated into one [`AgentEvent::RunSummary`]: tool calls,
+//! the file edits among them, the token usage and cost from the run's [`AgentEvent::Usage`]
+//! events, and the gaps between streamed deltas. Every delta replaces its entry, so the gaps are
+//! read from each patch rather than from the final entries.
+
+use std::collections::BTreeMap;
+
+use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+use crate::logs::{
+    ActionType, AgentEvent, DeltaTiming, LatencyHistogram, NormalizedEntry, NormalizedEntryType,
+    RunStatus, TokenUsage, ToolStatus,
+    utils::{ConversationPatch, EntryIndexProvider, patch::extract_normalized_entry_from_patch},
+};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunSummary {
+    /// File edits that were not denied or failed
+    pub edits: u32,
+    pub tool_calls: u32,
+    pub usage: TokenUsage,
+    /// Unset when no client reported a cost
+    pub cost_usd: Option<f64>,
+    pub latency: LatencyHistogram,
+}
+
+impl RunSummary {
+    /// Aggregate the normalized entries currently in `msg_store`.
+    pub fn from_msg_store(msg_store: &MsgStore) -> Self {
+        let mut entries: BTreeMap<usize, NormalizedEntry> = BTreeMap::new();
+        let mut latency = LatencyHistogram::default();
+        for msg in msg_store.get_history() {
+            if let LogMsg::JsonPatch(patch) = msg
+                && let Some((index, entry)) = extract_normalized_entry_from_patch(&patch)
+            {
+                if let Some(timing) = DeltaTiming::from_entry(&entry) {
+                    latency.record(timing);
+                }
+                entries.insert(index, entry);
+            }
+        }
+
+        let summary = Self {
+            latency,
+            ..Self::default()
+        };
+        entries.values().fold(summary, |mut summary, entry| {
+            summary.observe(entry);
+            summary
+        })
+    }
+
+    fn observe(&mut self, entry: &NormalizedEntry) {
+        match &entry.entry_type {
+            NormalizedEntryType::ToolUse {
+                action_type,
+                status,
+                ..
+            } => {
+                self.tool_calls += 1;
+                if matches!(action_type, ActionType::FileEdit { .. })
+                    && !matches!(status, ToolStatus::Failed | ToolStatus::Denied { .. })
+                {
+                    self.edits += 1;
+                }
+            }
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::Usage { usage, cost_usd },
+            } => {
+                self.usage += *usage;
+                if let Some(cost) = cost_usd {
+                    *self.cost_usd.get_or_insert(0.0) += cost;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn into_event(self, status: RunStatus) -> AgentEvent {
+        AgentEvent::RunSummary {
+            edits: self.edits,
+            tool_calls: self.tool_calls,
+            usage: self.usage,
+            cost_usd: self.cost_usd,
+            status,
+            latency: (!self.latency.is_empty()).then_some(self.latency),
+        }
+    }
+
+    /// Patch adding the `RunSummary` entry after the existing entries in `msg_store`.
+    pub fn into_patch(self, msg_store: &MsgStore, status: RunStatus) -> json_patch::Patch {
+        let index = EntryIndexProvider::start_from(msg_store).next();
+        ConversationPatch::add_normalized_entry(
+            index,
+            self.into_event(status).into_normalized_entry(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::FileChange;
+
+    fn tool_use(action_type: ActionType, status: ToolStatus) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::ToolUse {
+                tool_name: "tool".to_string(),
+                action_type,
+                status,
+            },
+            content: String::new(),
+            metadata: None,
+        }
+    }
+
+    fn edit(path: &str) -> ActionType {
+        ActionType::FileEdit {
+            path: path.to_string(),
+            changes: vec![FileChange::Write {
+                content: "x".to_string(),
+            }],
+        }
+    }
+
+    fn usage(input_tokens: u64, output_tokens: u64, cost_usd: Option<f64>) -> NormalizedEntry {
+        AgentEvent::Usage {
+            usage: TokenUsage {
+                input_tokens,
+                output_tokens,
+            },
+            cost_usd,
+        }
+        .into_normalized_entry()
+    }
+
+    #[test]
+    fn test_summary_aggregates_synthetic_stream() {
+        let store = MsgStore::new();
+        let read = ActionType::FileRead {
+            path: "src/lib.rs".to_string(),
+        };
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            0,
+            tool_use(edit("src/lib.rs"), ToolStatus::Created),
+        ));
+        // The edit completing replaces its entry rather than adding a second one
+        store.push_patch(ConversationPatch::replace(
+            0,
+            tool_use(edit("src/lib.rs"), ToolStatus::Success),
+        ));
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            1,
+            tool_use(read, ToolStatus::Success),
+        ));
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            2,
+            tool_use(
+                edit("src/main.rs"),
+                ToolStatus::Denied {
+                    reason: Some("no".to_string()),
+                },
+            ),
+        ));
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            3,
+            usage(1200, 300, Some(0.25)),
+        ));
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            4,
+            usage(800, 200, Some(0.5)),
+        ));
+        store.push_stdout("raw output is ignored");
+
+        let summary = RunSummary::from_msg_store(&store);
+        assert_eq!(
+            summary,
+            RunSummary {
+                edits: 1,
+                tool_calls: 3,
+                usage: TokenUsage {
+                    input_tokens: 2000,
+                    output_tokens: 500,
+                },
+                cost_usd: Some(0.75),
+                latency: LatencyHistogram::default(),
+            }
+        );
+
+        let patch = summary.into_patch(&store, RunStatus::Succeeded);
+        let (index, entry) = extract_normalized_entry_from_patch(&patch).unwrap();
+        assert_eq!(index, 5);
+        assert!(matches!(
+            entry.entry_type,
+            NormalizedEntryType::AgentEvent {
+                event: AgentEvent::RunSummary {
+                    edits: 1,
+                    tool_calls: 3,
+                    status: RunStatus::Succeeded,
+                    ..
+                }
+            }
+        ));
+    }
+
+    #[test]
+    fn test_summary_of_empty_stream_has_no_cost() {
+        let summary = RunSummary::from_msg_store(&MsgStore::new());
+        assert_eq!(summary, RunSummary::default());
+    }
+}
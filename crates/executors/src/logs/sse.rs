@@ -0,0 +1,170 @@
+//! Server-Sent Events adapter over the normalized log stream.
+//!
+//! Each normalized entry pushed to a [`MsgStore`] becomes one SSE frame named after the entry's
+//! kind (`assistant_message`, `tool_use`, ...; agent events use the event name, e.g.
+//! `agent_finished`), with the entry's JSON as data. The stream ends with an `agent_finished`
+//! frame, synthesized without an exit code if the store finishes without one.
+
+use std::fmt;
+
+use futures::{Stream, StreamExt, future};
+use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+use crate::logs::{
+    AgentEvent, NormalizedEntry, NormalizedEntryType,
+    utils::patch::extract_normalized_entry_from_patch,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseFrame {
+    pub event: String,
+    pub data: String,
+}
+
+impl SseFrame {
+    pub fn from_entry(entry: &NormalizedEntry) -> Self {
+        Self {
+            event: entry_kind(entry),
+            data: serde_json::to_string(entry).unwrap_or_else(|_| "{}".to_string()),
+        }
+    }
+}
+
+/// Wire format: an `event:` line, one `data:` line per line of data, then a blank line.
+impl fmt::Display for SseFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "event: {}", self.event)?;
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {line}")?;
+        }
+        writeln!(f)
+    }
+}
+
+/// Stream the normalized entries of `store`, history first, as SSE frames until the agent finishes.
+pub fn to_sse_stream(store: &MsgStore) -> impl Stream<Item = SseFrame> + Send + 'static {
+    store
+        .history_plus_stream()
+        .filter_map(|msg| {
+            future::ready(match msg {
+                Ok(LogMsg::JsonPatch(patch)) => {
+                    extract_normalized_entry_from_patch(&patch).map(|(_, entry)| Some(entry))
+                }
+                Ok(LogMsg::Finished) => Some(None),
+                _ => None,
+            })
+        })
+        .scan(false, |finished, entry| {
+            if *finished {
+                return future::ready(None);
+            }
+            let entry = entry.unwrap_or_else(|| {
+                AgentEvent::AgentFinished {
+                    exit_code: None,
+                    post_command_exit_code: None,
+                }
+                .into_normalized_entry()
+            });
+            *finished = matches!(
+                entry.entry_type,
+                NormalizedEntryType::AgentEvent {
+                    event: AgentEvent::AgentFinished { .. }
+                }
+            );
+            future::ready(Some(SseFrame::from_entry(&entry)))
+        })
+}
+
+/// Serde tag of the entry type, or of the event for agent events.
+fn entry_kind(entry: &NormalizedEntry) -> String {
+    let (value, tag) = match &entry.entry_type {
+        NormalizedEntryType::AgentEvent { event } => (serde_json::to_value(event), "event"),
+        entry_type => (serde_json::to_value(entry_type), "type"),
+    };
+    value
+        .ok()
+        .and_then(|value| value.get(tag)?.as_str().map(str::to_string))
+        .unwrap_or_else(|| "message".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::utils::ConversationPatch;
+
+    fn assistant_message(content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_frames_end_with_agent_finished() {
+        let store = MsgStore::new();
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            0,
+            assistant_message("Done.\nAll tests pass."),
+        ));
+        store.push_stdout("raw output is not a normalized entry");
+        let finished = AgentEvent::AgentFinished {
+            exit_code: Some(0),
+            post_command_exit_code: None,
+        };
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            1,
+            finished.clone().into_normalized_entry(),
+        ));
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            2,
+            assistant_message("after the end"),
+        ));
+        store.push_finished();
+
+        let frames: Vec<SseFrame> = to_sse_stream(&store).collect().await;
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].event, "assistant_message");
+        let message: serde_json::Value = serde_json::from_str(&frames[0].data).unwrap();
+        assert_eq!(message["content"], "Done.\nAll tests pass.");
+        assert_eq!(
+            frames[0].to_string(),
+            format!("event: assistant_message\ndata: {}\n\n", frames[0].data)
+        );
+
+        assert_eq!(frames[1].event, "agent_finished");
+        let terminal: NormalizedEntry = serde_json::from_str(&frames[1].data).unwrap();
+        assert!(matches!(
+            terminal.entry_type,
+            NormalizedEntryType::AgentEvent { event } if event == finished
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sse_terminal_frame_synthesized_on_finish() {
+        let store = MsgStore::new();
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            0,
+            assistant_message("Working on it"),
+        ));
+        store.push_finished();
+
+        let frames: Vec<SseFrame> = to_sse_stream(&store).collect().await;
+
+        assert_eq!(
+            frames.iter().map(|f| f.event.as_str()).collect::<Vec<_>>(),
+            ["assistant_message", "agent_finished"]
+        );
+
+        let multiline = SseFrame {
+            event: "assistant_message".to_string(),
+            data: "a\nb".to_string(),
+        };
+        assert_eq!(
+            multiline.to_string(),
+            "event: assistant_message\ndata: a\ndata: b\n\n"
+        );
+    }
+}
@@ -2,6 +2,9 @@
 //!
 //! Uses `PlainTextLogProcessor` with a 2-second `latency_threshold` to split stderr streams into entries.
 //! Each entry is normalized as `ErrorMessage` and emitted as JSON patches to the message store.
+//! Deprecation notices and MCP server failures are additionally surfaced once each as
+//! `AgentEvent::Deprecation` and `AgentEvent::McpServerFailed`, and changes in the reported
+//! account balance as `AgentEvent::CreditBalance`.
 //!
 //! Example:
 //! ```rust,ignore
@@ -14,10 +17,13 @@ use futures::StreamExt;
 use workspace_utils::msg_store::MsgStore;
 
 use super::{
-    NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
+    AgentEvent, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
     plain_text_processor::PlainTextLogProcessor,
 };
-use crate::logs::utils::EntryIndexProvider;
+use crate::logs::utils::{
+    ConversationPatch, CreditBalanceDetector, DeprecationDetector, EntryIndexProvider,
+    McpFailureDetector,
+};
 
 /// Standard stderr log normalizer that uses PlainTextLogProcessor to stream error logs.
 ///
@@ -37,8 +43,26 @@ use crate::logs::utils::EntryIndexProvider;
 /// * `msg_store` - the message store providing a stream of stderr chunks and accepting patches.
 /// * `entry_index_provider` - provider of incremental entry indices for patch ordering.
 pub fn normalize_stderr_logs(msg_store: Arc<MsgStore>, entry_index_provider: EntryIndexProvider) {
+    normalize_stderr_logs_with_detectors(
+        msg_store,
+        entry_index_provider,
+        DeprecationDetector::new(),
+        McpFailureDetector::new(),
+    );
+}
+
+/// Same as [`normalize_stderr_logs`], matching deprecations and MCP failures with
+/// client-specific detectors.
+pub fn normalize_stderr_logs_with_detectors(
+    msg_store: Arc<MsgStore>,
+    entry_index_provider: EntryIndexProvider,
+    mut deprecations: DeprecationDetector,
+    mut mcp_failures: McpFailureDetector,
+) {
     tokio::spawn(async move {
+        let deprecation_index = entry_index_provider.clone();
         let mut stderr = msg_store.stderr_chunked_stream();
+        let mut balances = CreditBalanceDetector::new();
 
         // Create a processor with time-based emission for stderr
         let mut processor = PlainTextLogProcessor::builder()
@@ -55,9 +79,77 @@ pub fn normalize_stderr_logs(msg_store: Arc<MsgStore>, entry_index_provider: Ent
             .build();
 
         while let Some(Ok(chunk)) = stderr.next().await {
+            let notices = deprecations.process_chunk(&chunk);
+            let failures = mcp_failures.process_chunk(&chunk);
+            let balance_changes = balances.process_chunk(&chunk);
             for patch in processor.process(chunk) {
                 msg_store.push_patch(patch);
             }
+            for message in notices {
+                let entry = AgentEvent::Deprecation { message }.into_normalized_entry();
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    deprecation_index.next(),
+                    entry,
+                ));
+            }
+            for (name, error) in failures {
+                let entry = AgentEvent::McpServerFailed { name, error }.into_normalized_entry();
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    deprecation_index.next(),
+                    entry,
+                ));
+            }
+            for (remaining, unit) in balance_changes {
+                let entry = AgentEvent::CreditBalance { remaining, unit }.into_normalized_entry();
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    deprecation_index.next(),
+                    entry,
+                ));
+            }
         }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use workspace_utils::log_msg::LogMsg;
+
+    use super::*;
+    use crate::logs::utils::patch::extract_normalized_entry_from_patch;
+
+    #[tokio::test]
+    async fn test_deprecation_reported_once_on_shared_stderr() {
+        // The path Gemini, Qwen, Opencode, Amp and Copilot normalize their stderr through
+        let msg_store = Arc::new(MsgStore::new());
+        let notice = "(node:4242) [DEP0040] DeprecationWarning: The `punycode` module is \
+                      deprecated.\n";
+        msg_store.push_stderr(notice);
+        msg_store.push_stderr(notice);
+        msg_store.push_finished();
+
+        normalize_stderr_logs(
+            msg_store.clone(),
+            EntryIndexProvider::start_from(&msg_store),
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let events: Vec<AgentEvent> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .filter_map(|(_, entry)| match entry.entry_type {
+                NormalizedEntryType::AgentEvent { event } => Some(event),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            events,
+            vec![AgentEvent::Deprecation {
+                message: "The `punycode` module is deprecated.".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Keeps binary data an agent writes to stdout or stderr out of the log store.
+//!
+//! Raw output is decoded as UTF-8 before it is stored. Invalid byte sequences and control
+//! characters (other than whitespace and ANSI escapes) are binary; each run of them is replaced by
+//! a single marker line carrying its size, which [`detect_binary_output`] reports as
+//! `AgentEvent::BinaryOutput` whichever client wrote it. Short stretches of text inside a binary
+//! blob, such as the strings in an executable, count towards the run rather than splitting it.
+
+use std::sync::Arc;
+
+use futures::{Stream, StreamExt};
+use workspace_utils::msg_store::MsgStore;
+
+use crate::logs::{
+    AgentEvent,
+    utils::{ConversationPatch, EntryIndexProvider},
+};
+
+/// Printable text shorter than this between two binary runs is absorbed into the run.
+const MIN_TEXT_RUN: usize = 32;
+
+const MARKER_PREFIX: &str = "[binary output: ";
+const MARKER_SUFFIX: &str = " bytes]";
+
+/// The line that stands in for `bytes` of binary output.
+pub fn binary_output_marker(bytes: u64) -> String {
+    format!("{MARKER_PREFIX}{bytes}{MARKER_SUFFIX}")
+}
+
+/// Size of the binary run a marker line stands in for.
+pub fn parse_binary_output_marker(line: &str) -> Option<u64> {
+    line.trim()
+        .strip_prefix(MARKER_PREFIX)?
+        .strip_suffix(MARKER_SUFFIX)?
+        .parse()
+        .ok()
+}
+
+/// Add an `AgentEvent::BinaryOutput` entry for each marker line in the stdout and stderr of
+/// `msg_store`.
+pub fn detect_binary_output(msg_store: Arc<MsgStore>) {
+    let entry_index = EntryIndexProvider::start_from(&msg_store);
+    tokio::spawn(async move {
+        let mut lines = futures::stream::select(
+            msg_store.stdout_lines_stream(),
+            msg_store.stderr_lines_stream(),
+        );
+        while let Some(Ok(line)) = lines.next().await {
+            if let Some(bytes) = parse_binary_output_marker(&line) {
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    entry_index.next(),
+                    AgentEvent::BinaryOutput { bytes }.into_normalized_entry(),
+                ));
+            }
+        }
+    });
+}
+
+/// Incremental decoder of raw output into text with binary runs replaced by marker lines.
+#[derive(Debug)]
+pub struct BinaryOutputDetector {
+    /// Start of a UTF-8 sequence split across chunks
+    partial: Vec<u8>,
+    /// Size of the binary run in progress
+    binary: u64,
+    /// Text after the binary run in progress, absorbed into it if more binary follows
+    tail: String,
+    at_line_start: bool,
+}
+
+impl Default for BinaryOutputDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BinaryOutputDetector {
+    pub fn new() -> Self {
+        Self {
+            partial: Vec::new(),
+            binary: 0,
+            tail: String::new(),
+            at_line_start: true,
+        }
+    }
+
+    /// Decode the next chunk. Text that may still turn out to be part of a binary run is held
+    /// back until it is long enough or [`Self::finish`] is called.
+    pub fn push(&mut self, chunk: &[u8]) -> String {
+        let mut out = String::new();
+        let mut bytes = std::mem::take(&mut self.partial);
+        bytes.extend_from_slice(chunk);
+
+        let mut rest = bytes.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(text) => {
+                    self.text(text, &mut out);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    self.text(std::str::from_utf8(valid).unwrap_or_default(), &mut out);
+                    match err.error_len() {
+                        Some(len) => {
+                            self.binary_bytes(len);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            self.partial = invalid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+
+    /// Flush everything held back once the output has ended.
+    pub fn finish(&mut self) -> String {
+        let mut out = String::new();
+        let partial = std::mem::take(&mut self.partial);
+        if !partial.is_empty() {
+            self.binary_bytes(partial.len());
+        }
+        self.flush_binary(&mut out);
+        out
+    }
+
+    fn text(&mut self, text: &str, out: &mut String) {
+        let mut start = 0;
+        for (i, c) in text.char_indices() {
+            if is_binary_char(c) {
+                self.printable(&text[start..i], out);
+                self.binary_bytes(c.len_utf8());
+                start = i + c.len_utf8();
+            }
+        }
+        self.printable(&text[start..], out);
+    }
+
+    fn printable(&mut self, text: &str, out: &mut String) {
+        if text.is_empty() {
+            return;
+        }
+        if self.binary == 0 {
+            self.emit(text, out);
+            return;
+        }
+        self.tail.push_str(text);
+        if self.tail.len() >= MIN_TEXT_RUN {
+            self.flush_binary(out);
+        }
+    }
+
+    fn binary_bytes(&mut self, len: usize) {
+        self.binary += (std::mem::take(&mut self.tail).len() + len) as u64;
+    }
+
+    fn flush_binary(&mut self, out: &mut String) {
+        if self.binary > 0 {
+            if !self.at_line_start {
+                out.push('\n');
+            }
+            out.push_str(&binary_output_marker(std::mem::take(&mut self.binary)));
+            out.push('\n');
+            self.at_line_start = true;
+        }
+        let tail = std::mem::take(&mut self.tail);
+        self.emit(&tail, out);
+    }
+
+    fn emit(&mut self, text: &str, out: &mut String) {
+        if let Some(last) = text.chars().last() {
+            out.push_str(text);
+            self.at_line_start = last == '\n';
+        }
+    }
+}
+
+fn is_binary_char(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\r' | '\t' | '\x1b' | '\x08' | '\x0c')
+}
+
+/// Decode a stream of raw output chunks with a [`BinaryOutputDetector`], skipping chunks that
+/// produce no text yet and flushing the detector when the stream ends.
+pub fn decode_output_stream<S, B>(chunks: S) -> impl Stream<Item = std::io::Result<String>>
+where
+    S: Stream<Item = std::io::Result<B>> + Unpin,
+    B: AsRef<[u8]>,
+{
+    futures::stream::unfold(
+        (chunks, Some(BinaryOutputDetector::new())),
+        |(mut chunks, mut detector)| async move {
+            // The detector is gone once the stream has ended
+            detector.as_ref()?;
+            loop {
+                match chunks.next().await {
+                    Some(Ok(chunk)) => {
+                        let text = detector.as_mut()?.push(chunk.as_ref());
+                        if !text.is_empty() {
+                            return Some((Ok(text), (chunks, detector)));
+                        }
+                    }
+                    Some(Err(err)) => return Some((Err(err), (chunks, detector))),
+                    None => {
+                        let text = detector.take()?.finish();
+                        return (!text.is_empty()).then_some((Ok(text), (chunks, None)));
+                    }
+                }
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn markers(text: &str) -> Vec<u64> {
+        text.lines()
+            .filter_map(parse_binary_output_marker)
+            .collect()
+    }
+
+    #[test]
+    fn test_binary_bytes_replaced_by_single_marker() {
+        let mut binary = vec![0x7f, b'E', b'L', b'F', 0x02, 0x01, 0x01, 0x00];
+        // Pseudo-random bytes, like a compressed or compiled payload
+        let mut seed = 0x2545_f491_u32;
+        binary.extend((0..4096).map(|_| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            (seed >> 16) as u8
+        }));
+        binary.extend([0xff, 0xfe, 0x00, 0xc3]);
+
+        let mut detector = BinaryOutputDetector::new();
+        let mut out = detector.push(b"building...\n");
+        for chunk in binary.chunks(100) {
+            out.push_str(&detector.push(chunk));
+        }
+        out.push_str(&detector.push(b"done, no errors found in the workspace\n"));
+        out.push_str(&detector.finish());
+
+        assert_eq!(markers(&out), vec![binary.len() as u64]);
+        assert!(out.starts_with("building...\n[binary output: "));
+        assert!(out.ends_with(" bytes]\ndone, no errors found in the workspace\n"));
+        assert!(!out.contains('\u{fffd}'));
+        assert!(!out.contains('\0'));
+    }
+
+    #[test]
+    fn test_utf8_split_across_chunks_is_text() {
+        let text = "naïve café — ✓\n".as_bytes();
+        let mut detector = BinaryOutputDetector::new();
+        let mut out = String::new();
+        for chunk in text.chunks(1) {
+            out.push_str(&detector.push(chunk));
+        }
+        out.push_str(&detector.finish());
+
+        assert_eq!(out, "naïve café — ✓\n");
+    }
+
+    #[tokio::test]
+    async fn test_decode_output_stream_flushes_trailing_binary() {
+        let chunks = futures::stream::iter([
+            Ok::<_, std::io::Error>(b"partial line".to_vec()),
+            Ok(vec![0x00, 0x01, 0x02]),
+        ]);
+
+        let out: Vec<String> = decode_output_stream(chunks)
+            .map(Result::unwrap)
+            .collect()
+            .await;
+
+        assert_eq!(out, ["partial line", "\n[binary output: 3 bytes]\n"]);
+    }
+
+    #[tokio::test]
+    async fn test_markers_reported_for_any_client() {
+        use workspace_utils::log_msg::LogMsg;
+
+        use crate::logs::{NormalizedEntryType, utils::patch::extract_normalized_entry_from_patch};
+
+        let msg_store = Arc::new(MsgStore::new());
+        let mut detector = BinaryOutputDetector::new();
+        // Shaped like Codex's JSON-RPC stdout and a binary dump on stderr
+        msg_store.push_stdout(detector.push(b"{\"method\":\"codex/event\"}\n"));
+        msg_store.push_stderr(detector.push(&[0x00; 100]));
+        msg_store.push_stderr(detector.finish());
+        msg_store.push_finished();
+
+        detect_binary_output(msg_store.clone());
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let events: Vec<AgentEvent> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .filter_map(|(_, entry)| match entry.entry_type {
+                NormalizedEntryType::AgentEvent { event } => Some(event),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(events, vec![AgentEvent::BinaryOutput { bytes: 100 }]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Picks remaining credit or token balances out of executor output.
+//!
+//! Clients on metered plans print the balance left after a run, e.g. `Credits remaining: 1,250`
+//! or `Balance: $4.20`. The detector reports a balance whenever it differs from the last one seen.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Each pattern exposes the amount as `remaining` and optionally the unit as `unit` or `currency`.
+static PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // `Credits remaining: 1,250`, `Balance: $4.20`, `Remaining balance: 300 tokens`
+        r"(?i)\b(?:(?P<label>credits?|tokens?)\s+(?:remaining|left)|(?:remaining\s+)?balance)\s*:\s*(?P<currency>\$)?\s*(?P<remaining>\d[\d,]*(?:\.\d+)?)\s*(?P<unit>credits?|tokens?|usd)?\b",
+        // `1,250 credits remaining`, `$4.20 left`
+        r"(?i)(?P<currency>\$)?(?P<remaining>\d[\d,]*(?:\.\d+)?)\s*(?P<unit>credits?|tokens?)?\s+(?:remaining|left)\b",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid credit balance pattern"))
+    .collect()
+});
+
+/// Parse a balance from `line` as `(remaining, unit)`.
+pub fn parse_credit_balance(line: &str) -> Option<(f64, String)> {
+    let line = strip_ansi_escapes::strip_str(line);
+    PATTERNS.iter().find_map(|pattern| {
+        let captures = pattern.captures(&line)?;
+        let remaining = captures["remaining"].replace(',', "").parse::<f64>().ok()?;
+        let unit = if captures.name("currency").is_some() {
+            "USD".to_string()
+        } else {
+            let unit = captures
+                .name("unit")
+                .or_else(|| captures.name("label"))?
+                .as_str()
+                .to_ascii_lowercase();
+            match unit.as_str() {
+                "usd" => "USD".to_string(),
+                _ => unit.trim_end_matches('s').to_string() + "s",
+            }
+        };
+        Some((remaining, unit))
+    })
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CreditBalanceDetector {
+    last: Option<(f64, String)>,
+    partial: String,
+}
+
+impl CreditBalanceDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a raw output chunk, returning balances on the lines it completes that changed.
+    pub fn process_chunk(&mut self, chunk: &str) -> Vec<(f64, String)> {
+        self.partial.push_str(chunk);
+        let Some(last_newline) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial.drain(..=last_newline).collect();
+        let mut balances = Vec::new();
+        for balance in complete.lines().filter_map(parse_credit_balance) {
+            if self.last.as_ref() != Some(&balance) {
+                self.last = Some(balance.clone());
+                balances.push(balance);
+            }
+        }
+        balances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balance_lines() {
+        assert_eq!(
+            parse_credit_balance("Credits remaining: 1,250"),
+            Some((1250.0, "credits".to_string()))
+        );
+        assert_eq!(
+            parse_credit_balance("Balance: $4.20"),
+            Some((4.2, "USD".to_string()))
+        );
+        assert_eq!(
+            parse_credit_balance("You have 300 tokens left this month"),
+            Some((300.0, "tokens".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_lines_without_balance() {
+        assert_eq!(parse_credit_balance("Compiled 3 crates in 2.1s"), None);
+        assert_eq!(parse_credit_balance("2 tests left to run"), None);
+        assert_eq!(
+            parse_credit_balance("Balance the load across workers"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_detector_reports_changes_only() {
+        let mut detector = CreditBalanceDetector::new();
+        assert_eq!(
+            detector.process_chunk("Credits remaining: 10\nCredits remaining: 10\n"),
+            vec![(10.0, "credits".to_string())]
+        );
+        assert_eq!(
+            detector.process_chunk("Credits remaining: 9\n"),
+            vec![(9.0, "credits".to_string())]
+        );
+    }
+}
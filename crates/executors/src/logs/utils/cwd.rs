@@ -0,0 +1,145 @@
+//! Tracks the agent's effective working directory across shell commands.
+//!
+//! Agents may `cd` inside their shell tool and then refer to files with paths relative to the new
+//! directory. The tracker follows `cd` commands and resolves later relative paths back to the
+//! worktree root.
+
+use std::path::{Component, Path, PathBuf};
+
+use workspace_utils::path::make_path_relative;
+
+#[derive(Debug, Clone, Default)]
+pub struct CwdTracker {
+    /// Worktree-relative directory; empty means the worktree root
+    current: PathBuf,
+}
+
+impl CwdTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current directory relative to the worktree, `.` for the root.
+    pub fn current(&self) -> String {
+        display(&self.current)
+    }
+
+    /// Inspect a shell command for a leading `cd <dir>` and follow it.
+    ///
+    /// Returns the new worktree-relative directory when it changed. Targets outside the worktree
+    /// are ignored.
+    pub fn observe_command(&mut self, command: &str, worktree_path: &str) -> Option<String> {
+        let target = parse_cd_target(command)?;
+        let next = if Path::new(&target).is_absolute() {
+            let relative = make_path_relative(&target, worktree_path);
+            if Path::new(&relative).is_absolute() {
+                return None;
+            }
+            normalize(Path::new(&relative))?
+        } else {
+            normalize(&self.current.join(&target))?
+        };
+
+        if next == self.current {
+            return None;
+        }
+        self.current = next;
+        Some(self.current())
+    }
+
+    /// Resolve a relative path emitted by the agent against the tracked directory.
+    pub fn resolve(&self, path: &str) -> String {
+        if self.current.as_os_str().is_empty() || Path::new(path).is_absolute() {
+            return path.to_string();
+        }
+        normalize(&self.current.join(path))
+            .map(|resolved| display(&resolved))
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+/// Extract the directory from a command whose first step is `cd <dir>`.
+fn parse_cd_target(command: &str) -> Option<String> {
+    let first = command
+        .split("&&")
+        .next()?
+        .split([';', '\n'])
+        .next()?
+        .trim();
+    let rest = first.strip_prefix("cd")?;
+    if !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let target = shlex::split(rest.trim())?.into_iter().next()?;
+    // `cd -` and `cd ~` depend on shell state we do not track
+    if target == "-" || target.starts_with('~') {
+        return None;
+    }
+    Some(target)
+}
+
+/// Lexically normalize a worktree-relative path. Returns `None` if it escapes the worktree.
+fn normalize(path: &Path) -> Option<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    return None;
+                }
+            }
+            Component::Normal(part) => normalized.push(part),
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(normalized)
+}
+
+fn display(path: &Path) -> String {
+    if path.as_os_str().is_empty() {
+        ".".to_string()
+    } else {
+        path.to_string_lossy().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cd_then_relative_path() {
+        let mut tracker = CwdTracker::new();
+        assert_eq!(
+            tracker.observe_command("cd crates/server && cargo test", "/repo"),
+            Some("crates/server".to_string())
+        );
+        assert_eq!(tracker.resolve("src/main.rs"), "crates/server/src/main.rs");
+        assert_eq!(tracker.resolve("../utils/lib.rs"), "crates/utils/lib.rs");
+    }
+
+    #[test]
+    fn test_cd_absolute_and_outside_worktree() {
+        let mut tracker = CwdTracker::new();
+        assert_eq!(
+            tracker.observe_command("cd /repo/frontend", "/repo"),
+            Some("frontend".to_string())
+        );
+        assert_eq!(tracker.observe_command("cd /tmp", "/repo"), None);
+        assert_eq!(tracker.observe_command("cd ../..", "/repo"), None);
+        assert_eq!(
+            tracker.observe_command("cd ..", "/repo"),
+            Some(".".to_string())
+        );
+        assert_eq!(tracker.resolve("src/App.tsx"), "src/App.tsx");
+    }
+
+    #[test]
+    fn test_non_cd_commands_ignored() {
+        let mut tracker = CwdTracker::new();
+        assert_eq!(tracker.observe_command("cdk deploy", "/repo"), None);
+        assert_eq!(tracker.observe_command("ls && cd src", "/repo"), None);
+        assert_eq!(tracker.current(), ".");
+    }
+}
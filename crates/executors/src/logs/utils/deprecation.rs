@@ -0,0 +1,134 @@
+//! Picks deprecation notices out of executor output.
+//!
+//! CLIs print deprecation warnings on stderr where they are easily lost among other output. The
+//! detector matches them line by line against a generic set of patterns plus any client-specific
+//! signatures, and reports each distinct message only once.
+
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+
+/// Patterns shared by every client; each exposes the notice text as the `msg` group.
+static COMMON_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Node.js runtime warnings, e.g. `(node:123) [DEP0040] DeprecationWarning: ...`
+        r"DeprecationWarning:\s*(?P<msg>.+)$",
+        // npm install noise, e.g. `npm warn deprecated glob@7.2.3: ...`
+        r"(?i)^npm warn deprecated\s+(?P<msg>.+)$",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid deprecation pattern"))
+    .collect()
+});
+
+#[derive(Debug, Clone)]
+pub struct DeprecationDetector {
+    patterns: Vec<Regex>,
+    seen: HashSet<String>,
+    partial: String,
+}
+
+impl Default for DeprecationDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeprecationDetector {
+    pub fn new() -> Self {
+        Self {
+            patterns: COMMON_PATTERNS.clone(),
+            seen: HashSet::new(),
+            partial: String::new(),
+        }
+    }
+
+    /// Add client-specific signatures. Each pattern must define a `msg` capture group.
+    pub fn with_patterns(mut self, patterns: &[&str]) -> Self {
+        self.patterns.extend(
+            patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("valid deprecation pattern")),
+        );
+        self
+    }
+
+    /// Return the notice in `line` if it is a deprecation that has not been reported yet.
+    pub fn detect(&mut self, line: &str) -> Option<String> {
+        let line = strip_ansi_escapes::strip_str(line);
+        let line = line.trim();
+        let message = self.patterns.iter().find_map(|pattern| {
+            pattern
+                .captures(line)
+                .and_then(|captures| captures.name("msg"))
+                .map(|msg| msg.as_str().trim().to_string())
+        })?;
+        if message.is_empty() || !self.seen.insert(message.clone()) {
+            return None;
+        }
+        Some(message)
+    }
+
+    /// Feed a raw output chunk, returning new notices found on the lines it completes.
+    pub fn process_chunk(&mut self, chunk: &str) -> Vec<String> {
+        self.partial.push_str(chunk);
+        let Some(last_newline) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial.drain(..=last_newline).collect();
+        complete
+            .lines()
+            .filter_map(|line| self.detect(line))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_deprecation_reported_once() {
+        let mut detector = DeprecationDetector::new();
+        let line =
+            "(node:4242) [DEP0040] DeprecationWarning: The `punycode` module is deprecated.\n";
+
+        let first = detector.process_chunk(line);
+        let second = detector.process_chunk(line);
+
+        assert_eq!(
+            first,
+            vec!["The `punycode` module is deprecated.".to_string()]
+        );
+        assert!(second.is_empty());
+    }
+
+    #[test]
+    fn test_line_split_across_chunks() {
+        let mut detector = DeprecationDetector::new();
+        assert!(
+            detector
+                .process_chunk("npm warn deprecated inflight@1.0.6: leaks ")
+                .is_empty()
+        );
+        assert_eq!(
+            detector.process_chunk("memory\nunrelated output\n"),
+            vec!["inflight@1.0.6: leaks memory".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_client_patterns_and_unrelated_lines() {
+        let mut detector = DeprecationDetector::new()
+            .with_patterns(&[r"(?i)^warning:\s*(?P<msg>.*\bdeprecated\b.*)$"]);
+        assert_eq!(
+            detector.detect("Warning: --foo is deprecated, use --bar"),
+            Some("--foo is deprecated, use --bar".to_string())
+        );
+        assert_eq!(detector.detect("Error: something failed"), None);
+        assert_eq!(
+            DeprecationDetector::new().detect("Warning: --foo is deprecated"),
+            None
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Agent-level events derived from the normalized entries of any executor.
+//!
+//! Each client's normalizer turns its own stream format into [`NormalizedEntry`]s.
+//! [`detect_entry_events`] installs a patch filter that watches those entries as they are stored
+//! and adds the events that depend only on them, so Claude, Codex, Gemini, Opencode and the other
+//! clients all report them the same way:
+//!
+//! - `FileRead` for each distinct file a tool call reads
+//! - `OutOfBoundsAccess` for each path outside the worktree a command or edit reaches
+//! - `ProposedCommand` for each `About to run: <cmd>` line of an assistant message, linked to the
+//!   tool call that runs it, and `RepeatedAction` when the same command is announced in a row
+//! - `UncertaintyFlag` for each assistant message in which the agent hedges
+//!
+//! Events follow the entry that gave rise to them and draw their indices from the store's shared
+//! counter. Assistant messages may be streamed into their entry, so only their complete lines and
+//! sentences are scanned until another entry follows.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use json_patch::{Patch, PatchOperation};
+use serde_json::from_value;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::logs::{
+    ActionType, AgentEvent, NormalizedEntry, NormalizedEntryType,
+    utils::{
+        ConversationPatch, EntryIndexProvider, FileReadTracker, OutOfBoundsDetector,
+        ProposedCommandTracker, RepeatedActionDetector, detect_uncertainty,
+        proposed_command::parse_proposed_command,
+    },
+};
+
+/// Add the events derived from every normalized entry pushed to `msg_store` from now on, for an
+/// agent running in `worktree_path`.
+pub fn detect_entry_events(msg_store: &MsgStore, worktree_path: &Path) {
+    let detector = Mutex::new(EntryEventDetector::new(
+        worktree_path,
+        EntryIndexProvider::start_from(msg_store),
+    ));
+    msg_store.add_patch_filter(Arc::new(move |patch| {
+        let events = detector
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .observe(&patch);
+        std::iter::once(patch).chain(events).collect()
+    }));
+}
+
+#[derive(Debug)]
+pub struct EntryEventDetector {
+    worktree_path: String,
+    entry_index: EntryIndexProvider,
+    file_reads: FileReadTracker,
+    out_of_bounds: OutOfBoundsDetector,
+    proposed_commands: ProposedCommandTracker,
+    repeated_proposals: RepeatedActionDetector,
+    /// Latest assistant message and its content, until another entry follows it
+    message: Option<(usize, String)>,
+    /// Lines of each assistant message already scanned
+    lines_seen: HashMap<usize, usize>,
+    /// Assistant messages already flagged as uncertain
+    uncertain: HashSet<usize>,
+    /// Tool calls already matched against the announced commands
+    tool_calls: HashSet<String>,
+}
+
+impl EntryEventDetector {
+    pub fn new(worktree_path: &Path, entry_index: EntryIndexProvider) -> Self {
+        Self {
+            worktree_path: worktree_path.to_string_lossy().to_string(),
+            entry_index,
+            file_reads: FileReadTracker::new(),
+            out_of_bounds: OutOfBoundsDetector::new(),
+            proposed_commands: ProposedCommandTracker::new(),
+            repeated_proposals: RepeatedActionDetector::new(),
+            message: None,
+            lines_seen: HashMap::new(),
+            uncertain: HashSet::new(),
+            tool_calls: HashSet::new(),
+        }
+    }
+
+    /// Patches adding or updating the events for the entries `patch` adds or replaces.
+    pub fn observe(&mut self, patch: &Patch) -> Vec<Patch> {
+        let mut patches = Vec::new();
+        for (index, entry) in written_entries(patch) {
+            if let Some((message, content)) = self.message.take_if(|(message, _)| *message != index)
+            {
+                patches.extend(self.scan_message(message, &content, true));
+            }
+            match &entry.entry_type {
+                NormalizedEntryType::AssistantMessage => {
+                    patches.extend(self.scan_message(index, &entry.content, false));
+                    self.message = Some((index, entry.content));
+                }
+                NormalizedEntryType::ToolUse { action_type, .. } => {
+                    let events = self
+                        .file_reads
+                        .observe(action_type)
+                        .into_iter()
+                        .chain(self.out_of_bounds.observe(action_type, &self.worktree_path))
+                        .collect::<Vec<_>>();
+                    patches.extend(events.into_iter().map(|event| self.add(event)));
+                    if let ActionType::CommandRun { command, .. } = action_type
+                        && let Some(tool_call_id) = entry
+                            .metadata
+                            .as_ref()
+                            .and_then(|metadata| metadata.get("tool_call_id")?.as_str())
+                        && self.tool_calls.insert(tool_call_id.to_string())
+                        && let Some((index, event)) =
+                            self.proposed_commands.correlate(tool_call_id, command)
+                    {
+                        patches.push(ConversationPatch::replace(
+                            index,
+                            event.into_normalized_entry(),
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+        patches
+    }
+
+    /// Flag a hedge in assistant message `index` and announce the commands in its lines not scanned
+    /// yet. The last line may still be streaming, so commands in it are only announced once the
+    /// message is `settled`.
+    fn scan_message(&mut self, index: usize, content: &str, settled: bool) -> Vec<Patch> {
+        let mut lines: Vec<&str> = content.split('\n').collect();
+        if !settled {
+            lines.pop();
+        }
+        let seen = self.lines_seen.entry(index).or_default();
+        let start = (*seen).min(lines.len());
+        *seen = (*seen).max(lines.len());
+
+        // A hedge in the streaming line is flagged once its sentence is complete
+        let complete = if settled {
+            content
+        } else {
+            &content[..content
+                .rfind(['\n', '.', '!', '?'])
+                .map_or(0, |end| end + 1)]
+        };
+
+        let mut patches = Vec::new();
+        if !self.uncertain.contains(&index)
+            && let Some(excerpt) = detect_uncertainty(complete)
+        {
+            self.uncertain.insert(index);
+            patches.push(self.add(AgentEvent::UncertaintyFlag {
+                excerpt,
+                entry_index: index,
+            }));
+        }
+        for command in lines[start..]
+            .iter()
+            .filter_map(|line| parse_proposed_command(line))
+        {
+            let repeated = self.repeated_proposals.observe(&command, &command);
+            let event = AgentEvent::ProposedCommand {
+                command: command.clone(),
+                tool_call_id: None,
+            };
+            let idx = self.entry_index.next();
+            self.proposed_commands.record(command, idx);
+            patches.push(ConversationPatch::add_normalized_entry(
+                idx,
+                event.into_normalized_entry(),
+            ));
+            patches.extend(repeated.map(|event| self.add(event)));
+        }
+        patches
+    }
+
+    fn add(&self, event: AgentEvent) -> Patch {
+        ConversationPatch::add_normalized_entry(
+            self.entry_index.next(),
+            event.into_normalized_entry(),
+        )
+    }
+}
+
+/// The normalized entries `patch` adds or replaces, with their index.
+fn written_entries(patch: &Patch) -> Vec<(usize, NormalizedEntry)> {
+    patch
+        .iter()
+        .filter_map(|op| match op {
+            PatchOperation::Add(add) => Some((&add.path, &add.value)),
+            PatchOperation::Replace(replace) => Some((&replace.path, &replace.value)),
+            _ => None,
+        })
+        .filter_map(|(path, value)| {
+            let index = path.strip_prefix("/entries/")?.parse().ok()?;
+            if value.get("type")?.as_str()? != "NORMALIZED_ENTRY" {
+                return None;
+            }
+            let entry = from_value(value.get("content")?.clone()).ok()?;
+            Some((index, entry))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use workspace_utils::log_msg::LogMsg;
+
+    use super::*;
+    use crate::logs::{
+        ActionType, FileChange, ToolStatus, utils::patch::extract_normalized_entry_from_patch,
+    };
+
+    const WORKTREE: &str = "/tmp/work";
+
+    fn tool_use(tool_name: &str, action_type: ActionType) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::ToolUse {
+                tool_name: tool_name.to_string(),
+                action_type,
+                status: ToolStatus::Created,
+            },
+            content: String::new(),
+            metadata: None,
+        }
+    }
+
+    fn read(path: &str) -> ActionType {
+        ActionType::FileRead {
+            path: path.to_string(),
+        }
+    }
+
+    /// Events in `store`, in entry order.
+    fn events(store: &MsgStore) -> Vec<AgentEvent> {
+        let entries: BTreeMap<usize, NormalizedEntry> = store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .collect();
+        entries
+            .into_values()
+            .filter_map(|entry| match entry.entry_type {
+                NormalizedEntryType::AgentEvent { event } => Some(event),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_file_reads_reported_once_for_any_client() {
+        let store = MsgStore::new();
+        detect_entry_events(&store, Path::new(WORKTREE));
+        let entry_index = EntryIndexProvider::start_from(&store);
+
+        // Shaped like Codex's view_image call and Gemini's read_file tool call
+        for entry in [
+            tool_use("view_image", read("assets/logo.png")),
+            tool_use("read_file", read("src/main.rs")),
+            tool_use("read_file", read("src/main.rs")),
+        ] {
+            store.push_patch(ConversationPatch::add_normalized_entry(
+                entry_index.next(),
+                entry,
+            ));
+        }
+        // A status update to an existing call is not a new read
+        store.push_patch(ConversationPatch::replace(
+            2,
+            tool_use("read_file", read("src/main.rs")),
+        ));
+
+        assert_eq!(
+            events(&store),
+            vec![
+                AgentEvent::FileRead {
+                    path: "assets/logo.png".to_string()
+                },
+                AgentEvent::FileRead {
+                    path: "src/main.rs".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_edits_and_events_are_not_reads() {
+        let mut detector =
+            EntryEventDetector::new(Path::new(WORKTREE), EntryIndexProvider::test_new());
+        let edit = tool_use(
+            "edit",
+            ActionType::FileEdit {
+                path: "src/main.rs".to_string(),
+                changes: vec![],
+            },
+        );
+        let event = AgentEvent::FileRead {
+            path: "src/main.rs".to_string(),
+        }
+        .into_normalized_entry();
+
+        assert!(
+            detector
+                .observe(&ConversationPatch::add_normalized_entry(0, edit))
+                .is_empty()
+        );
+        assert!(
+            detector
+                .observe(&ConversationPatch::add_normalized_entry(1, event))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds_commands_and_edits_warned_for_any_client() {
+        let store = MsgStore::new();
+        detect_entry_events(&store, Path::new(WORKTREE));
+        let entry_index = EntryIndexProvider::start_from(&store);
+        let command = |command: &str| ActionType::CommandRun {
+            command: command.to_string(),
+            result: None,
+        };
+        let edit = |path: &str| ActionType::FileEdit {
+            path: path.to_string(),
+            changes: vec![FileChange::Write {
+                content: "x".to_string(),
+            }],
+        };
+
+        // Shaped like Codex's exec and apply_patch calls and Opencode's bash tool
+        for entry in [
+            tool_use("exec", command("cat /tmp/work/src/main.rs ../other/.env")),
+            tool_use("apply_patch", edit("src/lib.rs")),
+            tool_use("apply_patch", edit("/etc/hosts")),
+            tool_use("bash", command("cp ../other/.env .env 2>/dev/null")),
+        ] {
+            store.push_patch(ConversationPatch::add_normalized_entry(
+                entry_index.next(),
+                entry,
+            ));
+        }
+
+        assert_eq!(
+            events(&store),
+            vec![
+                AgentEvent::OutOfBoundsAccess {
+                    path: "../other/.env".to_string()
+                },
+                AgentEvent::OutOfBoundsAccess {
+                    path: "/etc/hosts".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streamed_proposed_command_correlates_with_tool_call() {
+        let store = MsgStore::new();
+        detect_entry_events(&store, Path::new(WORKTREE));
+        let entry_index = EntryIndexProvider::start_from(&store);
+        let message = |content: &str| NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: content.to_string(),
+            metadata: None,
+        };
+        let mut exec = tool_use(
+            "exec",
+            ActionType::CommandRun {
+                command: "npm test".to_string(),
+                result: None,
+            },
+        );
+        exec.metadata = Some(serde_json::json!({ "tool_call_id": "call-1" }));
+
+        // Shaped like Codex streaming a message into its entry before running the command
+        let index = entry_index.next();
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            index,
+            message("About to run: `npm"),
+        ));
+        store.push_patch(ConversationPatch::replace(
+            index,
+            message("About to run: `npm test`"),
+        ));
+        assert!(events(&store).is_empty());
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            entry_index.next(),
+            exec.clone(),
+        ));
+        // A status update to the same call does not claim another announcement
+        store.push_patch(ConversationPatch::replace(1, exec));
+
+        assert_eq!(
+            events(&store),
+            vec![AgentEvent::ProposedCommand {
+                command: "npm test".to_string(),
+                tool_call_id: Some("call-1".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_uncertainty_flagged_once_per_message() {
+        let store = MsgStore::new();
+        detect_entry_events(&store, Path::new(WORKTREE));
+        let index = EntryIndexProvider::start_from(&store).next();
+        let message = |content: &str| NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: content.to_string(),
+            metadata: None,
+        };
+
+        // Shaped like Gemini streaming its final answer, with nothing after it
+        let excerpt = "I couldn't verify the migration because the DB is down.";
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            index,
+            message("Done. I couldn't verify"),
+        ));
+        store.push_patch(ConversationPatch::replace(
+            index,
+            message(&format!("Done. {excerpt}")),
+        ));
+        store.push_patch(ConversationPatch::replace(
+            index,
+            message(&format!(
+                "Done. {excerpt}\nI'm not sure about the rest either."
+            )),
+        ));
+
+        assert_eq!(
+            events(&store),
+            vec![AgentEvent::UncertaintyFlag {
+                excerpt: excerpt.to_string(),
+                entry_index: index,
+            }]
+        );
+    }
+}
@@ -32,10 +32,11 @@ impl EntryIndexProvider {
         self.0.store(0, Ordering::Relaxed);
     }
 
-    /// Create a provider starting from the maximum existing normalized-entry index
-    /// observed in prior JSON patches in `MsgStore`.
+    /// Create a provider continuing after the maximum normalized-entry index observed in prior
+    /// JSON patches in `MsgStore`. Providers for the same store share one counter, so entries
+    /// added by different writers never reuse an index.
     pub fn start_from(msg_store: &MsgStore) -> Self {
-        let provider = EntryIndexProvider::new();
+        let provider = EntryIndexProvider(msg_store.entry_index_counter());
 
         let max_index: Option<usize> = msg_store
             .get_history()
@@ -58,7 +59,7 @@ impl EntryIndexProvider {
             .max();
 
         let start_at = max_index.map_or(0, |n| n.saturating_add(1));
-        provider.0.store(start_at, Ordering::Relaxed);
+        provider.0.fetch_max(start_at, Ordering::Relaxed);
         provider
     }
 }
@@ -110,4 +111,28 @@ mod tests {
         provider.next();
         assert_eq!(provider.current(), 2);
     }
+
+    #[test]
+    fn test_providers_for_one_store_share_indices() {
+        use crate::logs::{NormalizedEntry, NormalizedEntryType, utils::ConversationPatch};
+
+        let store = MsgStore::new();
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            4,
+            NormalizedEntry {
+                timestamp: None,
+                entry_type: NormalizedEntryType::SystemMessage,
+                content: "earlier".to_string(),
+                metadata: None,
+            },
+        ));
+
+        let normalizer = EntryIndexProvider::start_from(&store);
+        let late_writer = EntryIndexProvider::start_from(&store);
+        assert_eq!(normalizer.next(), 5);
+        assert_eq!(late_writer.next(), 6);
+        assert_eq!(normalizer.next(), 7);
+
+        assert_eq!(EntryIndexProvider::start_from(&MsgStore::new()).next(), 0);
+    }
 }
@@ -0,0 +1,67 @@
+//! Records which files an agent read, for privacy auditing.
+//!
+//! Each distinct worktree-relative path is reported once per stream as `AgentEvent::FileRead`.
+//! Only read actions count; edits are already visible as `FileEdit` tool calls.
+
+use std::collections::HashSet;
+
+use crate::logs::{ActionType, AgentEvent};
+
+#[derive(Debug, Clone, Default)]
+pub struct FileReadTracker {
+    seen: HashSet<String>,
+}
+
+impl FileReadTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Event for a `FileRead` action whose path has not been read before in this stream.
+    pub fn observe(&mut self, action_type: &ActionType) -> Option<AgentEvent> {
+        match action_type {
+            ActionType::FileRead { path } if !path.is_empty() && self.seen.insert(path.clone()) => {
+                Some(AgentEvent::FileRead { path: path.clone() })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &str) -> ActionType {
+        ActionType::FileRead {
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_repeated_reads_are_reported_once() {
+        let mut tracker = FileReadTracker::new();
+
+        assert_eq!(
+            tracker.observe(&read("src/main.rs")),
+            Some(AgentEvent::FileRead {
+                path: "src/main.rs".to_string()
+            })
+        );
+        assert_eq!(tracker.observe(&read("src/main.rs")), None);
+        assert!(tracker.observe(&read("src/lib.rs")).is_some());
+        assert_eq!(tracker.observe(&read("src/lib.rs")), None);
+    }
+
+    #[test]
+    fn test_edits_are_not_reads() {
+        let mut tracker = FileReadTracker::new();
+        let edit = ActionType::FileEdit {
+            path: "src/main.rs".to_string(),
+            changes: vec![],
+        };
+
+        assert_eq!(tracker.observe(&edit), None);
+        assert!(tracker.observe(&read("src/main.rs")).is_some());
+    }
+}
@@ -0,0 +1,185 @@
+//! Reads warning/error counts from linter and formatter summaries in command output.
+//!
+//! Recognizes the summaries printed by clippy, eslint and ruff. Cargo prints the same summary
+//! lines for plain builds, so they are only attributed to clippy when the output mentions it
+//! (lint names such as `clippy::needless_return`, or the `cargo clippy --fix` hint). Counts a
+//! tool did not report are left unset, and per-crate clippy summaries are added together.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::AgentEvent;
+
+/// clippy: ``warning: `executors` (lib) generated 3 warnings (run `cargo clippy --fix ...`)``
+static CLIPPY_WARNINGS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^warning: `[^`]+`(?: \([^)]+\))? generated (\d+) warnings?\b").unwrap()
+});
+
+/// clippy: ``error: could not compile `executors` (lib) due to 2 previous errors; 1 warning emitted``
+static CLIPPY_FAILED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^error: could not compile `[^`]+`(?: \([^)]+\))? due to (\d+) previous errors?(?:; (\d+) warnings? emitted)?",
+    )
+    .unwrap()
+});
+
+/// eslint: `✖ 5 problems (2 errors, 3 warnings)`
+static ESLINT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^✖ \d+ problems? \((\d+) errors?, (\d+) warnings?\)").unwrap());
+
+/// ruff: `Found 3 errors.` or `Found 3 errors (1 fixed, 2 remaining).`
+static RUFF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^Found (\d+) errors?(?:\.| \()").unwrap());
+
+const RUFF_CLEAN: &str = "All checks passed!";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintSummary {
+    pub tool: &'static str,
+    pub warnings: Option<u32>,
+    pub errors: Option<u32>,
+}
+
+impl LintSummary {
+    fn new(tool: &'static str) -> Self {
+        Self {
+            tool,
+            warnings: None,
+            errors: None,
+        }
+    }
+
+    /// One summary per linter that reported in `output`, in the order they first appear.
+    pub fn parse(output: &str) -> Vec<Self> {
+        let mentions_clippy = output.contains("clippy");
+        let mut summaries: Vec<Self> = Vec::new();
+        for line in output.lines() {
+            let line = strip_ansi_escapes::strip_str(line);
+            let line = line.trim();
+            let (tool, warnings, errors) = if let Some(captures) = CLIPPY_WARNINGS.captures(line)
+                && mentions_clippy
+            {
+                ("clippy", count(captures.get(1)), None)
+            } else if let Some(captures) = CLIPPY_FAILED.captures(line)
+                && mentions_clippy
+            {
+                ("clippy", count(captures.get(2)), count(captures.get(1)))
+            } else if let Some(captures) = ESLINT.captures(line) {
+                ("eslint", count(captures.get(2)), count(captures.get(1)))
+            } else if let Some(captures) = RUFF.captures(line) {
+                ("ruff", None, count(captures.get(1)))
+            } else if line == RUFF_CLEAN {
+                ("ruff", None, Some(0))
+            } else {
+                continue;
+            };
+
+            let index = match summaries.iter().position(|summary| summary.tool == tool) {
+                Some(index) => index,
+                None => {
+                    summaries.push(Self::new(tool));
+                    summaries.len() - 1
+                }
+            };
+            let summary = &mut summaries[index];
+            for (slot, count) in [
+                (&mut summary.warnings, warnings),
+                (&mut summary.errors, errors),
+            ] {
+                if let Some(count) = count {
+                    *slot = Some(slot.unwrap_or(0) + count);
+                }
+            }
+        }
+        summaries
+    }
+
+    pub fn into_event(self) -> AgentEvent {
+        AgentEvent::LintResults {
+            tool: self.tool.to_string(),
+            warnings: self.warnings,
+            errors: self.errors,
+        }
+    }
+}
+
+fn count(capture: Option<regex::Match<'_>>) -> Option<u32> {
+    capture.and_then(|capture| capture.as_str().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clippy_summaries_are_summed() {
+        let output = "\
+    Checking executors v0.1.0 (/repo/crates/executors)
+warning: unneeded `return` statement
+  --> crates/executors/src/lib.rs:10:5
+   = help: for further information visit https://rust-lang.github.io/rust-clippy/master/index.html#needless_return
+   = note: `#[warn(clippy::needless_return)]` on by default
+warning: `executors` (lib) generated 3 warnings (run `cargo clippy --fix --lib -p executors` to apply 1 suggestion)
+error: could not compile `server` (bin \"server\") due to 2 previous errors; 1 warning emitted
+";
+        assert_eq!(
+            LintSummary::parse(output),
+            vec![LintSummary {
+                tool: "clippy",
+                warnings: Some(4),
+                errors: Some(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_eslint_summary() {
+        let output = "\
+/repo/frontend/src/App.tsx
+  12:7  error    'unused' is assigned a value but never used  @typescript-eslint/no-unused-vars
+  20:1  warning  Unexpected console statement                 no-console
+
+✖ 5 problems (2 errors, 3 warnings)
+  1 error and 0 warnings potentially fixable with the `--fix` option.
+";
+        assert_eq!(
+            LintSummary::parse(output),
+            vec![LintSummary {
+                tool: "eslint",
+                warnings: Some(3),
+                errors: Some(2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_partial_counts_are_left_unset() {
+        assert_eq!(
+            LintSummary::parse(
+                "= note: `#[warn(clippy::needless_return)]` on by default\n\
+                 warning: `executors` (lib) generated 1 warning"
+            ),
+            vec![LintSummary {
+                tool: "clippy",
+                warnings: Some(1),
+                errors: None,
+            }]
+        );
+        assert_eq!(
+            LintSummary::parse("src/app.py:1:8: F401 `os` imported but unused\nFound 1 error."),
+            vec![LintSummary {
+                tool: "ruff",
+                warnings: None,
+                errors: Some(1),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_plain_cargo_build_is_not_clippy() {
+        let output = "warning: `executors` (lib) generated 2 warnings\n\
+                      Found 3 errors in 2 files (checked 10 source files)";
+        assert!(LintSummary::parse(output).is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+//! Picks MCP server startup and connection failures out of executor output.
+//!
+//! A server that fails to start usually only costs the agent some tools, so clients log the error
+//! and carry on. The detector matches those log lines against a generic set of patterns plus any
+//! client-specific signatures, and reports each failing server only once.
+
+use std::{collections::HashSet, sync::LazyLock};
+
+use regex::Regex;
+
+/// Patterns shared by every client; each exposes the server as `name` and the cause as `error`.
+static COMMON_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Gemini CLI and other ACP clients, e.g. `Error connecting to MCP server 'docs': ...`
+        r#"(?i)error connecting to mcp server ['"`]?(?P<name>[^'"`:\s]+)['"`]?:\s*(?P<error>.+)$"#,
+        // `MCP server "docs" failed to start: ...`
+        r#"(?i)mcp server ['"`]?(?P<name>[^'"`:\s]+)['"`]? failed to start:\s*(?P<error>.+)$"#,
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid MCP failure pattern"))
+    .collect()
+});
+
+#[derive(Debug, Clone)]
+pub struct McpFailureDetector {
+    patterns: Vec<Regex>,
+    seen: HashSet<String>,
+    partial: String,
+}
+
+impl Default for McpFailureDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl McpFailureDetector {
+    pub fn new() -> Self {
+        Self {
+            patterns: COMMON_PATTERNS.clone(),
+            seen: HashSet::new(),
+            partial: String::new(),
+        }
+    }
+
+    /// Add client-specific signatures. Each pattern must define `name` and `error` groups.
+    pub fn with_patterns(mut self, patterns: &[&str]) -> Self {
+        self.patterns.extend(
+            patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("valid MCP failure pattern")),
+        );
+        self
+    }
+
+    /// Return `(server, error)` if `line` reports a server that has not failed before.
+    pub fn detect(&mut self, line: &str) -> Option<(String, String)> {
+        let line = strip_ansi_escapes::strip_str(line);
+        let line = line.trim();
+        let (name, error) = self.patterns.iter().find_map(|pattern| {
+            let captures = pattern.captures(line)?;
+            Some((
+                captures.name("name")?.as_str().trim().to_string(),
+                captures.name("error")?.as_str().trim().to_string(),
+            ))
+        })?;
+        if name.is_empty() || !self.seen.insert(name.clone()) {
+            return None;
+        }
+        Some((name, error))
+    }
+
+    /// Feed a raw output chunk, returning new failures found on the lines it completes.
+    pub fn process_chunk(&mut self, chunk: &str) -> Vec<(String, String)> {
+        self.partial.push_str(chunk);
+        let Some(last_newline) = self.partial.rfind('\n') else {
+            return Vec::new();
+        };
+        let complete: String = self.partial.drain(..=last_newline).collect();
+        complete
+            .lines()
+            .filter_map(|line| self.detect(line))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_connection_failure_extracts_server_name() {
+        let mut detector = McpFailureDetector::new();
+        let line = "Error connecting to MCP server 'playwright': spawn npx ENOENT\n";
+
+        assert_eq!(
+            detector.process_chunk(line),
+            vec![("playwright".to_string(), "spawn npx ENOENT".to_string())]
+        );
+        assert!(detector.process_chunk(line).is_empty());
+    }
+
+    #[test]
+    fn test_client_patterns_and_unrelated_lines() {
+        let mut detector = McpFailureDetector::new()
+            .with_patterns(&[r"MCP client for `(?P<name>[^`]+)` failed to start: (?P<error>.+)$"]);
+
+        assert_eq!(
+            detector.detect("ERROR codex_core: MCP client for `docs` failed to start: timed out"),
+            Some(("docs".to_string(), "timed out".to_string()))
+        );
+        assert_eq!(detector.detect("Connected to MCP server 'docs'"), None);
+    }
+}
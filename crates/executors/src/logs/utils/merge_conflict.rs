@@ -0,0 +1,141 @@
+//! Notices merge conflicts in the output of commands an agent runs.
+//!
+//! Recognizes git's `CONFLICT (...)` lines from merge, rebase, cherry-pick and stash, the
+//! "Automatic merge failed" / "could not apply" notices, unmerged paths in `git status` (long and
+//! porcelain formats) and conflict markers, e.g. from `grep -n '<<<<<<<'`. The affected paths are
+//! collected in order where the output names them.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::AgentEvent;
+
+/// `CONFLICT (content): Merge conflict in src/lib.rs`, also for add/add and submodules.
+static CONFLICT_IN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^CONFLICT \([^)]+\): Merge conflict in (.+)$").expect("valid conflict pattern")
+});
+
+/// `CONFLICT (modify/delete): src/old.rs deleted in HEAD and modified in feature. ...`
+static CONFLICT_DELETED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^CONFLICT \([^)]+\): (\S+) deleted in ").expect("valid delete conflict pattern")
+});
+
+/// Notices that a conflict happened without naming the files.
+static CONFLICT_NOTICE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:CONFLICT \(|Automatic merge failed; fix conflicts|error: could not apply |hint: Resolve all conflicts manually)")
+        .expect("valid conflict notice pattern")
+});
+
+/// `git status` unmerged paths: `both modified:   src/lib.rs`.
+static STATUS_UNMERGED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:both modified|both added|both deleted|added by us|added by them|deleted by us|deleted by them):\s+(.+)$")
+        .expect("valid status pattern")
+});
+
+/// `git status --porcelain` unmerged paths: `UU src/lib.rs`.
+static PORCELAIN_UNMERGED: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:UU|AA|DD|AU|UA|DU|UD) (.+)$").expect("valid porcelain pattern")
+});
+
+/// A conflict marker, optionally prefixed by grep's `path:line:`.
+static MARKER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:([^:\s]+):(?:\d+:)?)?<{7}(?: |$)").expect("valid conflict marker pattern")
+});
+
+/// A `MergeConflict` event if `output` reports a conflict.
+pub fn detect_merge_conflict(output: &str) -> Option<AgentEvent> {
+    let mut found = false;
+    let mut files: Vec<String> = Vec::new();
+
+    for line in output.lines() {
+        let line = strip_ansi_escapes::strip_str(line);
+        // Porcelain status lines are meaningful with their leading status columns
+        let line = line.trim_end();
+        let path = if let Some(caps) = PORCELAIN_UNMERGED.captures(line) {
+            Some(caps[1].to_string())
+        } else {
+            let line = line.trim_start();
+            if let Some(caps) = CONFLICT_IN
+                .captures(line)
+                .or_else(|| CONFLICT_DELETED.captures(line))
+                .or_else(|| STATUS_UNMERGED.captures(line))
+            {
+                Some(caps[1].to_string())
+            } else if let Some(caps) = MARKER.captures(line) {
+                found = true;
+                caps.get(1).map(|path| path.as_str().to_string())
+            } else {
+                found |= CONFLICT_NOTICE.is_match(line);
+                None
+            }
+        };
+        if let Some(path) = path {
+            found = true;
+            let path = path.trim().trim_matches('"').to_string();
+            if !files.contains(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    found.then_some(AgentEvent::MergeConflict { files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_notice_lists_files() {
+        let output = "Auto-merging src/lib.rs\n\
+            CONFLICT (content): Merge conflict in src/lib.rs\n\
+            Auto-merging README.md\n\
+            CONFLICT (modify/delete): docs/old.md deleted in HEAD and modified in feature. \
+            Version feature of docs/old.md left in tree.\n\
+            Automatic merge failed; fix conflicts and then commit the result.\n";
+
+        assert_eq!(
+            detect_merge_conflict(output),
+            Some(AgentEvent::MergeConflict {
+                files: vec!["src/lib.rs".to_string(), "docs/old.md".to_string()],
+            })
+        );
+
+        let status = "On branch main\nYou have unmerged paths.\n\n\
+            Unmerged paths:\n\
+            \tboth modified:   src/lib.rs\n\
+            \tdeleted by them: src/old.rs\n";
+        assert_eq!(
+            detect_merge_conflict(status),
+            Some(AgentEvent::MergeConflict {
+                files: vec!["src/lib.rs".to_string(), "src/old.rs".to_string()],
+            })
+        );
+        assert_eq!(
+            detect_merge_conflict("UU src/lib.rs\n M src/main.rs\nAA Cargo.lock\n"),
+            Some(AgentEvent::MergeConflict {
+                files: vec!["src/lib.rs".to_string(), "Cargo.lock".to_string()],
+            })
+        );
+    }
+
+    #[test]
+    fn test_conflict_markers_and_clean_output() {
+        assert_eq!(
+            detect_merge_conflict("src/lib.rs:12:<<<<<<< HEAD\nsrc/lib.rs:20:>>>>>>> feature\n"),
+            Some(AgentEvent::MergeConflict {
+                files: vec!["src/lib.rs".to_string()],
+            })
+        );
+        // A rebase that stops without naming files is still a conflict
+        assert_eq!(
+            detect_merge_conflict("error: could not apply 1a2b3c4... Fix watcher\n"),
+            Some(AgentEvent::MergeConflict { files: vec![] })
+        );
+        assert_eq!(
+            detect_merge_conflict("Auto-merging src/lib.rs\nMerge made by the 'ort' strategy.\n"),
+            None
+        );
+    }
+}
@@ -1,7 +1,56 @@
 //! Utility modules for executor framework
 
+pub mod binary_output;
+pub mod credit_balance;
+pub mod cwd;
+pub mod deprecation;
+pub mod entry_events;
 pub mod entry_index;
+pub mod file_read;
+pub mod lint_results;
+pub mod mcp_failure;
+pub mod merge_conflict;
+pub mod model_error;
+pub mod out_of_bounds;
 pub mod patch;
+pub mod pretty_json;
+pub mod proposed_command;
+pub mod proposed_edit;
+pub mod question;
+pub mod repeated_action;
+pub mod scratchpad;
+pub mod secret_redaction;
+pub mod setup_instruction;
+pub mod suggested_actions;
+pub mod test_results;
+pub mod timestamp;
+pub mod turn_end;
+pub mod uncertainty;
+pub mod unified_diff;
 
+pub use binary_output::{BinaryOutputDetector, detect_binary_output, parse_binary_output_marker};
+pub use credit_balance::CreditBalanceDetector;
+pub use cwd::CwdTracker;
+pub use deprecation::DeprecationDetector;
+pub use entry_events::detect_entry_events;
 pub use entry_index::EntryIndexProvider;
+pub use file_read::FileReadTracker;
+pub use lint_results::LintSummary;
+pub use mcp_failure::McpFailureDetector;
+pub use merge_conflict::detect_merge_conflict;
+pub use model_error::ModelErrorDetector;
+pub use out_of_bounds::OutOfBoundsDetector;
 pub use patch::ConversationPatch;
+pub use pretty_json::PrettyJsonBuffer;
+pub use proposed_command::ProposedCommandTracker;
+pub use proposed_edit::ProposedEditTracker;
+pub use question::QuestionDetector;
+pub use repeated_action::RepeatedActionDetector;
+pub use scratchpad::scratchpad_update;
+pub use secret_redaction::mask_secrets;
+pub use setup_instruction::parse_setup_instructions;
+pub use suggested_actions::parse_suggested_actions;
+pub use test_results::TestSummary;
+pub use turn_end::TurnEndDetector;
+pub use uncertainty::detect_uncertainty;
+pub use unified_diff::parse_unified_diffs;
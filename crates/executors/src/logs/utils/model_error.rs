@@ -0,0 +1,172 @@
+//! Notices the client refusing or not knowing the selected model anywhere in a run's log.
+//!
+//! The spawn-time check only sees what the client prints before it settles, but most clients
+//! report a model outside the plan later: Codex as an app-server error event once the first turn
+//! starts, Claude as an `API Error` assistant message. Those reach the normalized log as error,
+//! system or assistant entries, so the detector matches their text against the same signatures
+//! the spawn error is classified with and reports the first match once.
+
+use regex::Regex;
+use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+use crate::{
+    early_exit::{entitlement_failure, unknown_model_failure},
+    logs::{AgentEvent, NormalizedEntry, NormalizedEntryType, utils::patch},
+};
+
+#[derive(Debug, Clone)]
+pub struct ModelErrorDetector {
+    /// Client-specific entitlement signatures, on top of the generic ones
+    entitlement: &'static [Regex],
+    /// Named when a signature does not say which model it refused
+    selected_model: Option<String>,
+    reported: bool,
+}
+
+impl ModelErrorDetector {
+    pub fn new(entitlement: &'static [Regex], selected_model: Option<String>) -> Self {
+        Self {
+            entitlement,
+            selected_model,
+            reported: false,
+        }
+    }
+
+    /// Return the event for `entry` if it is the first to report a refused or unknown model.
+    /// Unknown-model wording is generic enough to turn up in the agent's own prose, so it is
+    /// only matched on error and system entries.
+    pub fn observe(&mut self, entry: &NormalizedEntry) -> Option<AgentEvent> {
+        if self.reported {
+            return None;
+        }
+        let selected_model = self.selected_model.as_deref();
+        let event = match entry.entry_type {
+            NormalizedEntryType::ErrorMessage { .. } | NormalizedEntryType::SystemMessage => {
+                entitlement_failure(&entry.content, self.entitlement, selected_model)
+                    .map(|model| AgentEvent::ModelNotEntitled { model })
+                    .or_else(|| {
+                        unknown_model_failure(&entry.content, selected_model)
+                            .map(|model| AgentEvent::UnknownModel { model })
+                    })
+            }
+            NormalizedEntryType::AssistantMessage => {
+                entitlement_failure(&entry.content, self.entitlement, selected_model)
+                    .map(|model| AgentEvent::ModelNotEntitled { model })
+            }
+            _ => None,
+        }?;
+        self.reported = true;
+        Some(event)
+    }
+
+    /// Replay the normalized entries in `msg_store` in order and return the first report.
+    pub fn scan(mut self, msg_store: &MsgStore) -> Option<AgentEvent> {
+        msg_store
+            .get_history()
+            .into_iter()
+            .find_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => patch::extract_normalized_entry_from_patch(&patch)
+                    .and_then(|(_, entry)| self.observe(&entry)),
+                _ => None,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        executors::codex::CODEX_ENTITLEMENT_PATTERNS,
+        logs::{NormalizedEntryError, utils::ConversationPatch},
+    };
+
+    fn entry(entry_type: NormalizedEntryType, content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    fn error(content: &str) -> NormalizedEntry {
+        entry(
+            NormalizedEntryType::ErrorMessage {
+                error_type: NormalizedEntryError::Other,
+            },
+            content,
+        )
+    }
+
+    #[test]
+    fn test_codex_error_event_after_first_turn() {
+        let store = MsgStore::new();
+        let entries = [
+            entry(NormalizedEntryType::SystemMessage, "model: gpt-5-pro"),
+            entry(NormalizedEntryType::AssistantMessage, "Looking at the repo"),
+            error(
+                "Error: {\"detail\":\"The 'gpt-5-pro' model is not supported when using Codex \
+                 with a ChatGPT account.\"} None",
+            ),
+            error("Error: Project `proj_x` does not have access to model `o3-pro` None"),
+        ];
+        for (index, entry) in entries.into_iter().enumerate() {
+            store.push_patch(ConversationPatch::add_normalized_entry(index, entry));
+        }
+
+        let detector =
+            ModelErrorDetector::new(&CODEX_ENTITLEMENT_PATTERNS, Some("gpt-5".to_string()));
+        assert_eq!(
+            detector.scan(&store),
+            Some(AgentEvent::ModelNotEntitled {
+                model: "gpt-5-pro".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_generic_wording_falls_back_to_selected_model() {
+        let mut detector = ModelErrorDetector::new(&[], Some("sonnet".to_string()));
+        assert_eq!(
+            detector.observe(&error("Model is not available on your plan")),
+            Some(AgentEvent::ModelNotEntitled {
+                model: "sonnet".to_string()
+            })
+        );
+        assert_eq!(
+            detector.observe(&error("Model is not available on your plan")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_unknown_model_only_from_error_entries() {
+        let mut detector = ModelErrorDetector::new(&[], None);
+        assert_eq!(
+            detector.observe(&entry(
+                NormalizedEntryType::AssistantMessage,
+                "The test asserts that model foo was not found",
+            )),
+            None
+        );
+        assert_eq!(
+            detector.observe(&error("Error: unknown model: opus-9")),
+            Some(AgentEvent::UnknownModel {
+                model: "opus-9".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_ignores_user_messages_and_unrelated_errors() {
+        let mut detector = ModelErrorDetector::new(&CODEX_ENTITLEMENT_PATTERNS, None);
+        assert_eq!(detector.observe(&error("Error: rate limited")), None);
+        assert_eq!(
+            detector.observe(&entry(
+                NormalizedEntryType::UserMessage,
+                "Model gpt-5 is not available on your plan",
+            )),
+            None
+        );
+    }
+}
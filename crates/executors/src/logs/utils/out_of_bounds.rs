@@ -0,0 +1,189 @@
+//! Flags proposed commands and edits that reach outside the worktree.
+//!
+//! File edits are checked by path. Shell commands are split into words and every word that looks
+//! like a path (absolute, `~`-prefixed, or climbing with `..`) is checked; relative words are
+//! resolved against the worktree root, since the shell's own directory is not known here. Each
+//! offending path is reported once per stream as `AgentEvent::OutOfBoundsAccess`.
+
+use std::{
+    collections::HashSet,
+    path::{Component, Path},
+};
+
+use workspace_utils::path::{expand_tilde, make_path_relative};
+
+use crate::logs::{ActionType, AgentEvent};
+
+/// Device files that commands routinely redirect to; never worth a warning.
+const IGNORED_PREFIXES: &[&str] = &["/dev/"];
+
+#[derive(Debug, Clone, Default)]
+pub struct OutOfBoundsDetector {
+    seen: HashSet<String>,
+}
+
+impl OutOfBoundsDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events for paths outside `worktree_path` that `action_type` edits or names in a command,
+    /// skipping paths already reported.
+    pub fn observe(&mut self, action_type: &ActionType, worktree_path: &str) -> Vec<AgentEvent> {
+        let candidates: Vec<&str> = match action_type {
+            ActionType::FileEdit { path, .. } => vec![path.as_str()],
+            ActionType::CommandRun { command, .. } => path_words(command),
+            _ => Vec::new(),
+        };
+        candidates
+            .into_iter()
+            .filter(|path| !is_in_bounds(path, worktree_path))
+            .filter(|path| self.seen.insert(path.to_string()))
+            .map(|path| AgentEvent::OutOfBoundsAccess {
+                path: path.to_string(),
+            })
+            .collect()
+    }
+}
+
+fn is_in_bounds(path: &str, worktree_path: &str) -> bool {
+    if path.is_empty()
+        || IGNORED_PREFIXES
+            .iter()
+            .any(|prefix| path.starts_with(prefix))
+    {
+        return true;
+    }
+    let expanded = expand_tilde(path);
+    let relative = make_path_relative(&expanded.to_string_lossy(), worktree_path);
+    stays_inside(Path::new(&relative))
+}
+
+/// Words of `command` that look like filesystem paths, including `--flag=<path>` values and
+/// redirection targets written without a space.
+fn path_words(command: &str) -> Vec<&str> {
+    command
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| matches!(c, '"' | '\'' | ';' | '(' | ')')))
+        .map(|word| word.rsplit_once('=').map_or(word, |(_, value)| value))
+        .map(strip_redirection)
+        .filter(|word| {
+            word.starts_with('/')
+                || word.starts_with('~')
+                || Path::new(word)
+                    .components()
+                    .any(|component| component == Component::ParentDir)
+        })
+        .collect()
+}
+
+/// The target of a redirection such as `2>/dev/null` or `&>log.txt`; other words unchanged.
+fn strip_redirection(word: &str) -> &str {
+    let rest = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '&');
+    if rest.starts_with(['>', '<']) {
+        rest.trim_start_matches(['>', '<', '&'])
+    } else {
+        word
+    }
+}
+
+/// Whether a worktree-relative path stays inside the worktree once `..` is applied.
+fn stays_inside(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match depth.checked_sub(1) {
+                Some(parent) => depth = parent,
+                None => return false,
+            },
+            Component::Normal(_) => depth += 1,
+            Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(command: &str) -> ActionType {
+        ActionType::CommandRun {
+            command: command.to_string(),
+            result: None,
+        }
+    }
+
+    fn edit(path: &str) -> ActionType {
+        ActionType::FileEdit {
+            path: path.to_string(),
+            changes: vec![],
+        }
+    }
+
+    fn paths(events: Vec<AgentEvent>) -> Vec<String> {
+        events
+            .into_iter()
+            .filter_map(|event| match event {
+                AgentEvent::OutOfBoundsAccess { path } => Some(path),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_in_bounds_references_are_ignored() {
+        let mut detector = OutOfBoundsDetector::new();
+
+        assert!(detector.observe(&edit("src/main.rs"), "/repo").is_empty());
+        assert!(
+            detector
+                .observe(
+                    &command("cat /repo/src/lib.rs src/../Cargo.toml 2>/dev/null > /dev/null"),
+                    "/repo"
+                )
+                .is_empty()
+        );
+        assert!(
+            detector
+                .observe(&command("cargo test --manifest-path=./Cargo.toml"), "/repo")
+                .is_empty()
+        );
+        assert!(
+            detector
+                .observe(
+                    &ActionType::FileRead {
+                        path: "/etc/hosts".to_string()
+                    },
+                    "/repo"
+                )
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_out_of_bounds_references_are_reported_once() {
+        let mut detector = OutOfBoundsDetector::new();
+
+        assert_eq!(
+            paths(detector.observe(&edit("/etc/hosts"), "/repo")),
+            vec!["/etc/hosts"]
+        );
+        assert_eq!(
+            paths(detector.observe(
+                &command("cp ../secrets.env src/ && cat '/etc/hosts'"),
+                "/repo"
+            )),
+            vec!["../secrets.env"]
+        );
+        assert_eq!(
+            paths(detector.observe(&command("ls --dir=/opt/other >/tmp/out.txt"), "/repo")),
+            vec!["/opt/other", "/tmp/out.txt"]
+        );
+        assert_eq!(
+            paths(detector.observe(&edit("../secrets.env"), "/repo")),
+            Vec::<String>::new()
+        );
+    }
+}
@@ -6,7 +6,10 @@ use serde_json::{from_value, json, to_value};
 use ts_rs::TS;
 use workspace_utils::{diff::Diff, msg_store::MsgStore};
 
-use crate::logs::{NormalizedEntry, utils::EntryIndexProvider};
+use crate::logs::{
+    NormalizedEntry,
+    utils::{EntryIndexProvider, timestamp::next_timestamp},
+};
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, TS)]
 #[serde(rename_all = "lowercase")]
@@ -37,6 +40,14 @@ pub fn escape_json_pointer_segment(s: &str) -> String {
     s.replace('~', "~0").replace('/', "~1")
 }
 
+/// Stamp entries that do not carry a timestamp with the time they were read.
+fn with_read_timestamp(mut entry: NormalizedEntry) -> NormalizedEntry {
+    if entry.timestamp.is_none() {
+        entry.timestamp = Some(next_timestamp());
+    }
+    entry
+}
+
 /// Helper functions to create JSON patches for conversation entries
 pub struct ConversationPatch;
 
@@ -46,7 +57,7 @@ impl ConversationPatch {
         let patch_entry = PatchEntry {
             op: PatchOperation::Add,
             path: format!("/entries/{entry_index}"),
-            value: PatchType::NormalizedEntry(entry),
+            value: PatchType::NormalizedEntry(with_read_timestamp(entry)),
         };
 
         from_value(json!([patch_entry])).unwrap()
@@ -110,7 +121,7 @@ impl ConversationPatch {
         let patch_entry = PatchEntry {
             op: PatchOperation::Replace,
             path: format!("/entries/{entry_index}"),
-            value: PatchType::NormalizedEntry(entry),
+            value: PatchType::NormalizedEntry(with_read_timestamp(entry)),
         };
 
         from_value(json!([patch_entry])).unwrap()
@@ -175,3 +186,54 @@ pub fn replace_normalized_entry(
 ) {
     upsert_normalized_entry(msg_store, index, normalized_entry, false);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::NormalizedEntryType;
+
+    fn entry(content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn test_entries_are_stamped_in_read_order() {
+        let patches = vec![
+            ConversationPatch::add_normalized_entry(0, entry("first")),
+            ConversationPatch::add_normalized_entry(1, entry("second")),
+            ConversationPatch::replace(1, entry("second, updated")),
+            ConversationPatch::add_normalized_entry(2, entry("third")),
+        ];
+
+        let stamps: Vec<String> = patches
+            .iter()
+            .map(|patch| {
+                let (_, entry) = extract_normalized_entry_from_patch(patch).unwrap();
+                entry.timestamp.expect("entry should be stamped")
+            })
+            .collect();
+        assert!(
+            stamps.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{stamps:?}"
+        );
+    }
+
+    #[test]
+    fn test_existing_timestamp_is_kept() {
+        let mut stamped = entry("from agent");
+        stamped.timestamp = Some("2024-01-01T00:00:00.000000Z".to_string());
+
+        let patch = ConversationPatch::add_normalized_entry(0, stamped);
+        let (_, entry) = extract_normalized_entry_from_patch(&patch).unwrap();
+
+        assert_eq!(
+            entry.timestamp.as_deref(),
+            Some("2024-01-01T00:00:00.000000Z")
+        );
+    }
+}
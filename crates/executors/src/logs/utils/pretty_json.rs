@@ -0,0 +1,135 @@
+//! Reassembly of pretty-printed JSON objects that span multiple lines.
+//!
+//! Line-oriented normalizers parse one JSON object per line. Some clients, when not running in
+//! streaming mode, print a single pretty-printed JSON blob at the end of the run instead. This
+//! buffer collects such lines until the braces are balanced so the blob can be parsed once.
+//!
+//! A pretty-printed blob opens with a line holding only `{` or `[`, so collection starts there and
+//! nowhere else; a truncated one-line event is left to the caller. If a line that is a complete
+//! JSON object on its own arrives while collecting, the blob was cut short and the stream is back
+//! to one event per line, so the partial blob is handed back instead of swallowing what follows.
+
+/// Accumulates lines of a pretty-printed JSON value until it is complete.
+#[derive(Debug, Default)]
+pub struct PrettyJsonBuffer {
+    buffer: String,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+}
+
+impl PrettyJsonBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a partial JSON value is currently buffered.
+    pub fn is_collecting(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    /// Whether `line` opens a pretty-printed blob.
+    pub fn starts_blob(line: &str) -> bool {
+        matches!(line.trim(), "{" | "[")
+    }
+
+    /// Drain the partial blob if `line` is a standalone JSON object rather than part of it.
+    pub fn interrupted_by(&mut self, line: &str) -> Option<String> {
+        let standalone = self.is_collecting()
+            && serde_json::from_str::<serde_json::Value>(line.trim())
+                .is_ok_and(|value| value.is_object());
+        standalone.then(|| self.take())
+    }
+
+    /// Append a line and return the complete JSON text once all braces and brackets are balanced.
+    pub fn push_line(&mut self, line: &str) -> Option<String> {
+        for ch in line.chars() {
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if ch == '\\' {
+                    self.escaped = true;
+                } else if ch == '"' {
+                    self.in_string = false;
+                }
+                continue;
+            }
+            match ch {
+                '"' => self.in_string = true,
+                '{' | '[' => self.depth += 1,
+                '}' | ']' => self.depth = self.depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+
+        if self.depth == 0 && !self.in_string {
+            Some(self.take())
+        } else {
+            None
+        }
+    }
+
+    /// Drain whatever is buffered, complete or not, and reset the scanner state.
+    pub fn take(&mut self) -> String {
+        self.depth = 0;
+        self.in_string = false;
+        self.escaped = false;
+        std::mem::take(&mut self.buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collects_until_balanced() {
+        let mut buffer = PrettyJsonBuffer::new();
+        assert_eq!(buffer.push_line("{"), None);
+        assert_eq!(buffer.push_line(r#"  "text": "a } in a string","#), None);
+        assert_eq!(buffer.push_line(r#"  "items": [1, 2]"#), None);
+        assert!(buffer.is_collecting());
+
+        let blob = buffer.push_line("}").unwrap();
+        let value: serde_json::Value = serde_json::from_str(&blob).unwrap();
+        assert_eq!(value["text"], "a } in a string");
+        assert!(!buffer.is_collecting());
+    }
+
+    #[test]
+    fn test_single_line_object_completes_immediately() {
+        let mut buffer = PrettyJsonBuffer::new();
+        assert_eq!(
+            buffer.push_line(r#"{"a": 1}"#).as_deref(),
+            Some("{\"a\": 1}\n")
+        );
+        assert!(!buffer.is_collecting());
+    }
+
+    #[test]
+    fn test_only_bare_open_line_starts_blob() {
+        assert!(PrettyJsonBuffer::starts_blob("{"));
+        assert!(PrettyJsonBuffer::starts_blob("  [ "));
+        assert!(!PrettyJsonBuffer::starts_blob(
+            r#"{"type":"assistant","message":{"#
+        ));
+        assert!(!PrettyJsonBuffer::starts_blob("{}"));
+    }
+
+    #[test]
+    fn test_standalone_object_interrupts_blob() {
+        let mut buffer = PrettyJsonBuffer::new();
+        assert_eq!(buffer.interrupted_by(r#"{"type":"result"}"#), None);
+
+        assert_eq!(buffer.push_line("{"), None);
+        assert_eq!(buffer.push_line(r#"  "type": "assistant","#), None);
+        assert_eq!(buffer.interrupted_by(r#"  "items": [1, 2]"#), None);
+        assert_eq!(buffer.interrupted_by(r#"  "text""#), None);
+
+        let partial = buffer.interrupted_by(r#"{"type":"result"}"#).unwrap();
+        assert_eq!(partial, "{\n  \"type\": \"assistant\",\n");
+        assert!(!buffer.is_collecting());
+    }
+}
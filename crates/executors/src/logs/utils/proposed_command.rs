@@ -0,0 +1,103 @@
+//! Captures shell commands an agent announces before running them.
+//!
+//! Some agents narrate `About to run: <cmd>` ahead of the actual tool call. Each announcement is
+//! surfaced as `AgentEvent::ProposedCommand` once its line is complete; when the matching tool
+//! call arrives the event is linked to its `tool_call_id`, which is also what approval requests
+//! are keyed by.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::AgentEvent;
+
+static ABOUT_TO_RUN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^\s*(?:[-*>]\s*)?(?:\*\*)?about to run(?:\*\*)?\s*:\s*(?P<cmd>.+?)\s*$")
+        .expect("valid proposed command pattern")
+});
+
+/// Extract the command from an `About to run: <cmd>` line, stripping inline-code backticks.
+pub fn parse_proposed_command(line: &str) -> Option<String> {
+    let command = ABOUT_TO_RUN.captures(line)?.name("cmd")?.as_str();
+    let command = command.trim_matches('`').trim();
+    (!command.is_empty()).then(|| command.to_string())
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProposedCommandTracker {
+    /// Announced commands not yet tied to a tool call, with their entry index
+    pending: Vec<(String, usize)>,
+}
+
+impl ProposedCommandTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commands announced in an agent message, in order of appearance.
+    pub fn detect(text: &str) -> Vec<String> {
+        text.lines().filter_map(parse_proposed_command).collect()
+    }
+
+    /// Remember an announced command emitted at `entry_index`.
+    pub fn record(&mut self, command: String, entry_index: usize) {
+        self.pending.push((command, entry_index));
+    }
+
+    /// Link a tool call to the oldest matching announcement, returning the entry index to update
+    /// and the correlated event.
+    pub fn correlate(&mut self, tool_call_id: &str, command: &str) -> Option<(usize, AgentEvent)> {
+        let position = self
+            .pending
+            .iter()
+            .position(|(proposed, _)| proposed == command.trim())?;
+        let (command, entry_index) = self.pending.remove(position);
+        Some((
+            entry_index,
+            AgentEvent::ProposedCommand {
+                command,
+                tool_call_id: Some(tool_call_id.to_string()),
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_proposed_command_line() {
+        assert_eq!(
+            parse_proposed_command("About to run: `cargo test -p executors`"),
+            Some("cargo test -p executors".to_string())
+        );
+        assert_eq!(
+            parse_proposed_command("- **About to run**: rm -rf target"),
+            Some("rm -rf target".to_string())
+        );
+        assert_eq!(parse_proposed_command("I ran: cargo test"), None);
+        assert_eq!(parse_proposed_command("About to run:   "), None);
+    }
+
+    #[test]
+    fn test_correlates_oldest_matching_announcement() {
+        let mut tracker = ProposedCommandTracker::new();
+        let text = "Plan:\nAbout to run: `npm test`\nAbout to run: `npm run lint`";
+        for (offset, command) in ProposedCommandTracker::detect(text).into_iter().enumerate() {
+            tracker.record(command, 10 + offset);
+        }
+
+        assert!(tracker.correlate("call-0", "npm ci").is_none());
+        let (index, event) = tracker.correlate("call-1", "npm run lint").unwrap();
+        assert_eq!(index, 11);
+        assert_eq!(
+            event,
+            AgentEvent::ProposedCommand {
+                command: "npm run lint".to_string(),
+                tool_call_id: Some("call-1".to_string()),
+            }
+        );
+        assert!(tracker.correlate("call-2", "npm run lint").is_none());
+    }
+}
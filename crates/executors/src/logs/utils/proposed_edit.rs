@@ -0,0 +1,143 @@
+//! Surfaces file edits an agent proposes before it writes them, for review-before-apply flows.
+//!
+//! Clients with [`BaseAgentCapability::ProposeBeforeApply`](crate::executors::BaseAgentCapability)
+//! announce each patch for approval before applying it. Every file in such a patch is reported as
+//! `AgentEvent::ProposedEdit`, whose `tool_call_id` matches the metadata of the `FileEdit` tool
+//! call that later applies it.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::logs::{ActionType, AgentEvent, FileChange};
+
+#[derive(Debug, Clone, Default)]
+pub struct ProposedEditTracker {
+    /// Paths proposed and not yet applied, by tool call id
+    pending: HashMap<String, HashSet<String>>,
+}
+
+impl ProposedEditTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Event for a proposed change to `path`, unless the same call already proposed it.
+    pub fn propose(
+        &mut self,
+        tool_call_id: &str,
+        path: &str,
+        changes: &[FileChange],
+    ) -> Option<AgentEvent> {
+        self.pending
+            .entry(tool_call_id.to_string())
+            .or_default()
+            .insert(path.to_string())
+            .then(|| AgentEvent::ProposedEdit {
+                path: path.to_string(),
+                diff: render_diff(changes),
+                tool_call_id: tool_call_id.to_string(),
+            })
+    }
+
+    /// Whether the `FileEdit` applied by `tool_call_id` carries out an earlier proposal.
+    pub fn applied(&mut self, tool_call_id: &str, action_type: &ActionType) -> bool {
+        let ActionType::FileEdit { path, .. } = action_type else {
+            return false;
+        };
+        let Some(paths) = self.pending.get_mut(tool_call_id) else {
+            return false;
+        };
+        let matched = paths.remove(path);
+        if paths.is_empty() {
+            self.pending.remove(tool_call_id);
+        }
+        matched
+    }
+
+    /// Forget the proposals of a call that was rejected or failed.
+    pub fn discard(&mut self, tool_call_id: &str) {
+        self.pending.remove(tool_call_id);
+    }
+}
+
+/// Unified-diff style rendering of `changes`; new files are shown as all-added lines.
+fn render_diff(changes: &[FileChange]) -> String {
+    changes
+        .iter()
+        .map(|change| match change {
+            FileChange::Edit { unified_diff, .. } => unified_diff.trim_end().to_string(),
+            FileChange::Write { content } => content
+                .lines()
+                .map(|line| format!("+{line}"))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            FileChange::Delete => "(delete file)".to_string(),
+            FileChange::Rename { new_path } => format!("(rename to {new_path})"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str =
+        "--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,1 +1,1 @@\n-fn old() {}\n+fn new() {}\n";
+
+    fn edit_changes() -> Vec<FileChange> {
+        vec![FileChange::Edit {
+            unified_diff: DIFF.to_string(),
+            has_line_numbers: true,
+        }]
+    }
+
+    fn file_edit(path: &str) -> ActionType {
+        ActionType::FileEdit {
+            path: path.to_string(),
+            changes: edit_changes(),
+        }
+    }
+
+    #[test]
+    fn test_proposed_edit_parsed() {
+        let mut tracker = ProposedEditTracker::new();
+
+        assert_eq!(
+            tracker.propose("call_1", "src/lib.rs", &edit_changes()),
+            Some(AgentEvent::ProposedEdit {
+                path: "src/lib.rs".to_string(),
+                diff: DIFF.trim_end().to_string(),
+                tool_call_id: "call_1".to_string(),
+            })
+        );
+        // A repeated approval request for the same patch is not a new proposal
+        assert_eq!(
+            tracker.propose("call_1", "src/lib.rs", &edit_changes()),
+            None
+        );
+
+        let new_file = [FileChange::Write {
+            content: "a\nb\n".to_string(),
+        }];
+        assert!(matches!(
+            tracker.propose("call_1", "src/new.rs", &new_file),
+            Some(AgentEvent::ProposedEdit { diff, .. }) if diff == "+a\n+b"
+        ));
+    }
+
+    #[test]
+    fn test_proposed_edit_correlated_with_applied_edit() {
+        let mut tracker = ProposedEditTracker::new();
+        tracker.propose("call_1", "src/lib.rs", &edit_changes());
+
+        assert!(!tracker.applied("call_2", &file_edit("src/lib.rs")));
+        assert!(!tracker.applied("call_1", &file_edit("src/main.rs")));
+        assert!(tracker.applied("call_1", &file_edit("src/lib.rs")));
+        // Each proposal is carried out once
+        assert!(!tracker.applied("call_1", &file_edit("src/lib.rs")));
+
+        tracker.propose("call_3", "src/lib.rs", &edit_changes());
+        tracker.discard("call_3");
+        assert!(!tracker.applied("call_3", &file_edit("src/lib.rs")));
+    }
+}
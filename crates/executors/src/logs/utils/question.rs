@@ -0,0 +1,115 @@
+//! Recognizes turns that end by asking the user a question.
+//!
+//! When an agent finishes a turn with a clarifying question it is waiting on the user, not on an
+//! approval. The detector looks at the final line of the last assistant message and reports it
+//! when it reads like a question directed at the user.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Patterns shared by every client, matched against the trimmed final line.
+static COMMON_PATTERNS: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // Direct questions, e.g. `Should I also update the docs?`
+        r"(?i)^(?:should|shall|would|do|does|can|could|will|may) (?:i|you|we)\b.*\?$",
+        // Requests for input, e.g. `Which approach would you prefer?`
+        r"(?i)\b(?:would you (?:like|prefer)|do you want|let me know)\b.*\?$",
+        r"(?i)\bplease (?:confirm|clarify)\b.*\?$",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid question pattern"))
+    .collect()
+});
+
+#[derive(Debug, Clone)]
+pub struct QuestionDetector {
+    patterns: Vec<Regex>,
+}
+
+impl Default for QuestionDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl QuestionDetector {
+    pub fn new() -> Self {
+        Self {
+            patterns: COMMON_PATTERNS.clone(),
+        }
+    }
+
+    /// Add client-specific signatures, matched against the final line of a message.
+    pub fn with_patterns(mut self, patterns: &[&str]) -> Self {
+        self.patterns.extend(
+            patterns
+                .iter()
+                .map(|pattern| Regex::new(pattern).expect("valid question pattern")),
+        );
+        self
+    }
+
+    /// Return the question `message` ends with, if any.
+    pub fn detect(&self, message: &str) -> Option<String> {
+        let line = message.lines().rev().find(|line| !line.trim().is_empty())?;
+        let line = line
+            .trim()
+            .trim_start_matches(['>', '-', '*', '#', ' '])
+            .trim_matches(['*', '_', '`', ' ']);
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.is_match(line))
+            .then(|| line.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clarifying_question_detected() {
+        let detector = QuestionDetector::new();
+        let message = "I've added the migration.\n\n**Should I also update the docs?**\n";
+
+        assert_eq!(
+            detector.detect(message),
+            Some("Should I also update the docs?".to_string())
+        );
+        assert_eq!(
+            detector.detect("Which approach would you prefer?"),
+            Some("Which approach would you prefer?".to_string())
+        );
+    }
+
+    #[test]
+    fn test_statements_and_rhetorical_questions_ignored() {
+        let detector = QuestionDetector::new();
+
+        assert_eq!(detector.detect("All tests pass."), None);
+        assert_eq!(
+            detector.detect("Should I update the docs? No, they are generated.\nDone."),
+            None
+        );
+        assert_eq!(
+            detector.detect("Why did this fail? The lock was stale."),
+            None
+        );
+        assert_eq!(detector.detect(""), None);
+    }
+
+    #[test]
+    fn test_client_patterns() {
+        let detector = QuestionDetector::new().with_patterns(&[r"(?i)^which .*\?$"]);
+
+        assert_eq!(
+            detector.detect("Which branch should this target?"),
+            Some("Which branch should this target?".to_string())
+        );
+        assert_eq!(
+            QuestionDetector::new().detect("Which branch should this target?"),
+            None
+        );
+    }
+}
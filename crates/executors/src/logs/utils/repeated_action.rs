@@ -0,0 +1,91 @@
+//! Notices an agent repeating the same action, e.g. re-running a failing command in a loop.
+//!
+//! Consecutive identical actions are counted and reported once as `AgentEvent::RepeatedAction`
+//! when the streak reaches the threshold. Any different action in between starts a new streak.
+
+use crate::logs::AgentEvent;
+
+/// Identical actions in a row before a repeat is reported.
+pub const DEFAULT_REPEAT_THRESHOLD: u32 = 3;
+
+#[derive(Debug, Clone)]
+pub struct RepeatedActionDetector {
+    threshold: u32,
+    /// Fingerprint of the latest action and how many times in a row it occurred
+    streak: Option<(String, u32)>,
+}
+
+impl Default for RepeatedActionDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepeatedActionDetector {
+    pub fn new() -> Self {
+        Self::with_threshold(DEFAULT_REPEAT_THRESHOLD)
+    }
+
+    pub fn with_threshold(threshold: u32) -> Self {
+        Self {
+            threshold: threshold.max(2),
+            streak: None,
+        }
+    }
+
+    /// Record an action. `fingerprint` decides whether two actions are identical (e.g. tool name
+    /// plus input) and `action` is how the event describes it.
+    pub fn observe(&mut self, action: &str, fingerprint: &str) -> Option<AgentEvent> {
+        let count = match &mut self.streak {
+            Some((last, count)) if last == fingerprint => {
+                *count += 1;
+                *count
+            }
+            streak => {
+                *streak = Some((fingerprint.to_string(), 1));
+                1
+            }
+        };
+        (count == self.threshold).then(|| AgentEvent::RepeatedAction {
+            action: action.to_string(),
+            count,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fires_once_at_threshold() {
+        let mut detector = RepeatedActionDetector::new();
+        let events: Vec<_> = (0..5)
+            .map(|_| detector.observe("Bash: cargo build", r#"Bash {"command":"cargo build"}"#))
+            .collect();
+
+        assert_eq!(
+            events,
+            vec![
+                None,
+                None,
+                Some(AgentEvent::RepeatedAction {
+                    action: "Bash: cargo build".to_string(),
+                    count: 3,
+                }),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_different_action_resets_streak() {
+        let mut detector = RepeatedActionDetector::with_threshold(2);
+
+        assert_eq!(detector.observe("Read: a.rs", "a"), None);
+        assert_eq!(detector.observe("Read: b.rs", "b"), None);
+        assert_eq!(detector.observe("Read: a.rs", "a"), None);
+        assert!(detector.observe("Read: a.rs", "a").is_some());
+    }
+}
@@ -0,0 +1,99 @@
+//! Notices writes to an agent's scratchpad or working-memory file.
+//!
+//! Agents that keep notes between steps usually write them to a file named like `scratchpad.md`,
+//! `MEMORY.md` or `NOTES.md`, or into a `memory/` / `memory-bank/` directory. Such edits are
+//! reported as `AgentEvent::ScratchpadUpdate` in addition to the ordinary `FileEdit` tool call.
+
+use std::path::Path;
+
+use crate::logs::{ActionType, AgentEvent};
+
+/// File stems, compared case-insensitively, that mark a scratchpad regardless of directory.
+const SCRATCHPAD_STEMS: &[&str] = &["scratchpad", "scratch", "memory", "notes", "working_memory"];
+
+/// Directories whose contents are agent memory, e.g. Cline's `memory-bank/`.
+const MEMORY_DIRS: &[&str] = &[
+    "memory",
+    "memory-bank",
+    "memory_bank",
+    ".memory",
+    ".scratchpad",
+];
+
+/// A `ScratchpadUpdate` event if `action_type` edits a scratchpad or memory file.
+pub fn scratchpad_update(action_type: &ActionType) -> Option<AgentEvent> {
+    match action_type {
+        ActionType::FileEdit { path, .. } if is_scratchpad_path(path) => {
+            Some(AgentEvent::ScratchpadUpdate { path: path.clone() })
+        }
+        _ => None,
+    }
+}
+
+fn is_scratchpad_path(path: &str) -> bool {
+    let path = Path::new(path);
+    let in_memory_dir = path
+        .parent()
+        .into_iter()
+        .flat_map(Path::components)
+        .any(|dir| {
+            let dir = dir.as_os_str().to_string_lossy().to_lowercase();
+            MEMORY_DIRS.contains(&dir.as_str())
+        });
+    if in_memory_dir {
+        return true;
+    }
+
+    let is_text = path
+        .extension()
+        .is_none_or(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "md" | "txt"));
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    is_text && SCRATCHPAD_STEMS.contains(&stem.trim_start_matches('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(path: &str) -> ActionType {
+        ActionType::FileEdit {
+            path: path.to_string(),
+            changes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_scratchpad_writes_are_reported() {
+        for path in [
+            "scratchpad.md",
+            "docs/NOTES.md",
+            ".claude/MEMORY.md",
+            "memory-bank/activeContext.md",
+            "tmp/.scratchpad/plan.json",
+        ] {
+            assert_eq!(
+                scratchpad_update(&edit(path)),
+                Some(AgentEvent::ScratchpadUpdate {
+                    path: path.to_string()
+                }),
+                "{path}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ordinary_edits_and_reads_are_ignored() {
+        assert_eq!(scratchpad_update(&edit("src/memory.rs")), None);
+        assert_eq!(scratchpad_update(&edit("src/notes/mod.rs")), None);
+        assert_eq!(scratchpad_update(&edit("README.md")), None);
+        assert_eq!(
+            scratchpad_update(&ActionType::FileRead {
+                path: "scratchpad.md".to_string()
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Picks out instructions asking the user to set up their environment.
+//!
+//! Agents that cannot install something themselves tell the user to, e.g. "Please run
+//! `npm install` first" or "You need to install protoc:" followed by a fenced command. Each such
+//! line is reported as `AgentEvent::SetupInstruction`, with the command when the line carries one
+//! in inline code or introduces a fenced block.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::AgentEvent;
+
+/// Phrases that ask the user to install or run something.
+static INSTRUCTION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:please (?:run|install|execute)|you(?:'ll| will)? (?:need|have) to (?:run|install)|you (?:should|must) (?:first )?(?:run|install)|make sure (?:to|you) (?:run|install)|run the following)\b",
+    )
+    .expect("valid instruction pattern")
+});
+
+/// The first inline code span.
+static INLINE_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`([^`\n]+)`").expect("valid inline code pattern"));
+
+/// Setup instructions in `message`, in order.
+pub fn parse_setup_instructions(message: &str) -> Vec<AgentEvent> {
+    let lines: Vec<&str> = message.lines().collect();
+    let mut events = Vec::new();
+    let mut in_fence = false;
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || !INSTRUCTION.is_match(trimmed) {
+            continue;
+        }
+        let command = INLINE_CODE
+            .captures(trimmed)
+            .map(|caps| caps[1].trim().to_string())
+            .or_else(|| fenced_block_after(&lines[i + 1..]));
+        events.push(AgentEvent::SetupInstruction {
+            command,
+            text: trimmed
+                .trim_start_matches(['>', '-', '*', '#', ' '])
+                .trim_matches(['*', '_', ' '])
+                .to_string(),
+        });
+    }
+    events
+}
+
+/// Contents of a fenced block that directly follows (blank lines aside), without shell prompts.
+fn fenced_block_after(lines: &[&str]) -> Option<String> {
+    let mut lines = lines
+        .iter()
+        .map(|line| line.trim())
+        .skip_while(|line| line.is_empty());
+    if !lines.next()?.starts_with("```") {
+        return None;
+    }
+    let body: Vec<&str> = lines
+        .take_while(|line| !line.starts_with("```"))
+        .map(|line| line.trim_start_matches("$ "))
+        .filter(|line| !line.is_empty())
+        .collect();
+    (!body.is_empty()).then(|| body.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_instruction_with_command() {
+        let message = "The build needs the protobuf compiler.\n\n\
+            Please run `brew install protobuf` and then retry.\n\n\
+            You'll need to install the pinned toolchain:\n\n\
+            ```bash\n\
+            $ rustup toolchain install 1.89\n\
+            ```\n";
+
+        assert_eq!(
+            parse_setup_instructions(message),
+            vec![
+                AgentEvent::SetupInstruction {
+                    command: Some("brew install protobuf".to_string()),
+                    text: "Please run `brew install protobuf` and then retry.".to_string(),
+                },
+                AgentEvent::SetupInstruction {
+                    command: Some("rustup toolchain install 1.89".to_string()),
+                    text: "You'll need to install the pinned toolchain:".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_instruction_without_command() {
+        let message = "I couldn't start the database.\n\
+            **You need to install PostgreSQL 16 before the tests can run.**";
+
+        assert_eq!(
+            parse_setup_instructions(message),
+            vec![AgentEvent::SetupInstruction {
+                command: None,
+                text: "You need to install PostgreSQL 16 before the tests can run.".to_string(),
+            }]
+        );
+        assert!(parse_setup_instructions("I ran `cargo test` and everything passes.").is_empty());
+        // Phrases inside code blocks are not addressed to the user
+        assert!(parse_setup_instructions("```\n# please run this as root\n```").is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+//! Extracts the follow-up actions an agent suggests at the end of a turn.
+//!
+//! Agents often close with a "Next steps" or "Suggestions" section. The parser looks for the last
+//! such heading in the final assistant message and collects the bulleted or numbered list under it.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Heading text once markdown markers and the trailing colon are stripped.
+static HEADING: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:(?:suggested|recommended|possible) )?(?:next steps|suggestions|follow[- ]ups?)$|^suggested actions$",
+    )
+    .expect("valid heading pattern")
+});
+
+/// A top-level `-`, `*`, `+` or `•` bullet, or a `1.` / `1)` numbered item.
+static LIST_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(?:[-*+•]|\d+[.)])\s+(.+)$").expect("valid list item pattern"));
+
+/// Return the items of the trailing next-steps section of `message`, if it has one.
+pub fn parse_suggested_actions(message: &str) -> Option<Vec<String>> {
+    let lines: Vec<&str> = message.lines().collect();
+    let heading = lines.iter().rposition(|line| is_heading(line))?;
+
+    let mut items = Vec::new();
+    for line in &lines[heading + 1..] {
+        if line.trim().is_empty() {
+            continue;
+        }
+        // Nested items and wrapped lines belong to the item above
+        if line.starts_with([' ', '\t']) && !items.is_empty() {
+            continue;
+        }
+        match LIST_ITEM.captures(line.trim_end()) {
+            Some(caps) => items.push(strip_emphasis(&caps[1]).to_string()),
+            // The list ended; a closing remark after it is fine, another paragraph before it is not
+            None => break,
+        }
+    }
+    (!items.is_empty()).then_some(items)
+}
+
+fn is_heading(line: &str) -> bool {
+    let text = line
+        .trim()
+        .trim_start_matches(['#', ' '])
+        .trim_matches(['*', '_', ' '])
+        .trim_end_matches(':')
+        .trim_end_matches(['*', '_'])
+        .trim();
+    HEADING.is_match(text)
+}
+
+fn strip_emphasis(text: &str) -> &str {
+    text.trim().trim_matches(['*', '_', ' '])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulleted_suggestions() {
+        let message = "I've fixed the race in the watcher.\n\n\
+            ## Next steps\n\
+            - Run the integration tests on CI\n\
+            - **Bump the crate version**\n  \
+              so downstream picks up the fix\n\
+            * Update the changelog\n";
+
+        assert_eq!(
+            parse_suggested_actions(message),
+            Some(vec![
+                "Run the integration tests on CI".to_string(),
+                "Bump the crate version".to_string(),
+                "Update the changelog".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_numbered_suggestions() {
+        let message = "All tests pass.\n\n\
+            **Suggestions:**\n\n\
+            1. Add a migration for the new column\n\
+            2) Backfill existing rows\n\
+            10. Drop the old index\n\n\
+            Let me know if you'd like me to start on any of these.";
+
+        assert_eq!(
+            parse_suggested_actions(message),
+            Some(vec![
+                "Add a migration for the new column".to_string(),
+                "Backfill existing rows".to_string(),
+                "Drop the old index".to_string(),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_lists_without_a_next_steps_heading_ignored() {
+        assert_eq!(
+            parse_suggested_actions("Changes:\n- Fixed the watcher\n- Added tests"),
+            None
+        );
+        assert_eq!(
+            parse_suggested_actions("## Next steps\nNothing left to do."),
+            None
+        );
+        assert_eq!(parse_suggested_actions(""), None);
+    }
+}
@@ -0,0 +1,138 @@
+//! Reads pass/fail counts from test-runner summaries in command output.
+//!
+//! Recognizes the final summary lines printed by cargo test, pytest and jest. Counts missing from
+//! a summary are left unset rather than assumed to be zero, and cargo's per-crate summaries are
+//! added together.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::AgentEvent;
+
+static SUMMARY_LINE: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        // cargo: `test result: ok. 12 passed; 0 failed; 1 ignored; 0 measured; ...`
+        r"^test result: (?:ok|FAILED)\.",
+        // pytest: `===== 1 failed, 10 passed, 2 skipped in 1.23s =====`
+        r"^=*\s*\d+ (?:passed|failed|skipped|errors?|deselected|xfailed|xpassed)\b.* in [\d.]+s\b",
+        // jest: `Tests:       1 failed, 2 skipped, 10 passed, 13 total`
+        r"^Tests:\s+\d+ ",
+    ]
+    .iter()
+    .map(|pattern| Regex::new(pattern).expect("valid test summary pattern"))
+    .collect()
+});
+
+static COUNT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+) (passed|failed|skipped|ignored|pending|todo)\b").unwrap());
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TestSummary {
+    pub passed: Option<u32>,
+    pub failed: Option<u32>,
+    pub skipped: Option<u32>,
+}
+
+impl TestSummary {
+    /// Summarize every test-runner summary line in `output`, if there is one.
+    pub fn parse(output: &str) -> Option<Self> {
+        let mut summary = None;
+        for line in output.lines() {
+            let line = strip_ansi_escapes::strip_str(line);
+            let line = line.trim();
+            if !SUMMARY_LINE.iter().any(|pattern| pattern.is_match(line)) {
+                continue;
+            }
+            let summary = summary.get_or_insert_with(Self::default);
+            for captures in COUNT.captures_iter(line) {
+                let Ok(count) = captures[1].parse::<u32>() else {
+                    continue;
+                };
+                let slot = match &captures[2] {
+                    "passed" => &mut summary.passed,
+                    "failed" => &mut summary.failed,
+                    _ => &mut summary.skipped,
+                };
+                *slot = Some(slot.unwrap_or(0) + count);
+            }
+        }
+        summary
+    }
+
+    pub fn into_event(self) -> AgentEvent {
+        AgentEvent::TestResults {
+            passed: self.passed,
+            failed: self.failed,
+            skipped: self.skipped,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cargo_summaries_are_summed() {
+        let output = "\
+running 3 tests
+test a ... ok
+test b ... FAILED
+test c ... ignored
+
+test result: FAILED. 1 passed; 1 failed; 1 ignored; 0 measured; 0 filtered out; finished in 0.01s
+
+running 2 tests
+test result: ok. 2 passed; 0 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.00s
+";
+        assert_eq!(
+            TestSummary::parse(output),
+            Some(TestSummary {
+                passed: Some(3),
+                failed: Some(1),
+                skipped: Some(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_pytest_summary() {
+        let output = "\
+tests/test_api.py ..F.s                                   [100%]
+=================== 1 failed, 3 passed, 1 skipped in 0.42s ===================
+";
+        assert_eq!(
+            TestSummary::parse(output),
+            Some(TestSummary {
+                passed: Some(3),
+                failed: Some(1),
+                skipped: Some(1),
+            })
+        );
+    }
+
+    #[test]
+    fn test_partial_jest_summary() {
+        let output = "\
+Test Suites: 2 passed, 2 total
+Tests:       7 passed, 7 total
+";
+        assert_eq!(
+            TestSummary::parse(output),
+            Some(TestSummary {
+                passed: Some(7),
+                failed: None,
+                skipped: None,
+            })
+        );
+    }
+
+    #[test]
+    fn test_output_without_summary() {
+        assert_eq!(
+            TestSummary::parse("Compiling executors v0.1.0\n3 passed"),
+            None
+        );
+    }
+}
@@ -0,0 +1,43 @@
+//! Wall-clock timestamps for normalized entries.
+//!
+//! Entries are stamped when their patch is built, i.e. when the line that produced them was read,
+//! rather than from any time the agent printed. Stamps never go backwards, even if the system
+//! clock does.
+
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::clock::{Clock, SystemClock};
+
+static LAST_STAMP_MICROS: AtomicI64 = AtomicI64::new(i64::MIN);
+
+/// Current time as ISO-8601 (UTC, microseconds), never earlier than a previously returned stamp.
+pub fn next_timestamp() -> String {
+    next_timestamp_from(&SystemClock)
+}
+
+/// [`next_timestamp`] reading the time from `clock`.
+pub fn next_timestamp_from(clock: &dyn Clock) -> String {
+    let now = DateTime::<Utc>::from(clock.now()).timestamp_micros();
+    let previous = LAST_STAMP_MICROS.fetch_max(now, Ordering::SeqCst);
+    let micros = previous.max(now);
+    DateTime::from_timestamp_micros(micros)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps_are_non_decreasing() {
+        let stamps: Vec<String> = (0..100).map(|_| next_timestamp()).collect();
+
+        for stamp in &stamps {
+            assert!(DateTime::parse_from_rfc3339(stamp).is_ok(), "{stamp}");
+        }
+        assert!(stamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
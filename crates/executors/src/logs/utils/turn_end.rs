@@ -0,0 +1,184 @@
+//! Reads how the agent ended its turn from its final message.
+//!
+//! Once the agent has exited, the entries in its [`MsgStore`] are replayed and the last assistant
+//! message with no tool call after it is taken as the one the turn ended on. That message is
+//! checked, whichever client wrote it, for setup steps the user has to take, a clarifying question
+//! the agent is waiting on and the follow-up work it suggests, reported in that order.
+
+use std::collections::BTreeMap;
+
+use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+
+use crate::logs::{
+    AgentEvent, NormalizedEntry, NormalizedEntryType,
+    utils::{
+        QuestionDetector, parse_setup_instructions, parse_suggested_actions,
+        patch::extract_normalized_entry_from_patch,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct TurnEndDetector {
+    questions: QuestionDetector,
+}
+
+impl TurnEndDetector {
+    pub fn new(questions: QuestionDetector) -> Self {
+        Self { questions }
+    }
+
+    /// Events for a turn that ended on `message`.
+    pub fn detect(&self, message: &str) -> Vec<AgentEvent> {
+        let question = self
+            .questions
+            .detect(message)
+            .map(|question| AgentEvent::QuestionPending { question });
+        let suggestions =
+            parse_suggested_actions(message).map(|items| AgentEvent::SuggestedActions { items });
+        parse_setup_instructions(message)
+            .into_iter()
+            .chain(question)
+            .chain(suggestions)
+            .collect()
+    }
+
+    /// Replay the normalized entries in `msg_store` and return the events for the message the
+    /// turn ended on, if it ended on one.
+    pub fn scan(&self, msg_store: &MsgStore) -> Vec<AgentEvent> {
+        let entries: BTreeMap<usize, NormalizedEntry> = msg_store
+            .get_history()
+            .iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => extract_normalized_entry_from_patch(patch),
+                _ => None,
+            })
+            .collect();
+        let mut last_message = None;
+        for entry in entries.values() {
+            match entry.entry_type {
+                NormalizedEntryType::AssistantMessage => last_message = Some(&entry.content),
+                // The agent moved on without waiting
+                NormalizedEntryType::ToolUse { .. } => last_message = None,
+                _ => {}
+            }
+        }
+        last_message.map_or_else(Vec::new, |message| self.detect(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logs::{
+        ActionType, ToolStatus,
+        utils::{ConversationPatch, EntryIndexProvider},
+    };
+
+    fn message(content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::AssistantMessage,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    fn store(entries: Vec<NormalizedEntry>) -> MsgStore {
+        let store = MsgStore::new();
+        let entry_index = EntryIndexProvider::start_from(&store);
+        for entry in entries {
+            store.push_patch(ConversationPatch::add_normalized_entry(
+                entry_index.next(),
+                entry,
+            ));
+        }
+        store
+    }
+
+    #[test]
+    fn test_question_pending_when_turn_ends_on_it() {
+        let detector = TurnEndDetector::new(QuestionDetector::new());
+        // Shaped like a Codex or Gemini turn, where events may follow the answer
+        let store = store(vec![
+            message("I've added the migration.\n\nShould I also update the docs?"),
+            AgentEvent::FileRead {
+                path: "README.md".to_string(),
+            }
+            .into_normalized_entry(),
+        ]);
+
+        assert_eq!(
+            detector.scan(&store),
+            vec![AgentEvent::QuestionPending {
+                question: "Should I also update the docs?".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tool_call_after_question_ends_nothing() {
+        let detector = TurnEndDetector::new(QuestionDetector::new());
+        let store = store(vec![
+            message("Should I also update the docs?"),
+            NormalizedEntry {
+                timestamp: None,
+                entry_type: NormalizedEntryType::ToolUse {
+                    tool_name: "read_file".to_string(),
+                    action_type: ActionType::FileRead {
+                        path: "README.md".to_string(),
+                    },
+                    status: ToolStatus::Created,
+                },
+                content: String::new(),
+                metadata: None,
+            },
+        ]);
+
+        assert!(detector.scan(&store).is_empty());
+        assert!(
+            TurnEndDetector::new(QuestionDetector::new())
+                .scan(&MsgStore::new())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_suggested_actions_when_turn_ends_on_them() {
+        let detector = TurnEndDetector::new(QuestionDetector::new());
+        let store = store(vec![message(
+            "Done.\n\n## Next steps\n1. Run the migrations\n2. Restart the server",
+        )]);
+
+        assert_eq!(
+            detector.scan(&store),
+            vec![AgentEvent::SuggestedActions {
+                items: vec![
+                    "Run the migrations".to_string(),
+                    "Restart the server".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_setup_instructions_reported_first() {
+        let detector = TurnEndDetector::new(QuestionDetector::new());
+        let store = store(vec![message(
+            "The tests need the database.\n\nPlease run `docker compose up -d db` first.\n\n\
+             Should I also seed it?",
+        )]);
+
+        assert_eq!(
+            detector.scan(&store),
+            vec![
+                AgentEvent::SetupInstruction {
+                    command: Some("docker compose up -d db".to_string()),
+                    text: "Please run `docker compose up -d db` first.".to_string(),
+                },
+                AgentEvent::QuestionPending {
+                    question: "Should I also seed it?".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Flags assistant messages in which the agent says it is unsure of its answer.
+//!
+//! Only first-person hedges about the agent's own work count ("I'm not sure", "I couldn't
+//! verify", "this might be wrong"); vaguer wording such as "probably" is too common in confident
+//! answers to be worth flagging. Quotes and code are skipped. The sentence containing the first
+//! hedge is reported as `AgentEvent::UncertaintyFlag`.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+static HEDGE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)\b(?:i['’]?m not (?:entirely |completely |100% |fully )?(?:sure|certain|confident)|i am not (?:entirely |completely |fully )?(?:sure|certain|confident)|i['’]?m unsure|i (?:could|might|may) be wrong|(?:this|that|it) (?:might|may|could) (?:be wrong|be incorrect|not work)|i (?:couldn['’]?t|could not|wasn['’]?t able to|was unable to|haven['’]?t been able to) (?:verify|confirm|test))\b",
+    )
+    .expect("valid hedge pattern")
+});
+
+static INLINE_CODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"`[^`\n]*`").expect("valid inline code pattern"));
+
+/// The sentence of `message` in which the agent hedges, if any.
+pub fn detect_uncertainty(message: &str) -> Option<String> {
+    let mut in_fence = false;
+    for line in message.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || trimmed.starts_with('>') {
+            continue;
+        }
+        let prose =
+            INLINE_CODE.replace_all(trimmed, |caps: &regex::Captures| " ".repeat(caps[0].len()));
+        if let Some(hedge) = HEDGE.find(&prose) {
+            return Some(sentence_around(trimmed, hedge.start(), hedge.end()));
+        }
+    }
+    None
+}
+
+/// The sentence of `line` spanning `start..end`, without list or emphasis markers.
+fn sentence_around(line: &str, start: usize, end: usize) -> String {
+    let is_boundary = |c: char| matches!(c, '.' | '!' | '?');
+    let from = line[..start].rfind(is_boundary).map_or(0, |i| i + 1);
+    let to = line[end..]
+        .find(is_boundary)
+        .map_or(line.len(), |i| end + i + 1);
+    line[from..to]
+        .trim()
+        .trim_start_matches(['-', '*', '#', ' '])
+        .trim_matches(['*', '_', ' '])
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uncertain_message_flagged() {
+        let message = "I updated the retry logic in `client.rs`.\n\n\
+            - The timeout is now 30s. I'm not entirely sure the backoff matches what the API \
+            expects, so please double-check it.\n";
+
+        assert_eq!(
+            detect_uncertainty(message),
+            Some(
+                "I'm not entirely sure the backoff matches what the API expects, so please \
+                 double-check it."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            detect_uncertainty("Done. I couldn't verify the migration because the DB is down"),
+            Some("I couldn't verify the migration because the DB is down".to_string())
+        );
+    }
+
+    #[test]
+    fn test_confident_message_not_flagged() {
+        let message = "I fixed the race in the watcher and all tests pass.\n\
+            Make sure to run `cargo test` before merging; it is probably worth a changelog entry.\n\
+            > I'm not sure this is right (quoting the issue)\n\
+            ```\n\
+            // this might be wrong\n\
+            ```\n";
+
+        assert_eq!(detect_uncertainty(message), None);
+        assert_eq!(detect_uncertainty(""), None);
+    }
+}
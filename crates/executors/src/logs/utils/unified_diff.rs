@@ -0,0 +1,204 @@
+//! Finds unified diffs in agent output and splits them into per-file hunks.
+//!
+//! Diffs show up in assistant messages (often inside a ```diff fence) and in the output of
+//! commands such as `git diff`. Surrounding text, `diff --git`/`index` lines and fences are
+//! skipped; a hunk ends once the line counts from its `@@` header are used up, which lets
+//! several files follow each other in one block.
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::logs::{AgentEvent, DiffHunk};
+
+static HUNK_HEADER: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").expect("valid hunk header")
+});
+
+/// The hunks a diff applies to one file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+impl FileDiff {
+    pub fn into_event(self) -> AgentEvent {
+        AgentEvent::Diff {
+            path: self.path,
+            hunks: self.hunks,
+        }
+    }
+}
+
+/// Path from a `---`/`+++` header line, without the `a/`/`b/` prefix or a trailing timestamp.
+fn header_path(line: &str) -> Option<&str> {
+    let path = line[4..].split('\t').next()?.trim();
+    if path.is_empty() || path == "/dev/null" {
+        return None;
+    }
+    Some(
+        path.strip_prefix("a/")
+            .or_else(|| path.strip_prefix("b/"))
+            .unwrap_or(path),
+    )
+}
+
+fn parse_count(value: Option<regex::Match<'_>>) -> u32 {
+    value.map_or(1, |m| m.as_str().parse().unwrap_or(0))
+}
+
+/// Every file diff in `text`, in order. Files without any hunk are dropped.
+pub fn parse_unified_diffs(text: &str) -> Vec<FileDiff> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut files: Vec<FileDiff> = Vec::new();
+    // Lines still expected from the old and new side of the open hunk
+    let mut remaining = (0u32, 0u32);
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        if remaining != (0, 0) {
+            let hunk = files
+                .last_mut()
+                .and_then(|file| file.hunks.last_mut())
+                .expect("open hunk");
+            match line.chars().next() {
+                Some(' ') | None => {
+                    remaining.0 = remaining.0.saturating_sub(1);
+                    remaining.1 = remaining.1.saturating_sub(1);
+                }
+                Some('-') => remaining.0 = remaining.0.saturating_sub(1),
+                Some('+') => remaining.1 = remaining.1.saturating_sub(1),
+                Some('\\') => {}
+                _ => {
+                    // Truncated hunk; re-read this line as ordinary text
+                    remaining = (0, 0);
+                    continue;
+                }
+            }
+            hunk.lines.push(line.to_string());
+            i += 1;
+            continue;
+        }
+
+        if line.starts_with("--- ")
+            && let Some(next) = lines.get(i + 1)
+            && next.starts_with("+++ ")
+        {
+            if let Some(path) = header_path(next).or_else(|| header_path(line)) {
+                files.push(FileDiff {
+                    path: path.to_string(),
+                    hunks: Vec::new(),
+                });
+            }
+            i += 2;
+            continue;
+        }
+
+        if let Some(captures) = HUNK_HEADER.captures(line)
+            && let Some(file) = files.last_mut()
+        {
+            let old_lines = parse_count(captures.get(2));
+            let new_lines = parse_count(captures.get(4));
+            file.hunks.push(DiffHunk {
+                header: line.to_string(),
+                old_start: captures[1].parse().unwrap_or(0),
+                old_lines,
+                new_start: captures[3].parse().unwrap_or(0),
+                new_lines,
+                lines: Vec::new(),
+            });
+            remaining = (old_lines, new_lines);
+        }
+        i += 1;
+    }
+
+    files.retain(|file| !file.hunks.is_empty());
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_file_diff_in_markdown() {
+        let text = "\
+Here is the change:
+
+```diff
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    println!(\"hi\");
++    println!(\"hello\");
+ }
+```
+";
+        let files = parse_unified_diffs(text);
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].path, "src/lib.rs");
+        assert_eq!(
+            files[0].hunks,
+            vec![DiffHunk {
+                header: "@@ -1,3 +1,3 @@".to_string(),
+                old_start: 1,
+                old_lines: 3,
+                new_start: 1,
+                new_lines: 3,
+                lines: vec![
+                    " fn main() {".to_string(),
+                    "-    println!(\"hi\");".to_string(),
+                    "+    println!(\"hello\");".to_string(),
+                    " }".to_string(),
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_multi_file_git_diff() {
+        let text = "\
+diff --git a/README.md b/README.md
+index 1111111..2222222 100644
+--- a/README.md
++++ b/README.md
+@@ -1 +1,2 @@
+ # Title
++Intro
+@@ -10,2 +11,1 @@ ## Usage
+-old
+ kept
+diff --git a/docs/new.md b/docs/new.md
+new file mode 100644
+--- /dev/null
++++ b/docs/new.md
+@@ -0,0 +1 @@
++new file
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+";
+        let files = parse_unified_diffs(text);
+
+        let paths: Vec<_> = files.iter().map(|file| file.path.as_str()).collect();
+        assert_eq!(paths, vec!["README.md", "docs/new.md", "gone.txt"]);
+        assert_eq!(files[0].hunks.len(), 2);
+        assert_eq!(files[0].hunks[1].old_start, 10);
+        assert_eq!(files[0].hunks[1].lines, vec!["-old", " kept"]);
+        assert_eq!(files[1].hunks[0].new_lines, 1);
+        assert_eq!(files[2].hunks[0].lines, vec!["-bye"]);
+    }
+
+    #[test]
+    fn test_text_without_diff() {
+        assert!(parse_unified_diffs("--- not a diff\nplain text\n@@ nothing @@").is_empty());
+    }
+}
@@ -2,7 +2,11 @@
 //!
 //! These helpers abstract over JSON vs TOML formats used by different agents.
 
-use std::{collections::HashMap, sync::LazyLock};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+};
 
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -43,6 +47,91 @@ impl McpConfig {
     pub fn set_servers(&mut self, servers: HashMap<String, serde_json::Value>) {
         self.servers = servers;
     }
+
+    /// The config that results from merging the preconfigured servers into `existing`, without
+    /// writing anything. Servers already in `existing` are kept unless a preconfigured server of
+    /// the same name replaces them; everything outside `servers_path` is left as is.
+    pub fn preview(&self, existing: &Value) -> Value {
+        let mut config = match existing {
+            Value::Object(map) if !map.is_empty() => existing.clone(),
+            _ => self.template.clone(),
+        };
+        let Some((last, parents)) = self.servers_path.split_last() else {
+            return config;
+        };
+        let mut current = &mut config;
+        for part in parents {
+            current = object_entry(current, part);
+        }
+        let servers = object_entry(current, last);
+        if let (Value::Object(servers), Some(preconfigured)) =
+            (servers, self.preconfigured.as_object())
+        {
+            for (name, server) in preconfigured {
+                if name != "meta" {
+                    servers.insert(name.clone(), server.clone());
+                }
+            }
+        }
+        config
+    }
+
+    /// Servers that [`Self::preview`] would add to, remove from or change in `existing`.
+    pub fn diff(&self, existing: &Value) -> McpConfigDiff {
+        let before = self.servers_at_path(existing);
+        let after = self.servers_at_path(&self.preview(existing));
+
+        let mut diff = McpConfigDiff::default();
+        for (name, server) in &after {
+            match before.get(name) {
+                None => diff.added.push(name.clone()),
+                Some(old) if old != server => diff.changed.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .keys()
+            .filter(|name| !after.contains_key(*name))
+            .cloned()
+            .collect();
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort();
+        diff
+    }
+
+    fn servers_at_path(&self, config: &Value) -> Map<String, Value> {
+        self.servers_path
+            .iter()
+            .try_fold(config, |value, key| value.get(key))
+            .and_then(Value::as_object)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Server names an MCP config merge would touch, each list sorted.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct McpConfigDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+/// The object under `key` in `value`, created (and `value` made an object) if missing.
+fn object_entry<'a>(value: &'a mut Value, key: &str) -> &'a mut Value {
+    if !value.is_object() {
+        *value = Value::Object(Map::new());
+    }
+    let entry = value
+        .as_object_mut()
+        .expect("value was just made an object")
+        .entry(key)
+        .or_insert_with(|| Value::Object(Map::new()));
+    if !entry.is_object() {
+        *entry = Value::Object(Map::new());
+    }
+    entry
 }
 
 /// Read an agent's external config file (JSON or TOML) and normalize it to serde_json::Value.
@@ -85,6 +174,41 @@ pub async fn write_agent_config(
     Ok(())
 }
 
+/// Fresh location for a per-spawn MCP config written by [`write_mcp_config_without`].
+pub fn temp_mcp_config_path() -> PathBuf {
+    std::env::temp_dir().join(format!("vk-mcp-{}.json", uuid::Uuid::new_v4()))
+}
+
+/// Write the MCP servers configured for `agent`, minus those named in `disabled`, to `path` as an
+/// `mcpServers` config for a single spawn. The agent's own config file is left untouched.
+pub async fn write_mcp_config_without(
+    agent: &CodingAgent,
+    disabled: &[String],
+    path: &Path,
+) -> Result<(), ExecutorError> {
+    let config_path = agent.default_mcp_config_path().ok_or_else(|| {
+        ExecutorError::Unsupported("MCP configuration is not supported by this agent".to_string())
+    })?;
+    let mcp_config = agent.get_mcp_config();
+    let config = read_agent_config(&config_path, &mcp_config).await?;
+
+    let mut servers = mcp_config
+        .servers_path
+        .iter()
+        .try_fold(&config, |value, key| value.get(key))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    for name in disabled {
+        if servers.remove(name).is_none() {
+            tracing::warn!("MCP server {name} is not configured, nothing to disable");
+        }
+    }
+
+    let content = serde_json::to_string_pretty(&serde_json::json!({ "mcpServers": servers }))?;
+    fs::write(path, content).await.map_err(ExecutorError::Io)
+}
+
 type ServerMap = Map<String, Value>;
 
 fn is_http_server(s: &Map<String, Value>) -> bool {
@@ -300,7 +424,8 @@ impl CodingAgent {
             CodingAgent::Opencode(_) => Opencode,
             CodingAgent::Copilot(..) => Copilot,
             CodingAgent::Jbai(agent) => match agent.client {
-                crate::executors::jbai::JbaiClient::Claude => Passthrough,
+                crate::executors::jbai::JbaiClient::Claude
+                | crate::executors::jbai::JbaiClient::Custom(_) => Passthrough,
                 crate::executors::jbai::JbaiClient::Codex => Codex,
                 crate::executors::jbai::JbaiClient::Gemini => Gemini,
                 crate::executors::jbai::JbaiClient::Opencode => Opencode,
@@ -313,3 +438,114 @@ impl CodingAgent {
         apply_adapter(adapter, canonical)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_disabled_server_removed_from_temp_config() {
+        let home_dir = tempfile::tempdir().unwrap();
+        let home = home_dir.path();
+        std::fs::write(
+            home.join(".claude.json"),
+            serde_json::json!({
+                "mcpServers": {
+                    "flaky": { "command": "flaky-mcp" },
+                    "docs": { "command": "docs-mcp", "args": ["--stdio"] },
+                },
+                "theme": "dark",
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let agent: CodingAgent = serde_json::from_value(serde_json::json!({
+            "CLAUDE_CODE": { "isolated_home": home }
+        }))
+        .unwrap();
+        let path = home.join("spawn-mcp.json");
+
+        write_mcp_config_without(&agent, &["flaky".to_string()], &path)
+            .await
+            .unwrap();
+
+        let written: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            written,
+            serde_json::json!({
+                "mcpServers": { "docs": { "command": "docs-mcp", "args": ["--stdio"] } }
+            })
+        );
+        // The agent's own config still has every server
+        let original: Value =
+            serde_json::from_str(&std::fs::read_to_string(home.join(".claude.json")).unwrap())
+                .unwrap();
+        assert!(original["mcpServers"].get("flaky").is_some());
+    }
+
+    fn jbai(client: &str) -> CodingAgent {
+        serde_json::from_value(serde_json::json!({ "JBAI": { "client": client } })).unwrap()
+    }
+
+    #[test]
+    fn test_preview_into_empty_config() {
+        for (client, key) in [
+            ("CLAUDE", "mcpServers"),
+            ("CODEX", "mcp_servers"),
+            ("OPENCODE", "mcp"),
+        ] {
+            let mcp_config = jbai(client).get_mcp_config();
+            let preconfigured = mcp_config.preconfigured.as_object().unwrap();
+            let mut expected: Vec<String> = preconfigured
+                .keys()
+                .filter(|name| *name != "meta")
+                .cloned()
+                .collect();
+            expected.sort();
+
+            let merged = mcp_config.preview(&serde_json::json!({}));
+            let servers = merged[key].as_object().unwrap();
+            assert_eq!(servers.len(), expected.len(), "{client}");
+            assert!(servers.get("meta").is_none(), "{client}");
+            assert_eq!(
+                mcp_config.diff(&serde_json::json!({})),
+                McpConfigDiff {
+                    added: expected,
+                    ..Default::default()
+                },
+                "{client}"
+            );
+        }
+        // Opencode's template keeps its schema reference
+        let merged = jbai("OPENCODE").get_mcp_config().preview(&Value::Null);
+        assert_eq!(merged["$schema"], "https://opencode.ai/config.json");
+    }
+
+    #[test]
+    fn test_preview_preserves_unrelated_server() {
+        let mcp_config = jbai("CLAUDE").get_mcp_config();
+        let existing = serde_json::json!({
+            "theme": "dark",
+            "mcpServers": {
+                "internal_docs": { "command": "docs-mcp", "args": ["--stdio"] },
+                "playwright": { "command": "playwright-mcp" },
+            },
+        });
+
+        let merged = mcp_config.preview(&existing);
+
+        assert_eq!(merged["theme"], "dark");
+        assert_eq!(
+            merged["mcpServers"]["internal_docs"],
+            existing["mcpServers"]["internal_docs"]
+        );
+        let diff = mcp_config.diff(&existing);
+        assert!(diff.removed.is_empty());
+        assert_eq!(diff.changed, ["playwright"]);
+        assert!(!diff.added.contains(&"internal_docs".to_string()));
+        assert!(diff.added.contains(&"vibe_kanban".to_string()));
+        // The input itself is untouched
+        assert!(existing["mcpServers"].get("vibe_kanban").is_none());
+    }
+}
@@ -0,0 +1,209 @@
+//! One retry on the profile's `fallback_model` when the requested model cannot be used.
+//!
+//! A client that rejects the model while starting is caught by the early-exit check: failures
+//! classified as [`ExecutorError::ModelNotEntitled`] or [`ExecutorError::UnknownModel`] are
+//! respawned at once by [`spawn_with_fallback`]. Most clients only report it once the first turn
+//! reaches the API, long after that window, so a run that fails later is retried from its exit
+//! instead, when [`fallback_after_exit`] finds the refusal in its log. Either way the switch is
+//! recorded as `AgentEvent::ModelFallback`.
+
+use std::future::Future;
+
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    executors::{ExecutorError, SpawnedChild},
+    logs::{AgentEvent, utils::ModelErrorDetector},
+};
+
+/// The model a startup failure blames, if the failure is about model availability.
+pub fn unavailable_model(err: &ExecutorError) -> Option<&str> {
+    match err {
+        ExecutorError::ModelNotEntitled { model } | ExecutorError::UnknownModel { model } => {
+            Some(model)
+        }
+        _ => None,
+    }
+}
+
+/// Run `spawn` with no model change, then once more with `fallback_model` if the first attempt
+/// failed because its model is unavailable. `spawn` must include the early-exit check and error
+/// classification so that model failures are recognisable.
+pub async fn spawn_with_fallback<F, Fut>(
+    fallback_model: Option<&str>,
+    mut spawn: F,
+) -> Result<SpawnedChild, ExecutorError>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<SpawnedChild, ExecutorError>>,
+{
+    let err = match spawn(None).await {
+        Ok(spawned) => return Ok(spawned),
+        Err(err) => err,
+    };
+    let (Some(from), Some(to)) = (unavailable_model(&err), fallback_model) else {
+        return Err(err);
+    };
+    if from == to {
+        return Err(err);
+    }
+
+    tracing::warn!("Model {from} is unavailable ({err}); retrying with fallback model {to}");
+    let from = from.to_string();
+    let mut spawned = spawn(Some(to.to_string())).await?;
+    spawned.events.push(AgentEvent::ModelFallback {
+        from,
+        to: to.to_string(),
+    });
+    Ok(spawned)
+}
+
+/// The `ModelFallback` for a run that failed after it started, if `detector` finds its log
+/// blaming an unavailable model other than `fallback_model`.
+pub fn fallback_after_exit(
+    detector: ModelErrorDetector,
+    msg_store: &MsgStore,
+    fallback_model: &str,
+) -> Option<AgentEvent> {
+    let (AgentEvent::ModelNotEntitled { model } | AgentEvent::UnknownModel { model }) =
+        detector.scan(msg_store)?
+    else {
+        return None;
+    };
+    (model != fallback_model).then(|| AgentEvent::ModelFallback {
+        from: model,
+        to: fallback_model.to_string(),
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::process::Stdio;
+
+    use command_group::AsyncCommandGroup;
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::{
+        early_exit::{EARLY_EXIT_WINDOW, check_early_exit},
+        logs::{
+            NormalizedEntry, NormalizedEntryError, NormalizedEntryType, utils::ConversationPatch,
+        },
+    };
+
+    fn spawn_true() -> SpawnedChild {
+        tokio::process::Command::new("true")
+            .group_spawn()
+            .unwrap()
+            .into()
+    }
+
+    fn not_entitled(model: &str) -> ExecutorError {
+        ExecutorError::ModelNotEntitled {
+            model: model.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_succeeds() {
+        let mut attempts = Vec::new();
+        let mut spawned = spawn_with_fallback(Some("sonnet"), |model| {
+            attempts.push(model.clone());
+            async move {
+                match model {
+                    None => Err(not_entitled("opus")),
+                    Some(_) => Ok(spawn_true()),
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(attempts, vec![None, Some("sonnet".to_string())]);
+        assert_eq!(
+            spawned.events,
+            vec![AgentEvent::ModelFallback {
+                from: "opus".to_string(),
+                to: "sonnet".to_string(),
+            }]
+        );
+        let _ = spawned.child.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_no_fallback_configured_fails() {
+        let mut attempts = 0;
+        let result = spawn_with_fallback(None, |_| {
+            attempts += 1;
+            async { Err(not_entitled("opus")) }
+        })
+        .await;
+
+        assert!(
+            matches!(result, Err(ExecutorError::ModelNotEntitled { model }) if model == "opus")
+        );
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_other_failures_are_not_retried() {
+        let mut attempts = 0;
+        let result = spawn_with_fallback(Some("sonnet"), |_| {
+            attempts += 1;
+            async {
+                Err(ExecutorError::SpawnFailed {
+                    status: "exit status: 2".to_string(),
+                    stderr: "unknown flag --foo".to_string(),
+                })
+            }
+        })
+        .await;
+
+        assert!(matches!(result, Err(ExecutorError::SpawnFailed { .. })));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_model_error_after_early_exit_window_falls_back() {
+        let mut command = tokio::process::Command::new("sh");
+        command
+            .arg("-c")
+            .arg("sleep 0.5; echo 'Error: unknown model: opus-9' >&2; exit 1")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut spawned: SpawnedChild = command.group_spawn().unwrap().into();
+
+        // Still running when the window closes, so the spawn itself is not retried
+        check_early_exit(&mut spawned, EARLY_EXIT_WINDOW)
+            .await
+            .unwrap();
+        let mut stderr = String::new();
+        let mut pipe = spawned.child.inner().stderr.take().unwrap();
+        pipe.read_to_string(&mut stderr).await.unwrap();
+        assert!(!spawned.child.wait().await.unwrap().success());
+
+        // As the stderr normalizer adds it
+        let store = MsgStore::new();
+        store.push_patch(ConversationPatch::add_normalized_entry(
+            0,
+            NormalizedEntry {
+                timestamp: None,
+                entry_type: NormalizedEntryType::ErrorMessage {
+                    error_type: NormalizedEntryError::Other,
+                },
+                content: stderr.trim().to_string(),
+                metadata: None,
+            },
+        ));
+
+        let detector = ModelErrorDetector::new(&[], Some("opus-9".to_string()));
+        assert_eq!(
+            fallback_after_exit(detector.clone(), &store, "sonnet"),
+            Some(AgentEvent::ModelFallback {
+                from: "opus-9".to_string(),
+                to: "sonnet".to_string(),
+            })
+        );
+        assert_eq!(fallback_after_exit(detector, &store, "opus-9"), None);
+    }
+}
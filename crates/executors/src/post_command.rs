@@ -0,0 +1,245 @@
+//! Verification command run after a coding agent exits.
+//!
+//! [`CodingAgentInitialRequest::post_command`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::post_command)
+//! runs in the agent's effective directory once the agent process is gone, e.g. `cargo build`,
+//! with the same environment the agent had. Its combined stdout and stderr are reported as an [`AgentEvent::PostCommand`] entry and the
+//! overall outcome as [`AgentEvent::AgentFinished`].
+
+use std::{path::Path, process::Stdio, time::Duration};
+
+use tokio::process::Command;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    env::ExecutionEnv,
+    executors::ExecutorError,
+    logs::{
+        AgentEvent,
+        utils::{ConversationPatch, EntryIndexProvider},
+    },
+};
+
+/// Only the tail of the command's output is kept in the entry.
+pub const POST_COMMAND_OUTPUT_LIMIT: usize = 16 * 1024;
+
+/// How long the post-command may run before it is killed and reported as failed.
+pub const POST_COMMAND_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PostCommandResult {
+    pub command: String,
+    pub output: String,
+    pub exit_code: Option<i32>,
+}
+
+impl PostCommandResult {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0)
+    }
+
+    /// Patches adding the post-command entry and the `AgentFinished` entry after the existing
+    /// entries in `msg_store`.
+    pub fn into_patches(
+        self,
+        msg_store: &MsgStore,
+        agent_exit_code: Option<i32>,
+    ) -> Vec<json_patch::Patch> {
+        let index = EntryIndexProvider::start_from(msg_store);
+        let finished = AgentEvent::AgentFinished {
+            exit_code: agent_exit_code,
+            post_command_exit_code: self.exit_code,
+        };
+        let post_command = AgentEvent::PostCommand {
+            command: self.command,
+            output: self.output,
+            exit_code: self.exit_code,
+        };
+        [post_command, finished]
+            .into_iter()
+            .map(|event| {
+                ConversationPatch::add_normalized_entry(index.next(), event.into_normalized_entry())
+            })
+            .collect()
+    }
+}
+
+/// Run `command` (program and arguments) in `dir`. A command that fails to start or outlives
+/// `timeout` is reported through the result, with the reason as output and no exit code.
+pub async fn run_post_command(
+    command: &[String],
+    dir: &Path,
+    env: &ExecutionEnv,
+    timeout: Duration,
+) -> Result<PostCommandResult, ExecutorError> {
+    let Some((program, args)) = command.split_first() else {
+        return Err(ExecutorError::InvalidArg(
+            "post_command must not be empty".to_string(),
+        ));
+    };
+    let display =
+        shlex::try_join(command.iter().map(String::as_str)).unwrap_or_else(|_| command.join(" "));
+
+    let mut cmd = Command::new(program);
+    cmd.args(args)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    env.apply_to_command(&mut cmd);
+
+    // Dropping the output future on timeout kills the command
+    let (output, exit_code) = match tokio::time::timeout(timeout, cmd.output()).await {
+        Ok(Ok(output)) => {
+            let mut combined = String::from_utf8_lossy(&output.stdout).into_owned();
+            combined.push_str(&String::from_utf8_lossy(&output.stderr));
+            (combined, output.status.code())
+        }
+        Ok(Err(err)) => (format!("Failed to run post-command: {err}"), None),
+        Err(_) => (format!("Post-command timed out after {timeout:?}"), None),
+    };
+
+    Ok(PostCommandResult {
+        command: display,
+        output: output_tail(&output).to_string(),
+        exit_code,
+    })
+}
+
+fn output_tail(output: &str) -> &str {
+    if output.len() <= POST_COMMAND_OUTPUT_LIMIT {
+        return output;
+    }
+    let mut start = output.len() - POST_COMMAND_OUTPUT_LIMIT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    &output[start..]
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::logs::{NormalizedEntryType, utils::patch::extract_normalized_entry_from_patch};
+
+    fn command(parts: &[&str]) -> Vec<String> {
+        parts.iter().map(|part| part.to_string()).collect()
+    }
+
+    fn scratch_dir() -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("post-command-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_passing_post_command() {
+        let dir = scratch_dir();
+        std::fs::write(dir.join("marker.txt"), "built").unwrap();
+
+        let result = run_post_command(
+            &command(&["cat", "marker.txt"]),
+            &dir,
+            &ExecutionEnv::new(),
+            POST_COMMAND_TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert!(result.success());
+        assert_eq!(result.command, "cat marker.txt");
+        assert_eq!(result.output, "built");
+    }
+
+    #[tokio::test]
+    async fn test_failing_post_command_is_reflected_in_agent_finished() {
+        let dir = scratch_dir();
+        let result = run_post_command(
+            &command(&["sh", "-c", "echo 'error: build failed' >&2; exit 3"]),
+            &dir,
+            &ExecutionEnv::new(),
+            POST_COMMAND_TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert!(!result.success());
+        assert_eq!(result.exit_code, Some(3));
+        assert_eq!(result.output, "error: build failed\n");
+
+        let events: Vec<_> = result
+            .into_patches(&MsgStore::new(), Some(0))
+            .iter()
+            .filter_map(extract_normalized_entry_from_patch)
+            .map(|(index, entry)| match entry.entry_type {
+                NormalizedEntryType::AgentEvent { event } => (index, event),
+                other => panic!("unexpected entry {other:?}"),
+            })
+            .collect();
+        assert_eq!(
+            events[1],
+            (
+                1,
+                AgentEvent::AgentFinished {
+                    exit_code: Some(0),
+                    post_command_exit_code: Some(3),
+                }
+            )
+        );
+        assert!(matches!(
+            events[0],
+            (
+                0,
+                AgentEvent::PostCommand {
+                    exit_code: Some(3),
+                    ..
+                }
+            )
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_empty_post_command_is_rejected() {
+        let result = run_post_command(
+            &[],
+            Path::new("."),
+            &ExecutionEnv::new(),
+            POST_COMMAND_TIMEOUT,
+        )
+        .await;
+        assert!(matches!(result, Err(ExecutorError::InvalidArg(_))));
+    }
+
+    #[tokio::test]
+    async fn test_post_command_sees_run_env() {
+        let dir = scratch_dir();
+        let mut env = ExecutionEnv::new();
+        env.insert("VK_TASK_ID", "task-1");
+        env.insert("HOME", dir.to_string_lossy());
+
+        let result = run_post_command(
+            &command(&["sh", "-c", "echo \"$VK_TASK_ID $HOME\""]),
+            &dir,
+            &env,
+            POST_COMMAND_TIMEOUT,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.output, format!("task-1 {}\n", dir.display()));
+    }
+
+    #[tokio::test]
+    async fn test_hung_post_command_times_out() {
+        let result = run_post_command(
+            &command(&["sleep", "30"]),
+            &scratch_dir(),
+            &ExecutionEnv::new(),
+            Duration::from_millis(100),
+        )
+        .await
+        .unwrap();
+
+        assert!(!result.success());
+        assert_eq!(result.exit_code, None);
+        assert_eq!(result.output, "Post-command timed out after 100ms");
+    }
+}
@@ -0,0 +1,111 @@
+//! Consolidated readiness checks for a coding agent, run ahead of display in the settings UI.
+//!
+//! Unlike [`CodingAgentInitialRequest::validate`](crate::actions::coding_agent_initial::CodingAgentInitialRequest::validate),
+//! every check is reported with its status so the UI can show what passed as well as what failed.
+
+use std::path::Path;
+
+use serde::Serialize;
+use ts_rs::TS;
+use workspace_utils::shell::resolve_executable_path;
+
+use crate::executors::ExecutorError;
+
+#[derive(Debug, Clone, Copy, Serialize, TS, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum PreflightCheckKind {
+    /// The client binary the command resolves to is on `PATH`
+    Binary,
+    /// The client is installed and logged in, with credentials available
+    Availability,
+    /// The requested model can be used with this client
+    Model,
+    /// The effective working directory exists
+    WorkingDir,
+}
+
+#[derive(Debug, Clone, Serialize, TS, PartialEq, Eq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+#[ts(export)]
+pub enum PreflightStatus {
+    Ok,
+    Failed { reason: String },
+}
+
+impl PreflightStatus {
+    pub fn failed(reason: impl ToString) -> Self {
+        Self::Failed {
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct PreflightCheck {
+    pub kind: PreflightCheckKind,
+    pub status: PreflightStatus,
+}
+
+#[derive(Debug, Clone, Default, Serialize, TS, PartialEq, Eq)]
+#[ts(export)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    pub fn push(&mut self, kind: PreflightCheckKind, status: PreflightStatus) {
+        self.checks.push(PreflightCheck { kind, status });
+    }
+
+    pub fn all_passed(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status == PreflightStatus::Ok)
+    }
+
+    /// Status of the check of `kind`, if it was run.
+    pub fn status(&self, kind: PreflightCheckKind) -> Option<&PreflightStatus> {
+        self.checks
+            .iter()
+            .find(|check| check.kind == kind)
+            .map(|check| &check.status)
+    }
+}
+
+/// Check that the program at the head of `base_command` resolves to an executable.
+pub async fn check_binary(base_command: &str) -> PreflightStatus {
+    match ensure_executable(base_command).await {
+        Ok(()) => PreflightStatus::Ok,
+        Err(ExecutorError::InvalidArg(reason)) => PreflightStatus::failed(reason),
+        Err(err) => PreflightStatus::failed(err),
+    }
+}
+
+/// Resolve the program at the head of `base_command` and check it can be run, so a missing or
+/// non-executable client fails with [`ExecutorError::ExecutableNotFound`] instead of an OS spawn
+/// error.
+pub async fn ensure_executable(base_command: &str) -> Result<(), ExecutorError> {
+    let program = shlex::split(base_command)
+        .and_then(|parts| parts.into_iter().next())
+        .ok_or_else(|| {
+            ExecutorError::InvalidArg(format!("Cannot parse command `{base_command}`"))
+        })?;
+    match resolve_executable_path(&program).await {
+        Some(path) if is_executable(&path) => Ok(()),
+        _ => Err(ExecutorError::ExecutableNotFound { program }),
+    }
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
@@ -10,8 +10,9 @@ use serde::{Deserialize, Deserializer, Serialize, de::Error as DeError};
 use thiserror::Error;
 use ts_rs::TS;
 
-use crate::executors::{
-    AvailabilityInfo, BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor,
+use crate::{
+    env::ExecutionEnv,
+    executors::{AvailabilityInfo, BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor},
 };
 
 /// Return the canonical form for variant keys.
@@ -228,6 +229,7 @@ impl ExecutorConfigs {
             Ok(mut user_overrides) => {
                 tracing::info!("Loaded user profile overrides from profiles.json");
                 user_overrides.canonicalise();
+                user_overrides.warn_invalid_jbai_profiles();
                 Self::merge_with_defaults(defaults, user_overrides)
             }
             Err(e) => {
@@ -350,6 +352,21 @@ impl ExecutorConfigs {
         Ok(overrides)
     }
 
+    /// Log configuration problems in user-defined JBAI profiles, so they surface before a run.
+    fn warn_invalid_jbai_profiles(&self) {
+        let Some(profile) = self.executors.get(&BaseCodingAgent::Jbai) else {
+            return;
+        };
+        let env = ExecutionEnv::new();
+        for (variant, agent) in &profile.configurations {
+            if let CodingAgent::Jbai(agent) = agent
+                && let Err(e) = agent.validate(&env)
+            {
+                tracing::warn!("JBAI profile {variant} is misconfigured: {e}");
+            }
+        }
+    }
+
     /// Validate that merged profiles are consistent and valid
     fn validate_merged(merged: &Self) -> Result<(), ProfileError> {
         for (executor_key, profile) in &merged.executors {
@@ -439,34 +456,23 @@ impl ExecutorConfigs {
                 (
                     AvailabilityInfo::LoginDetected {
                         last_auth_timestamp: time_a,
+                        ..
                     },
                     AvailabilityInfo::LoginDetected {
                         last_auth_timestamp: time_b,
+                        ..
                     },
                 ) => time_b.cmp(time_a),
-                // LoginDetected > InstallationFound
-                (AvailabilityInfo::LoginDetected { .. }, AvailabilityInfo::InstallationFound) => {
-                    std::cmp::Ordering::Less
-                }
-                (AvailabilityInfo::InstallationFound, AvailabilityInfo::LoginDetected { .. }) => {
-                    std::cmp::Ordering::Greater
-                }
-                // LoginDetected > NotFound
-                (AvailabilityInfo::LoginDetected { .. }, AvailabilityInfo::NotFound) => {
-                    std::cmp::Ordering::Less
-                }
-                (AvailabilityInfo::NotFound, AvailabilityInfo::LoginDetected { .. }) => {
-                    std::cmp::Ordering::Greater
-                }
-                // InstallationFound > NotFound
-                (AvailabilityInfo::InstallationFound, AvailabilityInfo::NotFound) => {
-                    std::cmp::Ordering::Less
-                }
-                (AvailabilityInfo::NotFound, AvailabilityInfo::InstallationFound) => {
-                    std::cmp::Ordering::Greater
+                // LoginDetected > Expired > InstallationFound > NotFound
+                (a, b) => {
+                    let rank = |info: &AvailabilityInfo| match info {
+                        AvailabilityInfo::LoginDetected { .. } => 0,
+                        AvailabilityInfo::Expired { .. } => 1,
+                        AvailabilityInfo::InstallationFound => 2,
+                        AvailabilityInfo::NotFound => 3,
+                    };
+                    rank(a).cmp(&rank(b))
                 }
-                // Same state - equal
-                _ => std::cmp::Ordering::Equal,
             }
         });
 
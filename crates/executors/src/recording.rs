@@ -0,0 +1,467 @@
+//! Record/replay proxy for the model provider traffic of an agent run.
+//!
+//! With `record_dir` set, the provider base URL variables (e.g. `ANTHROPIC_BASE_URL`) point the
+//! agent at a local proxy that forwards each request upstream and stores the exchange as a JSON
+//! cassette. With `replay_dir` set, the proxy answers from those cassettes in recorded order and
+//! never touches the network. Base URLs are used rather than `HTTPS_PROXY` because a CONNECT
+//! tunnel would hide the TLS traffic from the proxy.
+//!
+//! Cassettes are numbered per run, so each recorded run should get its own directory.
+
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Once},
+};
+
+use axum::{
+    Router,
+    body::{Body, Bytes},
+    extract::{Path as UrlPath, State},
+    http::{HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    routing::any,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::{sync::oneshot, task::JoinHandle};
+
+use crate::{
+    command::CmdOverrides,
+    env::ExecutionEnv,
+    executors::{ExecutorError, SpawnedChild},
+};
+
+/// A model provider whose base URL can be redirected through the proxy.
+#[derive(Debug, Clone, Copy)]
+pub struct Provider {
+    pub env_var: &'static str,
+    /// Path prefix the proxy serves this provider under.
+    pub prefix: &'static str,
+    pub default_upstream: &'static str,
+}
+
+pub const PROVIDERS: &[Provider] = &[
+    Provider {
+        env_var: "ANTHROPIC_BASE_URL",
+        prefix: "anthropic",
+        default_upstream: "https://api.anthropic.com",
+    },
+    Provider {
+        env_var: "OPENAI_BASE_URL",
+        prefix: "openai",
+        default_upstream: "https://api.openai.com/v1",
+    },
+    Provider {
+        env_var: "GOOGLE_GEMINI_BASE_URL",
+        prefix: "gemini",
+        default_upstream: "https://generativelanguage.googleapis.com",
+    },
+];
+
+/// Headers that describe a single hop and must not be copied between connections.
+const HOP_HEADERS: &[&str] = &[
+    "host",
+    "connection",
+    "content-length",
+    "transfer-encoding",
+    "accept-encoding",
+    "content-encoding",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl RecordingMode {
+    pub fn from_overrides(cmd: &CmdOverrides) -> Result<Option<Self>, ExecutorError> {
+        match (&cmd.record_dir, &cmd.replay_dir) {
+            (Some(_), Some(_)) => Err(ExecutorError::Unsupported(
+                "record_dir and replay_dir cannot be combined".to_string(),
+            )),
+            (Some(dir), None) => Ok(Some(Self::Record(dir.clone()))),
+            (None, Some(dir)) => Ok(Some(Self::Replay(dir.clone()))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn dir(&self) -> &Path {
+        match self {
+            Self::Record(dir) | Self::Replay(dir) => dir,
+        }
+    }
+}
+
+/// One recorded request/response exchange.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Cassette {
+    method: String,
+    path: String,
+    request_body: String,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+struct ProxyState {
+    mode: RecordingMode,
+    /// Provider prefix -> upstream base URL, used in record mode.
+    upstreams: HashMap<String, String>,
+    /// Requests seen so far per method and path, giving each exchange its sequence number.
+    counters: Mutex<HashMap<String, usize>>,
+    client: reqwest::Client,
+}
+
+impl ProxyState {
+    fn cassette_path(&self, method: &Method, path: &str) -> PathBuf {
+        let key = format!("{method} {path}");
+        let seq = {
+            let mut counters = self.counters.lock().unwrap();
+            let counter = counters.entry(key.clone()).or_default();
+            *counter += 1;
+            *counter - 1
+        };
+        let hex = Sha256::digest(key.as_bytes())
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>();
+        self.mode
+            .dir()
+            .join(format!("{}-{seq:04}.json", &hex[..16]))
+    }
+}
+
+fn ensure_rustls_crypto_provider() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        if let Err(err) = rustls::crypto::aws_lc_rs::default_provider().install_default() {
+            tracing::debug!("rustls crypto provider install failed: {err:?}");
+        }
+    });
+}
+
+/// A running record/replay proxy; it stops when dropped.
+pub struct RecordingProxy {
+    addr: SocketAddr,
+    server: JoinHandle<()>,
+}
+
+impl Drop for RecordingProxy {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+impl RecordingProxy {
+    /// Start a proxy on a local port. `upstreams` maps provider prefixes to upstream base URLs.
+    pub async fn start(
+        mode: RecordingMode,
+        upstreams: HashMap<String, String>,
+    ) -> std::io::Result<Self> {
+        ensure_rustls_crypto_provider();
+        tokio::fs::create_dir_all(mode.dir()).await?;
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(std::io::Error::other)?;
+        let state = Arc::new(ProxyState {
+            mode,
+            upstreams,
+            counters: Mutex::new(HashMap::new()),
+            client,
+        });
+        let app = Router::new()
+            .route("/{provider}/{*path}", any(handle))
+            .with_state(state);
+
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0)).await?;
+        let addr = listener.local_addr()?;
+        let server = tokio::spawn(async move {
+            if let Err(err) = axum::serve(listener, app).await {
+                tracing::warn!("Recording proxy stopped: {err}");
+            }
+        });
+
+        Ok(Self { addr, server })
+    }
+
+    /// Start a proxy for `mode`, forwarding each provider to the base URL the agent would have
+    /// used: the profile env, then `env`, then the provider default.
+    pub async fn start_for(
+        mode: RecordingMode,
+        cmd: &CmdOverrides,
+        env: &ExecutionEnv,
+    ) -> std::io::Result<Self> {
+        let upstreams = PROVIDERS
+            .iter()
+            .map(|provider| {
+                let upstream = cmd
+                    .env
+                    .as_ref()
+                    .and_then(|vars| vars.get(provider.env_var).cloned())
+                    .or_else(|| env.lookup(provider.env_var))
+                    .unwrap_or_else(|| provider.default_upstream.to_string());
+                (provider.prefix.to_string(), upstream)
+            })
+            .collect();
+        Self::start(mode, upstreams).await
+    }
+
+    pub fn base_url(&self, provider: &Provider) -> String {
+        format!("http://{}/{}", self.addr, provider.prefix)
+    }
+
+    /// Base URL variables that route every known provider through this proxy.
+    pub fn env_vars(&self) -> HashMap<String, String> {
+        PROVIDERS
+            .iter()
+            .map(|provider| (provider.env_var.to_string(), self.base_url(provider)))
+            .collect()
+    }
+
+    /// Keep the proxy running until the container stops waiting on `spawned`.
+    pub fn attach(self, spawned: &mut SpawnedChild) {
+        let upstream = spawned.exit_signal.take();
+        let (mut exit_tx, exit_rx) = oneshot::channel();
+        spawned.exit_signal = Some(exit_rx);
+
+        tokio::spawn(async move {
+            let upstream = async move {
+                match upstream {
+                    Some(rx) => rx.await.ok(),
+                    None => std::future::pending().await,
+                }
+            };
+
+            let outcome = tokio::select! {
+                result = upstream => result,
+                // Container stopped listening (process exited on its own)
+                _ = exit_tx.closed() => None,
+            };
+            drop(self);
+
+            if let Some(result) = outcome {
+                let _ = exit_tx.send(result);
+            }
+        });
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<ProxyState>>,
+    UrlPath((provider, path)): UrlPath<(String, String)>,
+    method: Method,
+    uri: Uri,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let path = match uri.query() {
+        Some(query) => format!("/{provider}/{path}?{query}"),
+        None => format!("/{provider}/{path}"),
+    };
+    let cassette_path = state.cassette_path(&method, &path);
+
+    let result = match &state.mode {
+        RecordingMode::Record(_) => {
+            record(
+                &state,
+                &provider,
+                &path,
+                method,
+                headers,
+                body,
+                &cassette_path,
+            )
+            .await
+        }
+        RecordingMode::Replay(_) => replay(&cassette_path).await,
+    };
+    match result {
+        Ok(cassette) => cassette_response(cassette),
+        Err((status, message)) => {
+            tracing::warn!("Recording proxy failed for {path}: {message}");
+            (status, message).into_response()
+        }
+    }
+}
+
+async fn record(
+    state: &ProxyState,
+    provider: &str,
+    path: &str,
+    method: Method,
+    headers: HeaderMap,
+    body: Bytes,
+    cassette_path: &Path,
+) -> Result<Cassette, (StatusCode, String)> {
+    let upstream = state.upstreams.get(provider).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("unknown provider `{provider}`"),
+        )
+    })?;
+    let url = format!(
+        "{}{}",
+        upstream.trim_end_matches('/'),
+        &path[provider.len() + 1..]
+    );
+
+    let mut request = state.client.request(method.clone(), url);
+    for (name, value) in headers.iter() {
+        if !HOP_HEADERS.contains(&name.as_str()) {
+            request = request.header(name, value);
+        }
+    }
+    let response = request
+        .body(body.clone())
+        .send()
+        .await
+        .map_err(|err| (StatusCode::BAD_GATEWAY, err.to_string()))?;
+
+    let status = response.status().as_u16();
+    let headers = response
+        .headers()
+        .iter()
+        .filter(|(name, _)| !HOP_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let response_body = response
+        .bytes()
+        .await
+        .map_err(|err| (StatusCode::BAD_GATEWAY, err.to_string()))?;
+
+    let cassette = Cassette {
+        method: method.to_string(),
+        path: path.to_string(),
+        request_body: String::from_utf8_lossy(&body).into_owned(),
+        status,
+        headers,
+        body: String::from_utf8_lossy(&response_body).into_owned(),
+    };
+    let json = serde_json::to_vec_pretty(&cassette)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    tokio::fs::write(cassette_path, json)
+        .await
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))?;
+    Ok(cassette)
+}
+
+async fn replay(cassette_path: &Path) -> Result<Cassette, (StatusCode, String)> {
+    let json = tokio::fs::read(cassette_path).await.map_err(|_| {
+        (
+            StatusCode::NOT_FOUND,
+            format!("no recorded response at {}", cassette_path.display()),
+        )
+    })?;
+    serde_json::from_slice(&json)
+        .map_err(|err| (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()))
+}
+
+fn cassette_response(cassette: Cassette) -> Response {
+    let mut response = Response::new(Body::from(cassette.body));
+    *response.status_mut() = StatusCode::from_u16(cassette.status).unwrap_or(StatusCode::OK);
+    for (name, value) in cassette.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::try_from(name), HeaderValue::try_from(value)) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use axum::routing::post;
+
+    use super::*;
+
+    /// A stand-in provider that numbers its replies.
+    async fn start_stub_provider() -> (String, Arc<AtomicUsize>, JoinHandle<()>) {
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = Router::new().route(
+            "/v1/messages",
+            post(move |body: String| {
+                let counter = counter.clone();
+                async move {
+                    let n = counter.fetch_add(1, Ordering::SeqCst);
+                    ([("x-stub", "yes")], format!("reply {n} to {body}"))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        (url, hits, server)
+    }
+
+    async fn post_message(proxy: &RecordingProxy, body: &str) -> reqwest::Response {
+        reqwest::Client::new()
+            .post(format!("{}/v1/messages", proxy.base_url(&PROVIDERS[0])))
+            .body(body.to_string())
+            .send()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_record_writes_cassettes_and_replay_serves_them() {
+        let dir = std::env::temp_dir().join(format!("recording-{}", uuid::Uuid::new_v4()));
+        let (stub_url, hits, stub) = start_stub_provider().await;
+
+        let recorder = RecordingProxy::start(
+            RecordingMode::Record(dir.clone()),
+            HashMap::from([("anthropic".to_string(), stub_url)]),
+        )
+        .await
+        .unwrap();
+        let first = post_message(&recorder, "hello").await.text().await.unwrap();
+        let second = post_message(&recorder, "again").await.text().await.unwrap();
+        drop(recorder);
+
+        assert_eq!(first, "reply 0 to hello");
+        assert_eq!(second, "reply 1 to again");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        // Replay must not reach the provider, which is no longer running.
+        stub.abort();
+        let replayer = RecordingProxy::start(RecordingMode::Replay(dir.clone()), HashMap::new())
+            .await
+            .unwrap();
+        let response = post_message(&replayer, "hello").await;
+        assert_eq!(response.headers()["x-stub"], "yes");
+        assert_eq!(response.text().await.unwrap(), "reply 0 to hello");
+        assert_eq!(
+            post_message(&replayer, "again").await.text().await.unwrap(),
+            "reply 1 to again"
+        );
+        assert_eq!(
+            post_message(&replayer, "extra").await.status(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_record_and_replay_are_exclusive() {
+        let cmd = CmdOverrides {
+            record_dir: Some(PathBuf::from("/tmp/a")),
+            replay_dir: Some(PathBuf::from("/tmp/b")),
+            ..Default::default()
+        };
+        assert!(RecordingMode::from_overrides(&cmd).is_err());
+        assert_eq!(
+            RecordingMode::from_overrides(&CmdOverrides::default()).unwrap(),
+            None
+        );
+    }
+}
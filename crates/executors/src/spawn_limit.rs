@@ -0,0 +1,160 @@
+//! Global cap on concurrently running coding agents.
+//!
+//! Set `VIBE_MAX_CONCURRENT_AGENTS` to a positive number to make further spawns queue until a
+//! running agent is reaped. Unset (or invalid) means no limit. Spawns are started from request
+//! handlers, so the queue wait is bounded by [`SPAWN_QUEUE_TIMEOUT`]. The slot is taken by
+//! [`crate::actions::ExecutorAction::spawn_limited`] and travels on the spawned child.
+
+use std::{
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::executors::ExecutorError;
+
+pub const MAX_CONCURRENT_AGENTS_ENV: &str = "VIBE_MAX_CONCURRENT_AGENTS";
+/// How long a spawn waits for a running agent to free its slot before failing.
+pub const SPAWN_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Held for the lifetime of an agent process; dropping it lets the next queued spawn proceed.
+pub type SpawnPermit = OwnedSemaphorePermit;
+
+static GLOBAL: LazyLock<SpawnLimiter> = LazyLock::new(SpawnLimiter::from_env);
+
+pub struct SpawnLimiter {
+    semaphore: Arc<Semaphore>,
+}
+
+impl SpawnLimiter {
+    /// A limiter allowing `limit` concurrent agents, or any number when `None`.
+    pub fn new(limit: Option<usize>) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit.unwrap_or(Semaphore::MAX_PERMITS))),
+        }
+    }
+
+    pub fn from_env() -> Self {
+        let limit = std::env::var(MAX_CONCURRENT_AGENTS_ENV)
+            .ok()
+            .and_then(|raw| {
+                let parsed = raw.trim().parse::<usize>().ok().filter(|n| *n > 0);
+                if parsed.is_none() {
+                    tracing::warn!("Ignoring invalid {MAX_CONCURRENT_AGENTS_ENV}={raw}");
+                }
+                parsed
+            });
+        Self::new(limit)
+    }
+
+    /// Process-wide limiter configured from the environment on first use.
+    pub fn global() -> &'static Self {
+        &GLOBAL
+    }
+
+    /// Wait for a free slot.
+    pub async fn acquire(&self) -> SpawnPermit {
+        self.semaphore
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("spawn semaphore is never closed")
+    }
+
+    /// [`Self::acquire`], failing with [`ExecutorError::SpawnQueueTimeout`] when no slot frees
+    /// up within `timeout`.
+    pub async fn acquire_within(&self, timeout: Duration) -> Result<SpawnPermit, ExecutorError> {
+        tokio::time::timeout(timeout, self.acquire())
+            .await
+            .map_err(|_| ExecutorError::SpawnQueueTimeout(timeout))
+    }
+
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        actions::{
+            ExecutorAction, ExecutorActionType, coding_agent_initial::CodingAgentInitialRequest,
+        },
+        approvals::NoopExecutorApprovalService,
+        env::ExecutionEnv,
+        executors::BaseCodingAgent,
+        profile::ExecutorProfileId,
+    };
+
+    #[tokio::test]
+    async fn test_extra_spawn_waits_for_a_running_agent() {
+        let limiter = Arc::new(SpawnLimiter::new(Some(2)));
+        let first = limiter.acquire().await;
+        let _second = limiter.acquire().await;
+
+        let queued = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire().await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(!queued.is_finished());
+
+        drop(first);
+        let third = tokio::time::timeout(Duration::from_secs(5), queued)
+            .await
+            .expect("queued spawn should proceed once a permit is released")
+            .unwrap();
+        assert_eq!(limiter.available(), 0);
+        drop(third);
+        assert_eq!(limiter.available(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_permit_released_when_spawn_fails() {
+        let limiter = SpawnLimiter::new(Some(1));
+        let request: CodingAgentInitialRequest = serde_json::from_value(serde_json::json!({
+            "prompt": "Fix the bug",
+            "executor_profile_id": ExecutorProfileId::new(BaseCodingAgent::ClaudeCode),
+            // Fails the containment check once the permit is held, before anything is spawned
+            "working_dir": "../outside",
+        }))
+        .unwrap();
+        let action =
+            ExecutorAction::new(ExecutorActionType::CodingAgentInitialRequest(request), None);
+
+        let result = action
+            .spawn_limited(
+                &limiter,
+                &std::env::temp_dir(),
+                Arc::new(NoopExecutorApprovalService),
+                &ExecutionEnv::new(),
+                Some(Duration::from_secs(5)),
+            )
+            .await;
+
+        assert!(matches!(result, Err(ExecutorError::InvalidWorkingDir(_))));
+        assert_eq!(limiter.available(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_queue_wait_is_bounded() {
+        let limiter = SpawnLimiter::new(Some(1));
+        let running = limiter.acquire().await;
+
+        let err = limiter
+            .acquire_within(Duration::from_millis(50))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ExecutorError::SpawnQueueTimeout(_)));
+
+        drop(running);
+        let permit = limiter
+            .acquire_within(Duration::from_millis(50))
+            .await
+            .unwrap();
+        assert_eq!(limiter.available(), 0);
+        drop(permit);
+    }
+}
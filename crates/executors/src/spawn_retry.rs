@@ -0,0 +1,182 @@
+//! Retrying spawns that fail for transient reasons.
+//!
+//! Busy hosts occasionally refuse to start a process (`EAGAIN`, interrupted syscalls, exhausted
+//! resources). [`StandardCodingAgentExecutor::spawn_with_retry`] retries such failures, as
+//! classified by [`ExecutorError::is_transient`], with exponential backoff; anything else is
+//! returned immediately.
+//!
+//! [`StandardCodingAgentExecutor::spawn_with_retry`]: crate::executors::StandardCodingAgentExecutor::spawn_with_retry
+//! [`ExecutorError::is_transient`]: crate::executors::ExecutorError::is_transient
+
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total spawn attempts, including the first; `1` disables retrying
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that spawns once and never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (1-based).
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = retry.saturating_sub(1).min(16);
+        self.initial_backoff
+            .checked_mul(1 << exp)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{
+        path::Path,
+        sync::{
+            Arc,
+            atomic::{AtomicU32, Ordering},
+        },
+    };
+
+    use async_trait::async_trait;
+    use command_group::AsyncCommandGroup;
+    use workspace_utils::msg_store::MsgStore;
+
+    use super::*;
+    use crate::{
+        env::ExecutionEnv,
+        executors::{ExecutorError, SpawnedChild, StandardCodingAgentExecutor},
+    };
+
+    /// Fails with `error()` for the first `failures` spawns, then starts `true`.
+    struct StubExecutor {
+        failures: u32,
+        error: fn() -> ExecutorError,
+        attempts: AtomicU32,
+    }
+
+    impl StubExecutor {
+        fn new(failures: u32, error: fn() -> ExecutorError) -> Self {
+            Self {
+                failures,
+                error,
+                attempts: AtomicU32::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl StandardCodingAgentExecutor for StubExecutor {
+        async fn spawn(
+            &self,
+            _current_dir: &Path,
+            _prompt: &str,
+            _env: &ExecutionEnv,
+        ) -> Result<SpawnedChild, ExecutorError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err((self.error)());
+            }
+            Ok(tokio::process::Command::new("true")
+                .group_spawn()
+                .map_err(ExecutorError::SpawnError)?
+                .into())
+        }
+
+        async fn spawn_follow_up(
+            &self,
+            current_dir: &Path,
+            prompt: &str,
+            _session_id: &str,
+            env: &ExecutionEnv,
+        ) -> Result<SpawnedChild, ExecutorError> {
+            self.spawn(current_dir, prompt, env).await
+        }
+
+        fn normalize_logs(&self, _raw_logs_event_store: Arc<MsgStore>, _worktree_path: &Path) {}
+
+        fn default_mcp_config_path(&self) -> Option<std::path::PathBuf> {
+            None
+        }
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    fn would_block() -> ExecutorError {
+        ExecutorError::SpawnError(std::io::Error::from(std::io::ErrorKind::WouldBlock))
+    }
+
+    #[tokio::test]
+    async fn test_flaky_spawn_succeeds_on_third_attempt() {
+        let executor = StubExecutor::new(2, would_block);
+        let mut spawned = executor
+            .spawn_with_retry(Path::new("."), "hi", &ExecutionEnv::new(), fast_policy())
+            .await
+            .unwrap();
+
+        assert_eq!(executor.attempts.load(Ordering::SeqCst), 3);
+        let _ = spawned.child.wait().await;
+    }
+
+    #[tokio::test]
+    async fn test_non_transient_errors_are_not_retried() {
+        let executor = StubExecutor::new(u32::MAX, || ExecutorError::ExecutableNotFound {
+            program: "jbai-claude".to_string(),
+        });
+        let result = executor
+            .spawn_with_retry(Path::new("."), "hi", &ExecutionEnv::new(), fast_policy())
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(ExecutorError::ExecutableNotFound { .. })
+        ));
+        assert_eq!(executor.attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let executor = StubExecutor::new(u32::MAX, would_block);
+        let result = executor
+            .spawn_with_retry(Path::new("."), "hi", &ExecutionEnv::new(), fast_policy())
+            .await;
+
+        assert!(matches!(result, Err(err) if err.is_transient()));
+        assert_eq!(executor.attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.backoff(1), Duration::from_millis(250));
+        assert_eq!(policy.backoff(2), Duration::from_millis(500));
+        assert_eq!(policy.backoff(3), Duration::from_secs(1));
+        assert_eq!(policy.backoff(10), Duration::from_secs(5));
+        assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,110 @@
+//! Turn-limit enforcement for executors without a native max-turns flag.
+//!
+//! Mirrors the child's stdout, counts lines the executor identifies as turn markers, and requests
+//! a graceful stop through the exit signal once the limit is exceeded.
+
+use futures::{StreamExt, stream::BoxStream};
+use tokio::sync::oneshot;
+use workspace_utils::stream_lines::LinesStreamExt;
+
+use crate::{
+    executors::{ExecutorError, ExecutorExitResult, SpawnedChild},
+    stdout_dup::duplicate_stdout,
+};
+
+/// Watch the spawned child's stdout and stop it once more than `max_turns` turns are counted.
+///
+/// Any exit signal already provided by the executor keeps working; its result is forwarded
+/// unchanged if it resolves before the limit is hit.
+pub fn enforce_turn_limit(
+    spawned: &mut SpawnedChild,
+    max_turns: u32,
+    is_turn_marker: fn(&str) -> bool,
+) -> Result<(), ExecutorError> {
+    let stdout = duplicate_stdout(&mut spawned.child)?;
+    let upstream = spawned.exit_signal.take();
+    let (mut exit_tx, exit_rx) = oneshot::channel();
+    spawned.exit_signal = Some(exit_rx);
+
+    tokio::spawn(async move {
+        let upstream = async move {
+            match upstream {
+                Some(rx) => rx.await.ok(),
+                None => std::future::pending().await,
+            }
+        };
+
+        let outcome = tokio::select! {
+            result = upstream => result,
+            true = exceeds_turn_limit(stdout, max_turns, is_turn_marker) => {
+                tracing::info!("Agent exceeded max_turns={max_turns}, stopping");
+                Some(ExecutorExitResult::Failure)
+            }
+            // Container stopped listening (process exited on its own)
+            _ = exit_tx.closed() => None,
+        };
+
+        if let Some(result) = outcome {
+            let _ = exit_tx.send(result);
+        }
+    });
+
+    Ok(())
+}
+
+/// Resolve to `true` as soon as more than `max_turns` turn markers have been seen, or `false`
+/// when the stream ends first.
+async fn exceeds_turn_limit(
+    stdout: BoxStream<'static, std::io::Result<String>>,
+    max_turns: u32,
+    is_turn_marker: fn(&str) -> bool,
+) -> bool {
+    let mut lines = stdout.lines();
+    let mut turns: u32 = 0;
+    while let Some(Ok(line)) = lines.next().await {
+        if is_turn_marker(&line) {
+            turns = turns.saturating_add(1);
+            if turns > max_turns {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executors::codex::is_turn_marker;
+
+    fn stdout_of(lines: &[&str]) -> BoxStream<'static, std::io::Result<String>> {
+        let chunks: Vec<std::io::Result<String>> =
+            lines.iter().map(|line| Ok(format!("{line}\n"))).collect();
+        futures::stream::iter(chunks).boxed()
+    }
+
+    /// One prompt the model answers in three turns: two tool rounds and the final reply
+    const CODEX_MULTI_TURN: &[&str] = &[
+        r#"{"method":"codex/event/task_started","params":{"msg":{"type":"task_started","model_context_window":272000}}}"#,
+        r#"{"method":"codex/event/agent_reasoning","params":{"msg":{"type":"agent_reasoning","text":"Find the tests"}}}"#,
+        r#"{"method":"codex/event/exec_command_begin","params":{"msg":{"type":"exec_command_begin","call_id":"c1","command":["ls"]}}}"#,
+        r#"{"method":"codex/event/token_count","params":{"msg":{"type":"token_count","info":null}}}"#,
+        r#"{"method":"codex/event/exec_command_end","params":{"msg":{"type":"exec_command_end","call_id":"c1","exit_code":0}}}"#,
+        r#"{"method":"codex/event/exec_command_begin","params":{"msg":{"type":"exec_command_begin","call_id":"c2","command":["cargo","test"]}}}"#,
+        r#"{"method":"codex/event/token_count","params":{"msg":{"type":"token_count","info":null}}}"#,
+        r#"{"method":"codex/event/exec_command_end","params":{"msg":{"type":"exec_command_end","call_id":"c2","exit_code":0}}}"#,
+        r#"{"method":"codex/event/agent_message","params":{"msg":{"type":"agent_message","message":"All tests pass."}}}"#,
+        r#"{"method":"codex/event/token_count","params":{"msg":{"type":"token_count","info":null}}}"#,
+        r#"{"method":"codex/event/task_complete","params":{"msg":{"type":"task_complete"}}}"#,
+    ];
+
+    #[tokio::test]
+    async fn test_counted_turns_exceed_limit() {
+        assert!(exceeds_turn_limit(stdout_of(CODEX_MULTI_TURN), 2, is_turn_marker).await);
+    }
+
+    #[tokio::test]
+    async fn test_counted_turns_within_limit() {
+        assert!(!exceeds_turn_limit(stdout_of(CODEX_MULTI_TURN), 3, is_turn_marker).await);
+    }
+}
@@ -0,0 +1,109 @@
+//! Wall-clock cap on an agent's total runtime.
+//!
+//! Unlike the turn limit, output does not matter: once the profile's `max_wallclock_secs` have
+//! passed since spawn, a graceful stop is requested through the exit signal and the container
+//! kills the process group, however busy the agent still is. The exit result says the limit was
+//! the cause, so the process is failed with [`ExecutorError::WallclockExceeded`] in its log.
+
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use crate::executors::{ExecutorError, ExecutorExitResult, ExecutorExitSignal, SpawnedChild};
+
+/// Stop the spawned child once it has run for `limit`.
+///
+/// Any exit signal already provided by the executor keeps working; its result is forwarded
+/// unchanged if it resolves first.
+pub fn enforce_wallclock(spawned: &mut SpawnedChild, limit: Duration) {
+    let upstream = spawned.exit_signal.take();
+    let (mut exit_tx, exit_rx) = oneshot::channel();
+    spawned.exit_signal = Some(exit_rx);
+
+    tokio::spawn(async move {
+        let outcome = tokio::select! {
+            outcome = watch_wallclock(upstream, limit) => outcome,
+            // Container stopped listening (process exited on its own)
+            _ = exit_tx.closed() => return,
+        };
+        let result = match outcome {
+            Ok(Some(result)) => result,
+            Ok(None) => return,
+            Err(err) => {
+                tracing::info!("{err}, stopping");
+                ExecutorExitResult::WallclockExceeded {
+                    limit_secs: limit.as_secs(),
+                }
+            }
+        };
+        let _ = exit_tx.send(result);
+    });
+}
+
+/// The upstream exit result if it arrives within `limit`, otherwise
+/// [`ExecutorError::WallclockExceeded`]. Without an upstream signal only the limit can resolve.
+async fn watch_wallclock(
+    upstream: Option<ExecutorExitSignal>,
+    limit: Duration,
+) -> Result<Option<ExecutorExitResult>, ExecutorError> {
+    let upstream = async move {
+        match upstream {
+            Some(rx) => rx.await.ok(),
+            None => std::future::pending().await,
+        }
+    };
+    tokio::time::timeout(limit, upstream)
+        .await
+        .map_err(|_| ExecutorError::WallclockExceeded {
+            limit_secs: limit.as_secs(),
+        })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use command_group::AsyncCommandGroup;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_streaming_agent_stopped_past_wallclock() {
+        let mut child = tokio::process::Command::new("sh")
+            .args(["-c", "while true; do echo tick; sleep 0.02; done"])
+            .stdout(std::process::Stdio::piped())
+            .kill_on_drop(true)
+            .group_spawn()
+            .unwrap();
+        let stdout = child.inner().stdout.take().unwrap();
+        let mut spawned = SpawnedChild::from(child);
+
+        enforce_wallclock(&mut spawned, Duration::from_millis(300));
+
+        // The agent keeps producing output right up to the cap
+        let mut lines = BufReader::new(stdout).lines();
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("tick"));
+        let result = spawned.exit_signal.take().unwrap().await.unwrap();
+        assert!(matches!(
+            result,
+            ExecutorExitResult::WallclockExceeded { limit_secs: 0 }
+        ));
+        assert!(lines.next_line().await.unwrap().is_some());
+
+        let _ = spawned.child.kill().await;
+    }
+
+    #[tokio::test]
+    async fn test_agent_finishing_under_wallclock() {
+        let (tx, rx) = oneshot::channel();
+        tx.send(ExecutorExitResult::Success).unwrap();
+
+        let outcome = watch_wallclock(Some(rx), Duration::from_secs(5)).await;
+        assert!(matches!(outcome, Ok(Some(ExecutorExitResult::Success))));
+
+        let outcome = watch_wallclock(None, Duration::from_millis(50)).await;
+        assert!(matches!(
+            outcome,
+            Err(ExecutorError::WallclockExceeded { limit_secs: 0 })
+        ));
+    }
+}
@@ -17,6 +17,7 @@ use db::{
         execution_process::{
             ExecutionContext, ExecutionProcess, ExecutionProcessRunReason, ExecutionProcessStatus,
         },
+        execution_process_logs::ExecutionProcessLogs,
         execution_process_repo_state::ExecutionProcessRepoState,
         repo::Repo,
         scratch::{DraftFollowUpData, Scratch, ScratchType},
@@ -33,10 +34,23 @@ use executors::{
         coding_agent_initial::CodingAgentInitialRequest,
     },
     approvals::{ExecutorApprovalService, NoopExecutorApprovalService},
+    auto_commit::run_auto_commit,
+    checkpoint::spawn_checkpoint_writer,
+    dirty_worktree::OnDirty,
     env::ExecutionEnv,
-    executors::{BaseCodingAgent, ExecutorExitResult, ExecutorExitSignal, InterruptSender},
-    logs::{NormalizedEntryType, utils::patch::extract_normalized_entry_from_patch},
+    executors::{
+        BaseCodingAgent, ExecutorError, ExecutorExitResult, ExecutorExitSignal, InterruptSender,
+    },
+    logs::{
+        NormalizedEntry, NormalizedEntryError, NormalizedEntryType, RunStatus, RunSummary,
+        utils::{
+            ConversationPatch, EntryIndexProvider, binary_output::decode_output_stream,
+            patch::extract_normalized_entry_from_patch,
+        },
+    },
+    post_command::{POST_COMMAND_TIMEOUT, PostCommandResult, run_post_command},
     profile::ExecutorProfileId,
+    spawn_limit::SpawnPermit,
 };
 use futures::{FutureExt, TryStreamExt, stream::select};
 use serde_json::json;
@@ -56,8 +70,9 @@ use services::services::{
 use tokio::{sync::RwLock, task::JoinHandle};
 use tokio_util::io::ReaderStream;
 use utils::{
+    assets::asset_dir,
     log_msg::LogMsg,
-    msg_store::MsgStore,
+    msg_store::{MsgStore, SPILL_TAIL_BYTES},
     text::{git_branch_id, short_uuid, truncate_to_char_boundary},
 };
 use uuid::Uuid;
@@ -339,12 +354,262 @@ impl LocalContainerService {
         any_committed
     }
 
+    /// Run the initial request's `post_command`, if it has one, in its effective directory and
+    /// add the result to the execution's log.
+    async fn run_post_command(
+        &self,
+        exec_id: Uuid,
+        agent_exit_code: Option<i64>,
+    ) -> Option<PostCommandResult> {
+        let ctx = ExecutionProcess::load_context(&self.db.pool, exec_id)
+            .await
+            .ok()?;
+        let action = ctx.execution_process.executor_action().ok()?;
+        let ExecutorActionType::CodingAgentInitialRequest(request) = action.typ() else {
+            return None;
+        };
+        let command = request.post_command.as_ref()?;
+        // Same env the agent ran with, so the command finds the same tools and HOME
+        let env = match self.execution_env(&ctx.workspace, action).await {
+            Ok(env) => request.post_command_env(&env),
+            Err(e) => {
+                tracing::warn!("Skipping post-command for {}: {}", exec_id, e);
+                return None;
+            }
+        };
+        let effective_dir = match request
+            .resolve_effective_dir(&self.workspace_to_current_dir(&ctx.workspace), &env)
+        {
+            Ok(dir) => dir,
+            Err(e) => {
+                tracing::warn!("Skipping post-command for {}: {}", exec_id, e);
+                return None;
+            }
+        };
+
+        let result =
+            match run_post_command(command, &effective_dir, &env, POST_COMMAND_TIMEOUT).await {
+                Ok(result) => result,
+                Err(e) => {
+                    tracing::warn!("Skipping post-command for {}: {}", exec_id, e);
+                    return None;
+                }
+            };
+
+        let msg_store = self.msg_stores.read().await.get(&exec_id).cloned();
+        if let Some(msg_store) = msg_store {
+            let agent_exit_code = agent_exit_code.and_then(|code| i32::try_from(code).ok());
+            let patches = result.clone().into_patches(&msg_store, agent_exit_code);
+            self.push_persisted_patches(exec_id, &msg_store, patches)
+                .await;
+        }
+
+        Some(result)
+    }
+
+    /// Commit the changes in the initial request's effective directory if it asks for
+    /// `auto_commit`, adding a `GitOp` entry to the execution's log. Returns whether a commit
+    /// was made.
+    async fn run_auto_commit(&self, exec_id: Uuid, exit_code: Option<i64>) -> bool {
+        let Ok(ctx) = ExecutionProcess::load_context(&self.db.pool, exec_id).await else {
+            return false;
+        };
+        let Ok(action) = ctx.execution_process.executor_action() else {
+            return false;
+        };
+        let ExecutorActionType::CodingAgentInitialRequest(request) = action.typ() else {
+            return false;
+        };
+        let Some(auto_commit) = request.auto_commit.as_ref() else {
+            return false;
+        };
+        let exit_code = exit_code.and_then(|code| i32::try_from(code).ok());
+        let effective_dir =
+            match request.effective_dir(&self.workspace_to_current_dir(&ctx.workspace)) {
+                Ok(dir) => dir,
+                Err(e) => {
+                    tracing::warn!("Skipping auto-commit for {}: {}", exec_id, e);
+                    return false;
+                }
+            };
+
+        let message = auto_commit.render_message(
+            &request.executor_profile_id.to_string(),
+            &request.prompt,
+            exit_code,
+        );
+        let result = match run_auto_commit(auto_commit, &effective_dir, &message, exit_code).await {
+            Ok(Some(result)) => result,
+            Ok(None) => return false,
+            Err(e) => {
+                tracing::warn!("Auto-commit failed for {}: {}", exec_id, e);
+                return false;
+            }
+        };
+
+        let msg_store = self.msg_stores.read().await.get(&exec_id).cloned();
+        if let Some(msg_store) = msg_store {
+            let patch = result.into_patch(&msg_store);
+            self.push_persisted_patches(exec_id, &msg_store, vec![patch])
+                .await;
+        }
+        true
+    }
+
+    /// Add a `ModelNotEntitled` or `UnknownModel` entry if the client refused the run's model at
+    /// any point, not only while it was starting.
+    async fn push_model_error(&self, exec_id: Uuid, action: &ExecutorAction) {
+        let Some(msg_store) = self.msg_stores.read().await.get(&exec_id).cloned() else {
+            return;
+        };
+        let Some(event) = action
+            .model_error_detector()
+            .and_then(|detector| detector.scan(&msg_store))
+        else {
+            return;
+        };
+        let idx = EntryIndexProvider::start_from(&msg_store).next();
+        let patch = ConversationPatch::add_normalized_entry(idx, event.into_normalized_entry());
+        self.push_persisted_patches(exec_id, &msg_store, vec![patch])
+            .await;
+    }
+
+    /// Add the events read from the message the agent ended its turn on, e.g. setup the user has
+    /// to do, a question the agent is waiting on or the next steps it suggests.
+    async fn push_turn_end_events(&self, exec_id: Uuid, action: &ExecutorAction) {
+        let Some(msg_store) = self.msg_stores.read().await.get(&exec_id).cloned() else {
+            return;
+        };
+        let Some(detector) = action.turn_end_detector() else {
+            return;
+        };
+        let entry_index = EntryIndexProvider::start_from(&msg_store);
+        let patches = detector
+            .scan(&msg_store)
+            .into_iter()
+            .map(|event| {
+                ConversationPatch::add_normalized_entry(
+                    entry_index.next(),
+                    event.into_normalized_entry(),
+                )
+            })
+            .collect();
+        self.push_persisted_patches(exec_id, &msg_store, patches)
+            .await;
+    }
+
+    /// Start a failed initial request again on its profile's `fallback_model` when its log shows
+    /// the model was unavailable, noting the switch in the failed run's log. Returns whether a
+    /// retry was started.
+    async fn try_model_fallback(&self, ctx: &ExecutionContext) -> bool {
+        let Ok(action) = ctx.execution_process.executor_action() else {
+            return false;
+        };
+        let ExecutorActionType::CodingAgentInitialRequest(request) = action.typ() else {
+            return false;
+        };
+        let exec_id = ctx.execution_process.id;
+        let Some(msg_store) = self.msg_stores.read().await.get(&exec_id).cloned() else {
+            return false;
+        };
+        let Some((retry, event)) = request.fallback_after_exit(&msg_store) else {
+            return false;
+        };
+
+        let idx = EntryIndexProvider::start_from(&msg_store).next();
+        let patch = ConversationPatch::add_normalized_entry(idx, event.into_normalized_entry());
+        self.push_persisted_patches(exec_id, &msg_store, vec![patch])
+            .await;
+
+        let retry = ExecutorAction::new(
+            ExecutorActionType::CodingAgentInitialRequest(retry),
+            action.next_action.clone(),
+        );
+        match self
+            .start_execution(
+                &ctx.workspace,
+                &ctx.session,
+                &retry,
+                &ExecutionProcessRunReason::CodingAgent,
+            )
+            .await
+        {
+            Ok(_) => true,
+            Err(e) => {
+                tracing::error!("Failed to retry {} on the fallback model: {}", exec_id, e);
+                false
+            }
+        }
+    }
+
+    /// Add the `RunSummary` roll-up of the execution's log once it has ended with `status`.
+    async fn push_run_summary(&self, exec_id: Uuid, status: &ExecutionProcessStatus) {
+        let Some(msg_store) = self.msg_stores.read().await.get(&exec_id).cloned() else {
+            return;
+        };
+        let status = match status {
+            ExecutionProcessStatus::Completed => RunStatus::Succeeded,
+            ExecutionProcessStatus::Killed => RunStatus::Stopped,
+            ExecutionProcessStatus::Failed | ExecutionProcessStatus::Running => RunStatus::Failed,
+        };
+        let patch = RunSummary::from_msg_store(&msg_store).into_patch(&msg_store, status);
+        self.push_persisted_patches(exec_id, &msg_store, vec![patch])
+            .await;
+    }
+
+    /// Log why the process was stopped as a failure, e.g. a limit the executor enforced.
+    async fn push_exit_error(&self, exec_id: Uuid, err: ExecutorError) {
+        let Some(msg_store) = self.msg_stores.read().await.get(&exec_id).cloned() else {
+            return;
+        };
+        let entry = NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::ErrorMessage {
+                error_type: NormalizedEntryError::Other,
+            },
+            content: err.to_string(),
+            metadata: None,
+        };
+        let idx = EntryIndexProvider::start_from(&msg_store).next();
+        let patch = ConversationPatch::add_normalized_entry(idx, entry);
+        self.push_persisted_patches(exec_id, &msg_store, vec![patch])
+            .await;
+    }
+
+    /// Push entries added after the agent exited to its store and the DB; only raw output is
+    /// streamed to the DB, so these are persisted directly.
+    async fn push_persisted_patches(
+        &self,
+        exec_id: Uuid,
+        msg_store: &MsgStore,
+        patches: Vec<json_patch::Patch>,
+    ) {
+        // Persist what the store kept, i.e. with secrets masked
+        let stored: Vec<_> = patches
+            .into_iter()
+            .flat_map(|patch| msg_store.push_patch_filtered(patch))
+            .collect();
+        for patch in stored {
+            if let Ok(json_line) = serde_json::to_string(&LogMsg::JsonPatch(patch))
+                && let Err(e) = ExecutionProcessLogs::append_log_line(
+                    &self.db.pool,
+                    exec_id,
+                    &format!("{json_line}\n"),
+                )
+                .await
+            {
+                tracing::warn!("Failed to persist log entry for {}: {}", exec_id, e);
+            }
+        }
+    }
+
     /// Spawn a background task that polls the child process for completion and
     /// cleans up the execution entry when it exits.
     pub fn spawn_exit_monitor(
         &self,
         exec_id: &Uuid,
         exit_signal: Option<ExecutorExitSignal>,
+        spawn_permit: Option<SpawnPermit>,
     ) -> JoinHandle<()> {
         let exec_id = *exec_id;
         let child_store = self.child_store.clone();
@@ -382,6 +647,15 @@ impl LocalContainerService {
                     status_result = match exit_result {
                         Ok(ExecutorExitResult::Success) => Ok(success_exit_status()),
                         Ok(ExecutorExitResult::Failure) => Ok(failure_exit_status()),
+                        Ok(ExecutorExitResult::WallclockExceeded { limit_secs }) => {
+                            container
+                                .push_exit_error(
+                                    exec_id,
+                                    ExecutorError::WallclockExceeded { limit_secs },
+                                )
+                                .await;
+                            Ok(failure_exit_status())
+                        }
                         Err(_) => Ok(success_exit_status()), // Channel closed, assume success
                     };
                 }
@@ -390,6 +664,8 @@ impl LocalContainerService {
                     status_result = exit_status_result.unwrap_or_else(|e| Err(std::io::Error::other(e)));
                 }
             }
+            // The agent has been reaped; free its slot before any follow-on action starts
+            drop(spawn_permit);
 
             let (exit_code, status) = match status_result {
                 Ok(exit_status) => {
@@ -404,6 +680,23 @@ impl LocalContainerService {
                 Err(_) => (None, ExecutionProcessStatus::Failed),
             };
 
+            // A failing post-command fails the attempt with its own exit code
+            let (exit_code, status) = if status == ExecutionProcessStatus::Completed
+                && !ExecutionProcess::was_stopped(&db.pool, exec_id).await
+                && let Some(result) = container.run_post_command(exec_id, exit_code).await
+                && !result.success()
+            {
+                (
+                    Some(result.exit_code.map_or(-1, i64::from)),
+                    ExecutionProcessStatus::Failed,
+                )
+            } else {
+                (exit_code, status)
+            };
+
+            let auto_committed = !ExecutionProcess::was_stopped(&db.pool, exec_id).await
+                && container.run_auto_commit(exec_id, exit_code).await;
+
             if !ExecutionProcess::was_stopped(&db.pool, exec_id).await
                 && let Err(e) =
                     ExecutionProcess::update_completion(&db.pool, exec_id, status, exit_code).await
@@ -412,6 +705,30 @@ impl LocalContainerService {
             }
 
             if let Ok(ctx) = ExecutionProcess::load_context(&db.pool, exec_id).await {
+                if matches!(
+                    ctx.execution_process.run_reason,
+                    ExecutionProcessRunReason::CodingAgent
+                ) {
+                    if let Ok(action) = ctx.execution_process.executor_action() {
+                        container.push_model_error(exec_id, action).await;
+                        if matches!(
+                            ctx.execution_process.status,
+                            ExecutionProcessStatus::Completed
+                        ) {
+                            container.push_turn_end_events(exec_id, action).await;
+                        }
+                    }
+                    container
+                        .push_run_summary(exec_id, &ctx.execution_process.status)
+                        .await;
+                }
+
+                // A model refused after the early-exit window fails the run instead of the spawn
+                let retried_on_fallback =
+                    matches!(ctx.execution_process.status, ExecutionProcessStatus::Failed)
+                        && !ExecutionProcess::was_stopped(&db.pool, exec_id).await
+                        && container.try_model_fallback(&ctx).await;
+
                 // Update executor session summary if available
                 if let Err(e) = container.update_executor_session_summary(&exec_id).await {
                     tracing::warn!("Failed to update executor session summary: {}", e);
@@ -433,7 +750,7 @@ impl LocalContainerService {
                 if success || cleanup_done {
                     // Commit changes (if any) and get feedback about whether changes were made
                     let changes_committed = match container.try_commit_changes(&ctx).await {
-                        Ok(committed) => committed,
+                        Ok(committed) => committed || auto_committed,
                         Err(e) => {
                             tracing::error!("Failed to commit changes after execution: {}", e);
                             // Treat commit failures as if changes were made to be safe
@@ -466,7 +783,7 @@ impl LocalContainerService {
                     }
                 }
 
-                if container.should_finalize(&ctx) {
+                if !retried_on_fallback && container.should_finalize(&ctx) {
                     // Only execute queued messages if the execution succeeded
                     // If it failed or was killed, just clear the queue and finalize
                     let should_execute_queued = !matches!(
@@ -604,19 +921,32 @@ impl LocalContainerService {
         format!("{}-{}", short_uuid(workspace_id), task_title_id)
     }
 
-    async fn track_child_msgs_in_store(&self, id: Uuid, child: &mut AsyncGroupChild) {
-        let store = Arc::new(MsgStore::new());
+    async fn track_child_msgs_in_store(
+        &self,
+        id: Uuid,
+        child: &mut AsyncGroupChild,
+        spill_dir: Option<PathBuf>,
+    ) {
+        let store = match spill_dir {
+            Some(dir) => {
+                let path = dir.join(format!("{id}.jsonl"));
+                MsgStore::with_spill(&path, SPILL_TAIL_BYTES).unwrap_or_else(|e| {
+                    tracing::warn!("Failed to spill logs to {}: {e}", path.display());
+                    MsgStore::new()
+                })
+            }
+            None => MsgStore::new(),
+        };
+        let store = Arc::new(store);
 
         let out = child.inner().stdout.take().expect("no stdout");
         let err = child.inner().stderr.take().expect("no stderr");
 
-        // Map stdout bytes -> LogMsg::Stdout
-        let out = ReaderStream::new(out)
-            .map_ok(|chunk| LogMsg::Stdout(String::from_utf8_lossy(&chunk).into_owned()));
+        // Map stdout bytes -> LogMsg::Stdout, with binary runs replaced by marker lines
+        let out = decode_output_stream(ReaderStream::new(out)).map_ok(LogMsg::Stdout);
 
         // Map stderr bytes -> LogMsg::Stderr
-        let err = ReaderStream::new(err)
-            .map_ok(|chunk| LogMsg::Stderr(String::from_utf8_lossy(&chunk).into_owned()));
+        let err = decode_output_stream(ReaderStream::new(err)).map_ok(LogMsg::Stderr);
 
         // If you have a JSON Patch source, map it to LogMsg::JsonPatch too, then select all three.
 
@@ -820,12 +1150,9 @@ impl LocalContainerService {
             variant: queued_data.variant.clone(),
         };
 
-        // Get latest agent session ID for session continuity (from coding agent turns)
-        let latest_agent_session_id = ExecutionProcess::find_latest_coding_agent_turn_session_id(
-            &self.db.pool,
-            ctx.session.id,
-        )
-        .await?;
+        // Agent session to resume, if any, plus the follow-up count and previous prompt
+        let continuation =
+            ExecutionProcess::session_continuation(&self.db.pool, ctx.session.id).await?;
 
         let repos =
             WorkspaceRepo::find_repos_for_workspace(&self.db.pool, ctx.workspace.id).await?;
@@ -838,12 +1165,14 @@ impl LocalContainerService {
             .filter(|dir| !dir.is_empty())
             .cloned();
 
-        let action_type = if let Some(agent_session_id) = latest_agent_session_id {
+        let action_type = if let Some(agent_session_id) = continuation.agent_session_id {
             ExecutorActionType::CodingAgentFollowUpRequest(CodingAgentFollowUpRequest {
                 prompt: queued_data.message.clone(),
                 session_id: agent_session_id,
                 executor_profile_id: executor_profile_id.clone(),
                 working_dir: working_dir.clone(),
+                session_turns: continuation.follow_up_turns,
+                previous_prompt: continuation.previous_prompt,
             })
         } else {
             ExecutorActionType::CodingAgentInitialRequest(CodingAgentInitialRequest {
@@ -851,6 +1180,15 @@ impl LocalContainerService {
                 executor_profile_id: executor_profile_id.clone(),
                 model_override: None,
                 working_dir,
+                max_turns: None,
+                context_manifest: None,
+                plan_only: false,
+                temperature: None,
+                post_command: None,
+                tags: None,
+                disable_mcp_servers: None,
+                auto_commit: None,
+                on_dirty_worktree: OnDirty::Allow,
             })
         };
 
@@ -1046,12 +1384,49 @@ impl ContainerService for LocalContainerService {
         Ok(true)
     }
 
+    async fn execution_env(
+        &self,
+        workspace: &Workspace,
+        executor_action: &ExecutorAction,
+    ) -> Result<ExecutionEnv, ContainerError> {
+        let mut env = ExecutionEnv::new();
+
+        // Load task and project context for environment variables
+        let task = workspace
+            .parent_task(&self.db.pool)
+            .await?
+            .ok_or(ContainerError::Other(anyhow!(
+                "Task not found for workspace"
+            )))?;
+        let project = task
+            .parent_project(&self.db.pool)
+            .await?
+            .ok_or(ContainerError::Other(anyhow!("Project not found for task")))?;
+
+        env.insert("VK_PROJECT_NAME", &project.name);
+        env.insert("VK_PROJECT_ID", project.id.to_string());
+        env.insert("VK_TASK_ID", task.id.to_string());
+        env.insert("VK_WORKSPACE_ID", workspace.id.to_string());
+        env.insert("VK_WORKSPACE_BRANCH", &workspace.branch);
+        if matches!(executor_action.base_executor(), Some(BaseCodingAgent::Jbai)) {
+            let token = self.config.read().await.jbai_token.clone();
+            if let Some(value) = token {
+                let trimmed = value.trim();
+                if !trimmed.is_empty() {
+                    env.insert("JBAI_TOKEN", trimmed);
+                }
+            }
+        }
+
+        Ok(env)
+    }
+
     async fn start_execution_inner(
         &self,
         workspace: &Workspace,
         execution_process: &ExecutionProcess,
         executor_action: &ExecutorAction,
-    ) -> Result<(), ContainerError> {
+    ) -> Result<ExecutionEnv, ContainerError> {
         // Get the worktree path
         let container_ref = workspace
             .container_ref
@@ -1079,50 +1454,58 @@ impl ContainerService for LocalContainerService {
                 _ => Arc::new(NoopExecutorApprovalService {}),
             };
 
-        // Build ExecutionEnv with VK_* variables
-        let mut env = ExecutionEnv::new();
-
-        // Load task and project context for environment variables
-        let task = workspace
-            .parent_task(&self.db.pool)
-            .await?
-            .ok_or(ContainerError::Other(anyhow!(
-                "Task not found for workspace"
-            )))?;
-        let project = task
-            .parent_project(&self.db.pool)
-            .await?
-            .ok_or(ContainerError::Other(anyhow!("Project not found for task")))?;
+        let env = self.execution_env(workspace, executor_action).await?;
+
+        // Create the child and stream, add to execution tracker with timeout. Coding agents
+        // first queue for a slot when VIBE_MAX_CONCURRENT_AGENTS is reached; failing that wait
+        // marks the process failed like any other spawn error
+        let mut spawned = executor_action
+            .spawn_with_timeout(
+                &current_dir,
+                approvals_service,
+                &env,
+                Duration::from_secs(30),
+            )
+            .await?;
 
-        env.insert("VK_PROJECT_NAME", &project.name);
-        env.insert("VK_PROJECT_ID", project.id.to_string());
-        env.insert("VK_TASK_ID", task.id.to_string());
-        env.insert("VK_WORKSPACE_ID", workspace.id.to_string());
-        env.insert("VK_WORKSPACE_BRANCH", &workspace.branch);
-        if matches!(executor_action.base_executor(), Some(BaseCodingAgent::Jbai)) {
-            let token = self.config.read().await.jbai_token.clone();
-            if let Some(value) = token {
-                let trimmed = value.trim();
-                if !trimmed.is_empty() {
-                    env.insert("JBAI_TOKEN", trimmed);
-                }
-            }
+        if let ExecutorActionType::CodingAgentInitialRequest(request) = executor_action.typ()
+            && self.config.read().await.analytics_enabled
+            && let Some(analytics) = &self.analytics
+        {
+            analytics.analytics_service.track_event(
+                &analytics.user_id,
+                "coding_agent_spawned",
+                serde_json::to_value(request.spawn_event()).ok(),
+            );
         }
 
-        // Create the child and stream, add to execution tracker with timeout
-        let mut spawned = tokio::time::timeout(
-            Duration::from_secs(30),
-            executor_action.spawn(&current_dir, approvals_service, &env),
+        self.track_child_msgs_in_store(
+            execution_process.id,
+            &mut spawned.child,
+            executor_action.spill_to_disk(),
         )
-        .await
-        .map_err(|_| {
-            ContainerError::Other(anyhow!(
-                "Timeout: process took more than 30 seconds to start"
-            ))
-        })??;
+        .await;
 
-        self.track_child_msgs_in_store(execution_process.id, &mut spawned.child)
-            .await;
+        if !spawned.events.is_empty()
+            && let Some(msg_store) = self.get_msg_store_by_id(&execution_process.id).await
+        {
+            let entry_index = EntryIndexProvider::start_from(&msg_store);
+            for event in spawned.events.drain(..) {
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    entry_index.next(),
+                    event.into_normalized_entry(),
+                ));
+            }
+        }
+
+        if let Some(interval) = executor_action.checkpoint_interval()
+            && let Some(msg_store) = self.get_msg_store_by_id(&execution_process.id).await
+        {
+            let path = asset_dir()
+                .join("checkpoints")
+                .join(format!("{}.json", execution_process.id));
+            spawn_checkpoint_writer(msg_store, path, interval);
+        }
 
         self.add_child_to_store(execution_process.id, spawned.child)
             .await;
@@ -1134,9 +1517,13 @@ impl ContainerService for LocalContainerService {
         }
 
         // Spawn unified exit monitor: watches OS exit and optional executor signal
-        let _hn = self.spawn_exit_monitor(&execution_process.id, spawned.exit_signal);
+        let _hn = self.spawn_exit_monitor(
+            &execution_process.id,
+            spawned.exit_signal,
+            spawned.spawn_permit,
+        );
 
-        Ok(())
+        Ok(env)
     }
 
     async fn stop_execution(
@@ -179,6 +179,10 @@ fn generate_types_content() -> String {
         executors::executors::BaseCodingAgent::decl(),
         executors::executors::CodingAgent::decl(),
         executors::executors::AvailabilityInfo::decl(),
+        executors::preflight::PreflightReport::decl(),
+        executors::preflight::PreflightCheck::decl(),
+        executors::preflight::PreflightCheckKind::decl(),
+        executors::preflight::PreflightStatus::decl(),
         executors::command::CommandBuilder::decl(),
         executors::profile::ExecutorProfileId::decl(),
         executors::profile::ExecutorConfig::decl(),
@@ -202,8 +206,12 @@ fn generate_types_content() -> String {
         executors::executors::droid::ReasoningEffortLevel::decl(),
         executors::executors::jbai::Jbai::decl(),
         executors::executors::jbai::JbaiClient::decl(),
+        executors::executors::jbai::JbaiClientInfo::decl(),
         executors::executors::AppendPrompt::decl(),
         executors::actions::coding_agent_initial::CodingAgentInitialRequest::decl(),
+        executors::context_manifest::FileRange::decl(),
+        executors::auto_commit::AutoCommit::decl(),
+        executors::dirty_worktree::OnDirty::decl(),
         executors::actions::coding_agent_follow_up::CodingAgentFollowUpRequest::decl(),
         executors::actions::review::ReviewRequest::decl(),
         executors::actions::review::RepoReviewContext::decl(),
@@ -211,6 +219,14 @@ fn generate_types_content() -> String {
         executors::logs::CommandRunResult::decl(),
         executors::logs::NormalizedEntry::decl(),
         executors::logs::NormalizedEntryType::decl(),
+        executors::logs::AgentEvent::decl(),
+        executors::logs::AgentPhase::decl(),
+        executors::logs::TokenUsage::decl(),
+        executors::logs::RunStatus::decl(),
+        executors::logs::delta_timing::LatencyBucket::decl(),
+        executors::logs::LatencyHistogram::decl(),
+        executors::logs::SessionMetadata::decl(),
+        executors::logs::DiffHunk::decl(),
         executors::logs::FileChange::decl(),
         executors::logs::ActionType::decl(),
         executors::logs::TodoItem::decl(),
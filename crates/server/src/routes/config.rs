@@ -15,6 +15,7 @@ use deployment::{Deployment, DeploymentError};
 use executors::{
     executors::{
         AvailabilityInfo, BaseAgentCapability, BaseCodingAgent, StandardCodingAgentExecutor,
+        jbai::{JbaiClientInfo, all_clients},
     },
     mcp_config::{McpConfig, read_agent_config, write_agent_config},
     profile::{ExecutorConfigs, ExecutorProfileId},
@@ -26,13 +27,10 @@ use services::services::config::{
     editor::{EditorConfig, EditorType},
     save_config_to_file,
 };
-use tokio::fs;
-use tokio::process::Command;
+use tokio::{fs, process::Command};
 use ts_rs::TS;
 use utils::{
-    api::oauth::LoginStatus,
-    assets::config_path,
-    response::ApiResponse,
+    api::oauth::LoginStatus, assets::config_path, response::ApiResponse,
     shell::resolve_executable_path,
 };
 
@@ -43,6 +41,7 @@ pub fn router() -> Router<DeploymentImpl> {
         .route("/info", get(get_user_system_info))
         .route("/config", put(update_config))
         .route("/jbai/models", get(get_jbai_models))
+        .route("/jbai/clients", get(get_jbai_clients))
         .route("/sounds/{sound}", get(get_sound))
         .route("/mcp-config", get(get_mcp_servers).post(update_mcp_servers))
         .route("/profiles", get(get_profiles).put(update_profiles))
@@ -485,6 +484,12 @@ async fn get_jbai_models(
     }
 }
 
+async fn get_jbai_clients(
+    State(_deployment): State<DeploymentImpl>,
+) -> ResponseJson<ApiResponse<Vec<JbaiClientInfo>>> {
+    ResponseJson(ApiResponse::success(all_clients()))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProfilesContent {
     pub content: String,
@@ -22,8 +22,8 @@ use executors::{
     actions::{
         ExecutorAction, ExecutorActionType, coding_agent_follow_up::CodingAgentFollowUpRequest,
     },
-    executors::BaseCodingAgent,
-    profile::ExecutorProfileId,
+    executors::{AgentOperation, BaseCodingAgent},
+    profile::{ExecutorConfigs, ExecutorProfileId},
 };
 use serde::Deserialize;
 use services::services::container::ContainerService;
@@ -146,6 +146,11 @@ pub async fn follow_up(
 
     // If retry settings provided, perform replace-logic before proceeding
     if let Some(proc_id) = payload.retry_process_id {
+        // Refuse before anything is reset when the agent can't resume from an earlier turn
+        if let Some(agent) = ExecutorConfigs::get_cached().get_coding_agent(&executor_profile_id) {
+            agent.check_operation(AgentOperation::Fork)?;
+        }
+
         // Validate process belongs to this session
         let process =
             ExecutionProcess::find_by_id(pool, proc_id)
@@ -179,8 +184,7 @@ pub async fn follow_up(
         let _ = ExecutionProcess::drop_at_and_after(pool, process.session_id, proc_id).await?;
     }
 
-    let latest_agent_session_id =
-        ExecutionProcess::find_latest_coding_agent_turn_session_id(pool, session.id).await?;
+    let continuation = ExecutionProcess::session_continuation(pool, session.id).await?;
 
     let prompt = payload.prompt;
 
@@ -193,12 +197,14 @@ pub async fn follow_up(
         .filter(|dir| !dir.is_empty())
         .cloned();
 
-    let action_type = if let Some(agent_session_id) = latest_agent_session_id {
+    let action_type = if let Some(agent_session_id) = continuation.agent_session_id {
         ExecutorActionType::CodingAgentFollowUpRequest(CodingAgentFollowUpRequest {
             prompt: prompt.clone(),
             session_id: agent_session_id,
             executor_profile_id: executor_profile_id.clone(),
             working_dir: working_dir.clone(),
+            session_turns: continuation.follow_up_turns,
+            previous_prompt: continuation.previous_prompt,
         })
     } else {
         ExecutorActionType::CodingAgentInitialRequest(
@@ -207,6 +213,15 @@ pub async fn follow_up(
                 executor_profile_id: executor_profile_id.clone(),
                 model_override: None,
                 working_dir,
+                max_turns: None,
+                context_manifest: None,
+                plan_only: false,
+                temperature: None,
+                post_command: None,
+                tags: None,
+                disable_mcp_servers: None,
+                auto_commit: None,
+                on_dirty_worktree: executors::dirty_worktree::OnDirty::Allow,
             },
         )
     };
@@ -15,9 +15,12 @@ use db::models::{
     workspace_repo::WorkspaceRepo,
 };
 use deployment::Deployment;
-use executors::actions::{
-    ExecutorAction, ExecutorActionType, coding_agent_follow_up::CodingAgentFollowUpRequest,
-    coding_agent_initial::CodingAgentInitialRequest,
+use executors::{
+    actions::{
+        ExecutorAction, ExecutorActionType, coding_agent_follow_up::CodingAgentFollowUpRequest,
+        coding_agent_initial::CodingAgentInitialRequest,
+    },
+    dirty_worktree::OnDirty,
 };
 use serde::{Deserialize, Serialize};
 use services::services::{
@@ -148,12 +151,9 @@ async fn trigger_pr_description_follow_up(
         return Ok(());
     };
 
-    // Get latest agent session ID if one exists (for coding agent continuity)
-    let latest_agent_session_id = ExecutionProcess::find_latest_coding_agent_turn_session_id(
-        &deployment.db().pool,
-        session.id,
-    )
-    .await?;
+    // Agent session to resume, if any, plus the follow-up count and previous prompt
+    let continuation =
+        ExecutionProcess::session_continuation(&deployment.db().pool, session.id).await?;
 
     let working_dir = workspace
         .agent_working_dir
@@ -162,12 +162,14 @@ async fn trigger_pr_description_follow_up(
         .cloned();
 
     // Build the action type (follow-up if session exists, otherwise initial)
-    let action_type = if let Some(agent_session_id) = latest_agent_session_id {
+    let action_type = if let Some(agent_session_id) = continuation.agent_session_id {
         ExecutorActionType::CodingAgentFollowUpRequest(CodingAgentFollowUpRequest {
             prompt,
             session_id: agent_session_id,
             executor_profile_id: executor_profile_id.clone(),
             working_dir: working_dir.clone(),
+            session_turns: continuation.follow_up_turns,
+            previous_prompt: continuation.previous_prompt,
         })
     } else {
         ExecutorActionType::CodingAgentInitialRequest(CodingAgentInitialRequest {
@@ -175,6 +177,15 @@ async fn trigger_pr_description_follow_up(
             executor_profile_id: executor_profile_id.clone(),
             model_override: None,
             working_dir,
+            max_turns: None,
+            context_manifest: None,
+            plan_only: false,
+            temperature: None,
+            post_command: None,
+            tags: None,
+            disable_mcp_servers: None,
+            auto_commit: None,
+            on_dirty_worktree: OnDirty::Allow,
         })
     };
 
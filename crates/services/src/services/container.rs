@@ -35,8 +35,16 @@ use executors::{
         coding_agent_initial::CodingAgentInitialRequest,
         script::{ScriptContext, ScriptRequest, ScriptRequestLanguage},
     },
+    dirty_worktree::OnDirty,
+    env::ExecutionEnv,
     executors::{ExecutorError, StandardCodingAgentExecutor},
-    logs::{NormalizedEntry, NormalizedEntryError, NormalizedEntryType, utils::ConversationPatch},
+    logs::{
+        AgentEvent, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
+        utils::{
+            ConversationPatch, EntryIndexProvider, detect_binary_output, detect_entry_events,
+            mask_secrets,
+        },
+    },
     profile::ExecutorProfileId,
 };
 use futures::{StreamExt, future};
@@ -526,12 +534,21 @@ pub trait ContainerService {
 
     async fn is_container_clean(&self, workspace: &Workspace) -> Result<bool, ContainerError>;
 
+    /// Env the agent of `executor_action` is spawned with in `workspace`, before its profile's
+    /// own env is applied.
+    async fn execution_env(
+        &self,
+        workspace: &Workspace,
+        executor_action: &ExecutorAction,
+    ) -> Result<ExecutionEnv, ContainerError>;
+
+    /// Spawn `executor_action` for `execution_process`, returning the env it was spawned with.
     async fn start_execution_inner(
         &self,
         workspace: &Workspace,
         execution_process: &ExecutionProcess,
         executor_action: &ExecutorAction,
-    ) -> Result<(), ContainerError>;
+    ) -> Result<ExecutionEnv, ContainerError>;
 
     async fn stop_execution(
         &self,
@@ -672,6 +689,9 @@ pub trait ContainerService {
                 }
             }
             temp_store.push_finished();
+            // Patches from the DB were masked when first stored; mask what is re-normalized
+            mask_secrets(&temp_store);
+            detect_binary_output(temp_store.clone());
 
             let process = match ExecutionProcess::find_by_id(&self.db().pool, *id).await {
                 Ok(Some(process)) => process,
@@ -726,56 +746,65 @@ pub trait ContainerService {
                 return None;
             };
 
+            // Same env the process was spawned with, which picks the client a jbai profile ran
+            #[cfg_attr(feature = "qa-mode", allow(unused_variables))]
+            let env = self
+                .execution_env(&workspace, &executor_action)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to rebuild execution env for {}: {}", process.id, e);
+                    ExecutionEnv::new()
+                });
+
             // Spawn normalizer on populated store
             match executor_action.typ() {
                 ExecutorActionType::CodingAgentInitialRequest(request) => {
+                    // A working_dir that failed the containment check never spawned
+                    let effective_dir = request
+                        .effective_dir(&current_dir)
+                        .unwrap_or_else(|_| current_dir.clone());
+                    detect_entry_events(&temp_store, &effective_dir);
                     #[cfg(feature = "qa-mode")]
                     {
                         let executor = QaMockExecutor;
-                        executor.normalize_logs(
-                            temp_store.clone(),
-                            &request.effective_dir(&current_dir),
-                        );
+                        executor.normalize_logs(temp_store.clone(), &effective_dir);
                     }
                     #[cfg(not(feature = "qa-mode"))]
                     {
                         let executor = ExecutorConfigs::get_cached()
                             .get_coding_agent_or_default(&request.executor_profile_id);
-                        executor.normalize_logs(
-                            temp_store.clone(),
-                            &request.effective_dir(&current_dir),
-                        );
+                        executor.normalize_logs_in(temp_store.clone(), &effective_dir, &env);
                     }
                 }
                 ExecutorActionType::CodingAgentFollowUpRequest(request) => {
+                    let effective_dir = request
+                        .effective_dir(&current_dir)
+                        .unwrap_or_else(|_| current_dir.clone());
+                    detect_entry_events(&temp_store, &effective_dir);
                     #[cfg(feature = "qa-mode")]
                     {
                         let executor = QaMockExecutor;
-                        executor.normalize_logs(
-                            temp_store.clone(),
-                            &request.effective_dir(&current_dir),
-                        );
+                        executor.normalize_logs(temp_store.clone(), &effective_dir);
                     }
                     #[cfg(not(feature = "qa-mode"))]
                     {
                         let executor = ExecutorConfigs::get_cached()
                             .get_coding_agent_or_default(&request.executor_profile_id);
-                        executor.normalize_logs(
-                            temp_store.clone(),
-                            &request.effective_dir(&current_dir),
-                        );
+                        executor.normalize_logs_in(temp_store.clone(), &effective_dir, &env);
                     }
                 }
                 #[cfg(feature = "qa-mode")]
                 ExecutorActionType::ReviewRequest(_request) => {
+                    detect_entry_events(&temp_store, &current_dir);
                     let executor = QaMockExecutor;
                     executor.normalize_logs(temp_store.clone(), &current_dir);
                 }
                 #[cfg(not(feature = "qa-mode"))]
                 ExecutorActionType::ReviewRequest(request) => {
+                    detect_entry_events(&temp_store, &current_dir);
                     let executor = ExecutorConfigs::get_cached()
                         .get_coding_agent_or_default(&request.executor_profile_id);
-                    executor.normalize_logs(temp_store.clone(), &current_dir);
+                    executor.normalize_logs_in(temp_store.clone(), &current_dir, &env);
                 }
                 _ => {
                     tracing::debug!(
@@ -797,8 +826,15 @@ pub trait ContainerService {
         }
     }
 
-    fn spawn_stream_raw_logs_to_db(&self, execution_id: &Uuid) -> JoinHandle<()> {
+    fn spawn_stream_raw_logs_to_db(
+        &self,
+        execution_id: &Uuid,
+        executor_action: &ExecutorAction,
+        env: &ExecutionEnv,
+    ) -> JoinHandle<()> {
         let execution_id = *execution_id;
+        let executor_action = executor_action.clone();
+        let env = env.clone();
         let msg_stores = self.msg_stores().clone();
         let db = self.db().clone();
 
@@ -845,6 +881,8 @@ pub trait ContainerService {
                             }
                         }
                         LogMsg::SessionId(agent_session_id) => {
+                            let agent_session_id =
+                                &executor_action.storable_session_id(agent_session_id, &env);
                             // Append this line to the database
                             if let Err(e) = CodingAgentTurn::update_agent_session_id(
                                 &db.pool,
@@ -923,6 +961,15 @@ pub trait ContainerService {
                 executor_profile_id: executor_profile_id.clone(),
                 model_override,
                 working_dir,
+                max_turns: None,
+                context_manifest: None,
+                plan_only: false,
+                temperature: None,
+                post_command: None,
+                tags: None,
+                disable_mcp_servers: None,
+                auto_commit: None,
+                on_dirty_worktree: OnDirty::Allow,
             }),
             cleanup_action.map(Box::new),
         );
@@ -1019,6 +1066,22 @@ pub trait ContainerService {
                 merge_commit: None,
             });
         }
+        // Compare against the previous turn's model before this process becomes the latest
+        let model_switch = match executor_action.typ() {
+            ExecutorActionType::CodingAgentFollowUpRequest(_) => {
+                ExecutionProcess::find_latest_by_session_and_run_reason(
+                    &self.db().pool,
+                    session.id,
+                    &ExecutionProcessRunReason::CodingAgent,
+                )
+                .await?
+                .and_then(|previous| {
+                    model_switch(previous.executor_action().ok()?, executor_action)
+                })
+            }
+            _ => None,
+        };
+
         let create_execution_process = CreateExecutionProcess {
             session_id: session.id,
             executor_action: executor_action.clone(),
@@ -1062,53 +1125,33 @@ pub trait ContainerService {
             .await?;
         }
 
-        if let Err(start_error) = self
+        let env = match self
             .start_execution_inner(workspace, &execution_process, executor_action)
             .await
         {
-            // Mark process as failed
-            if let Err(update_error) = ExecutionProcess::update_completion(
-                &self.db().pool,
-                execution_process.id,
-                ExecutionProcessStatus::Failed,
-                None,
-            )
-            .await
-            {
-                tracing::error!(
-                    "Failed to mark execution process {} as failed after start error: {}",
-                    execution_process.id,
-                    update_error
-                );
-            }
-            Task::update_status(&self.db().pool, task.id, TaskStatus::InReview).await?;
-
-            // Emit stderr error message
-            let log_message = LogMsg::Stderr(format!("Failed to start execution: {start_error}"));
-            if let Ok(json_line) = serde_json::to_string(&log_message) {
-                let _ = ExecutionProcessLogs::append_log_line(
+            Ok(env) => env,
+            Err(start_error) => {
+                // Mark process as failed
+                if let Err(update_error) = ExecutionProcess::update_completion(
                     &self.db().pool,
                     execution_process.id,
-                    &format!("{json_line}\n"),
+                    ExecutionProcessStatus::Failed,
+                    None,
                 )
-                .await;
-            }
+                .await
+                {
+                    tracing::error!(
+                        "Failed to mark execution process {} as failed after start error: {}",
+                        execution_process.id,
+                        update_error
+                    );
+                }
+                Task::update_status(&self.db().pool, task.id, TaskStatus::InReview).await?;
 
-            // Emit NextAction with failure context for coding agent requests
-            if let ContainerError::ExecutorError(ExecutorError::ExecutableNotFound { program }) =
-                &start_error
-            {
-                let help_text = format!("The required executable `{program}` is not installed.");
-                let error_message = NormalizedEntry {
-                    timestamp: None,
-                    entry_type: NormalizedEntryType::ErrorMessage {
-                        error_type: NormalizedEntryError::SetupRequired,
-                    },
-                    content: help_text,
-                    metadata: None,
-                };
-                let patch = ConversationPatch::add_normalized_entry(2, error_message);
-                if let Ok(json_line) = serde_json::to_string::<LogMsg>(&LogMsg::JsonPatch(patch)) {
+                // Emit stderr error message
+                let log_message =
+                    LogMsg::Stderr(format!("Failed to start execution: {start_error}"));
+                if let Ok(json_line) = serde_json::to_string(&log_message) {
                     let _ = ExecutionProcessLogs::append_log_line(
                         &self.db().pool,
                         execution_process.id,
@@ -1116,9 +1159,37 @@ pub trait ContainerService {
                     )
                     .await;
                 }
-            };
-            return Err(start_error);
-        }
+
+                // Emit NextAction with failure context for coding agent requests
+                if let ContainerError::ExecutorError(ExecutorError::ExecutableNotFound {
+                    program,
+                }) = &start_error
+                {
+                    let help_text =
+                        format!("The required executable `{program}` is not installed.");
+                    let error_message = NormalizedEntry {
+                        timestamp: None,
+                        entry_type: NormalizedEntryType::ErrorMessage {
+                            error_type: NormalizedEntryError::SetupRequired,
+                        },
+                        content: help_text,
+                        metadata: None,
+                    };
+                    let patch = ConversationPatch::add_normalized_entry(2, error_message);
+                    if let Ok(json_line) =
+                        serde_json::to_string::<LogMsg>(&LogMsg::JsonPatch(patch))
+                    {
+                        let _ = ExecutionProcessLogs::append_log_line(
+                            &self.db().pool,
+                            execution_process.id,
+                            &format!("{json_line}\n"),
+                        )
+                        .await;
+                    }
+                };
+                return Err(start_error);
+            }
+        };
 
         // Start processing normalised logs for executor requests and follow ups
         let workspace_root = self.workspace_to_current_dir(workspace);
@@ -1127,19 +1198,51 @@ pub trait ContainerService {
             && let Some((executor_profile_id, working_dir)) = match executor_action.typ() {
                 ExecutorActionType::CodingAgentInitialRequest(request) => Some((
                     &request.executor_profile_id,
-                    request.effective_dir(&workspace_root),
+                    request
+                        .effective_dir(&workspace_root)
+                        .unwrap_or_else(|_| workspace_root.clone()),
                 )),
                 ExecutorActionType::CodingAgentFollowUpRequest(request) => Some((
                     &request.executor_profile_id,
-                    request.effective_dir(&workspace_root),
+                    request
+                        .effective_dir(&workspace_root)
+                        .unwrap_or_else(|_| workspace_root.clone()),
                 )),
                 ExecutorActionType::ReviewRequest(request) => Some((
                     &request.executor_profile_id,
-                    request.effective_dir(&workspace_root),
+                    request
+                        .effective_dir(&workspace_root)
+                        .unwrap_or_else(|_| workspace_root.clone()),
                 )),
                 _ => None,
             }
         {
+            #[cfg(not(feature = "qa-mode"))]
+            let session_meta = ExecutorConfigs::get_cached()
+                .get_coding_agent(executor_profile_id)
+                .map(|agent| {
+                    let mut metadata =
+                        agent.session_metadata(&session.id.to_string(), &workspace_root);
+                    metadata.model = executor_action.resolved_model().or(metadata.model);
+                    AgentEvent::SessionMeta { metadata }
+                });
+            #[cfg(feature = "qa-mode")]
+            let session_meta: Option<AgentEvent> = None;
+            let run_tags = match executor_action.typ() {
+                ExecutorActionType::CodingAgentInitialRequest(request) => request.run_tags(),
+                _ => None,
+            };
+            mask_secrets(&msg_store);
+            detect_entry_events(&msg_store, &working_dir);
+            detect_binary_output(msg_store.clone());
+            for event in session_meta.into_iter().chain(run_tags).chain(model_switch) {
+                let idx = EntryIndexProvider::start_from(&msg_store).next();
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    idx,
+                    event.into_normalized_entry(),
+                ));
+            }
+
             #[cfg(feature = "qa-mode")]
             {
                 let executor = QaMockExecutor;
@@ -1150,7 +1253,7 @@ pub trait ContainerService {
                 if let Some(executor) =
                     ExecutorConfigs::get_cached().get_coding_agent(executor_profile_id)
                 {
-                    executor.normalize_logs(msg_store, &working_dir);
+                    executor.normalize_logs_in(msg_store, &working_dir, &env);
                 } else {
                     tracing::error!(
                         "Failed to resolve profile '{:?}' for normalization",
@@ -1160,7 +1263,7 @@ pub trait ContainerService {
             }
         }
 
-        self.spawn_stream_raw_logs_to_db(&execution_process.id);
+        self.spawn_stream_raw_logs_to_db(&execution_process.id, executor_action, &env);
         Ok(execution_process)
     }
 
@@ -1199,3 +1302,60 @@ pub trait ContainerService {
         Ok(())
     }
 }
+
+/// `ModelSwitched` event for a follow-up whose resolved model differs from the model the
+/// session's previous coding agent turn resolved to.
+fn model_switch(previous: &ExecutorAction, current: &ExecutorAction) -> Option<AgentEvent> {
+    AgentEvent::model_switched(
+        previous.resolved_model().as_deref(),
+        current.resolved_model().as_deref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use executors::{
+        actions::coding_agent_follow_up::CodingAgentFollowUpRequest, executors::BaseCodingAgent,
+    };
+
+    use super::*;
+
+    fn follow_up(executor: BaseCodingAgent, variant: &str) -> ExecutorAction {
+        ExecutorAction::new(
+            ExecutorActionType::CodingAgentFollowUpRequest(CodingAgentFollowUpRequest {
+                prompt: "Keep going".to_string(),
+                session_id: "session-1".to_string(),
+                executor_profile_id: ExecutorProfileId::with_variant(executor, variant.to_string()),
+                working_dir: None,
+                session_turns: 1,
+                previous_prompt: None,
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_model_switch_compares_previous_resolved_model() {
+        let flash = follow_up(BaseCodingAgent::Gemini, "FLASH");
+        let pro = follow_up(BaseCodingAgent::Gemini, "PRO");
+
+        match model_switch(&flash, &pro) {
+            Some(AgentEvent::ModelSwitched { from, to }) => {
+                assert_eq!(Some(from), flash.resolved_model());
+                assert_eq!(Some(to), pro.resolved_model());
+            }
+            other => panic!("expected ModelSwitched, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_model_switch_ignores_same_model() {
+        // Different variants that resolve to the same model are not a switch
+        let default = follow_up(BaseCodingAgent::Codex, "DEFAULT");
+        let high = follow_up(BaseCodingAgent::Codex, "HIGH");
+        assert_eq!(default.resolved_model(), high.resolved_model());
+
+        assert!(model_switch(&default, &high).is_none());
+        assert!(model_switch(&default, &default).is_none());
+    }
+}
@@ -1,6 +1,9 @@
 use std::{
     collections::VecDeque,
-    sync::{Arc, RwLock},
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock, atomic::AtomicUsize},
 };
 
 use axum::response::sse::Event;
@@ -12,6 +15,8 @@ use crate::{log_msg::LogMsg, stream_lines::LinesStreamExt};
 
 // 100 MB Limit
 const HISTORY_BYTES: usize = 100000 * 1024;
+/// In-memory tail kept when the full log is spilled to disk
+pub const SPILL_TAIL_BYTES: usize = 1024 * 1024;
 
 #[derive(Clone)]
 struct StoredMsg {
@@ -24,9 +29,25 @@ struct Inner {
     total_bytes: usize,
 }
 
+/// Append-only JSON-lines copy of every message pushed to a store.
+struct Spill {
+    path: PathBuf,
+    writer: Mutex<BufWriter<File>>,
+}
+
+/// Rewrites every JSON patch before it is stored, e.g. to mask secrets; may follow it with more.
+pub type PatchFilter = Arc<dyn Fn(json_patch::Patch) -> Vec<json_patch::Patch> + Send + Sync>;
+
 pub struct MsgStore {
     inner: RwLock<Inner>,
     sender: broadcast::Sender<LogMsg>,
+    history_limit: usize,
+    spill: Option<Spill>,
+    /// Secondary store receiving a copy of every message except raw stdout/stderr
+    mirror: RwLock<Option<Arc<MsgStore>>>,
+    patch_filter: RwLock<Option<PatchFilter>>,
+    /// Next free normalized-entry index, shared by everything adding entries to this store
+    entry_index: Arc<AtomicUsize>,
 }
 
 impl Default for MsgStore {
@@ -37,6 +58,26 @@ impl Default for MsgStore {
 
 impl MsgStore {
     pub fn new() -> Self {
+        Self::with_history_limit(HISTORY_BYTES, None)
+    }
+
+    /// Write every message to `path` as JSON lines and keep only a `tail_bytes` tail in memory.
+    /// Use [`read_spill`] to replay the full log.
+    pub fn with_spill(path: &Path, tail_bytes: usize) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = File::create(path)?;
+        Ok(Self::with_history_limit(
+            tail_bytes,
+            Some(Spill {
+                path: path.to_path_buf(),
+                writer: Mutex::new(BufWriter::new(file)),
+            }),
+        ))
+    }
+
+    fn with_history_limit(history_limit: usize, spill: Option<Spill>) -> Self {
         let (sender, _) = broadcast::channel(10000);
         Self {
             inner: RwLock::new(Inner {
@@ -44,21 +85,100 @@ impl MsgStore {
                 total_bytes: 0,
             }),
             sender,
+            history_limit,
+            spill,
+            mirror: RwLock::new(None),
+            patch_filter: RwLock::new(None),
+            entry_index: Arc::new(AtomicUsize::new(0)),
         }
     }
 
+    /// File receiving the full log, when this store spills to disk.
+    pub fn spill_path(&self) -> Option<&Path> {
+        self.spill.as_ref().map(|spill| spill.path.as_path())
+    }
+
+    /// Also append every message pushed from now on, other than raw stdout/stderr, to `secondary`,
+    /// e.g. an ephemeral view of the normalized log next to the persisted one. Messages reach
+    /// `secondary` in the same order they enter this store's history.
+    pub fn mirror_to(&self, secondary: Arc<MsgStore>) {
+        *self.mirror.write().unwrap() = Some(secondary);
+    }
+
+    /// Pass every JSON patch pushed from now on through `filter` and store what it returns.
+    /// Filters run in the order they were added, each on every patch the previous one returned.
+    pub fn add_patch_filter(&self, filter: PatchFilter) {
+        let mut slot = self.patch_filter.write().unwrap();
+        let filter: PatchFilter = match slot.take() {
+            Some(previous) => Arc::new(move |patch| {
+                previous(patch)
+                    .into_iter()
+                    .flat_map(|patch| filter(patch))
+                    .collect()
+            }),
+            None => filter,
+        };
+        *slot = Some(filter);
+    }
+
+    /// Counter handing out normalized-entry indices for this store, so that every writer (the
+    /// normalizer, a patch filter, events added after exit) draws from the same sequence.
+    pub fn entry_index_counter(&self) -> Arc<AtomicUsize> {
+        self.entry_index.clone()
+    }
+
     pub fn push(&self, msg: LogMsg) {
+        match msg {
+            LogMsg::JsonPatch(patch) => {
+                self.push_patch_filtered(patch);
+            }
+            msg => self.store(msg),
+        }
+    }
+
+    /// Push `patch` and return the patches actually stored once the patch filter has run, e.g.
+    /// to persist exactly what listeners saw.
+    pub fn push_patch_filtered(&self, patch: json_patch::Patch) -> Vec<json_patch::Patch> {
+        let filter = self.patch_filter.read().unwrap().clone();
+        let patches = match filter {
+            Some(filter) => filter(patch),
+            None => vec![patch],
+        };
+        for patch in &patches {
+            self.store(LogMsg::JsonPatch(patch.clone()));
+        }
+        patches
+    }
+
+    fn store(&self, msg: LogMsg) {
         let _ = self.sender.send(msg.clone()); // live listeners
         let bytes = msg.approx_bytes();
 
+        if let Some(spill) = &self.spill {
+            let mut writer = spill.writer.lock().unwrap();
+            let written = serde_json::to_writer(&mut *writer, &msg)
+                .map_err(std::io::Error::from)
+                .and_then(|_| writer.write_all(b"\n"))
+                .and_then(|_| writer.flush());
+            if let Err(e) = written {
+                tracing::warn!("Failed to spill log to {}: {e}", spill.path.display());
+            }
+        }
+
         let mut inner = self.inner.write().unwrap();
-        while inner.total_bytes.saturating_add(bytes) > HISTORY_BYTES {
+        while inner.total_bytes.saturating_add(bytes) > self.history_limit {
             if let Some(front) = inner.history.pop_front() {
                 inner.total_bytes = inner.total_bytes.saturating_sub(front.bytes);
             } else {
                 break;
             }
         }
+        // Forwarded under the history lock so concurrent pushes keep one order in both stores
+        if let Some(mirror) = self.mirror.read().unwrap().as_ref()
+            && !matches!(msg, LogMsg::Stdout(_) | LogMsg::Stderr(_))
+        {
+            mirror.push(msg.clone());
+        }
         inner.history.push_back(StoredMsg { msg, bytes });
         inner.total_bytes = inner.total_bytes.saturating_add(bytes);
     }
@@ -175,3 +295,154 @@ impl MsgStore {
         })
     }
 }
+
+/// Replay a log written by [`MsgStore::with_spill`], one message at a time.
+pub fn read_spill(path: &Path) -> std::io::Result<impl Iterator<Item = std::io::Result<LogMsg>>> {
+    let reader = BufReader::new(File::open(path)?);
+    Ok(reader
+        .lines()
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_spill_path() -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("msg-store-spill-{}", uuid::Uuid::new_v4()))
+            .join("logs.jsonl")
+    }
+
+    #[test]
+    fn test_spill_writes_every_message_to_file() {
+        let path = temp_spill_path();
+        let store = MsgStore::with_spill(&path, SPILL_TAIL_BYTES).unwrap();
+        assert_eq!(store.spill_path(), Some(path.as_path()));
+
+        store.push_stdout("hello\n");
+        store.push_stderr("oops\n");
+        store.push_session_id("session-1".to_string());
+        store.push_finished();
+
+        let replayed: Vec<LogMsg> = read_spill(&path)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert!(matches!(
+            replayed.as_slice(),
+            [
+                LogMsg::Stdout(out),
+                LogMsg::Stderr(err),
+                LogMsg::SessionId(id),
+                LogMsg::Finished,
+            ] if out == "hello\n" && err == "oops\n" && id == "session-1"
+        ));
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_spill_keeps_bounded_tail_in_memory() {
+        let path = temp_spill_path();
+        let tail_bytes = 1024;
+        let store = MsgStore::with_spill(&path, tail_bytes).unwrap();
+
+        for i in 0..500 {
+            store.push_stdout(format!("line {i:04} {}\n", "x".repeat(32)));
+        }
+
+        let history = store.get_history();
+        let history_bytes: usize = history.iter().map(LogMsg::approx_bytes).sum();
+        assert!(history_bytes <= tail_bytes, "{history_bytes}");
+        assert!(history.len() < 500);
+        assert!(matches!(history.last(), Some(LogMsg::Stdout(s)) if s.starts_with("line 0499")));
+
+        assert_eq!(read_spill(&path).unwrap().count(), 500);
+
+        let _ = std::fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_mirror_receives_same_sequence() {
+        let primary = Arc::new(MsgStore::new());
+        let secondary = Arc::new(MsgStore::new());
+        primary.push_stdout("before the mirror\n");
+        primary.mirror_to(secondary.clone());
+
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let primary = &primary;
+                scope.spawn(move || {
+                    for i in 0..100 {
+                        primary.push_session_id(format!("{thread}-{i}"));
+                        primary.push_stdout("raw\n");
+                    }
+                });
+            }
+        });
+        primary.push_finished();
+
+        let normalized = |store: &MsgStore| -> Vec<String> {
+            store
+                .get_history()
+                .iter()
+                .filter(|msg| !matches!(msg, LogMsg::Stdout(_) | LogMsg::Stderr(_)))
+                .map(|msg| serde_json::to_string(msg).unwrap())
+                .collect()
+        };
+        let mirrored = normalized(&secondary);
+        assert_eq!(mirrored.len(), 401);
+        assert_eq!(mirrored, normalized(&primary));
+        // Raw output stays in the primary store only
+        assert_eq!(secondary.get_history().len(), 401);
+    }
+
+    #[test]
+    fn test_patch_filter_rewrites_patches_only() {
+        let store = MsgStore::new();
+        store.add_patch_filter(Arc::new(|patch| vec![patch.clone(), patch]));
+
+        store.push_stdout("raw\n");
+        store.push_patch(json_patch::Patch(Vec::new()));
+
+        let history = store.get_history();
+        assert_eq!(history.len(), 3);
+        assert!(matches!(
+            history.as_slice(),
+            [
+                LogMsg::Stdout(_),
+                LogMsg::JsonPatch(_),
+                LogMsg::JsonPatch(_)
+            ]
+        ));
+    }
+
+    #[test]
+    fn test_patch_filters_run_in_order() {
+        let store = MsgStore::new();
+        let add = |path: &'static str| -> PatchFilter {
+            Arc::new(move |patch: json_patch::Patch| {
+                let extra: json_patch::Patch =
+                    serde_json::from_value(serde_json::json!([{ "op": "remove", "path": path }]))
+                        .unwrap();
+                vec![patch, extra]
+            })
+        };
+        store.add_patch_filter(add("/first"));
+        store.add_patch_filter(add("/second"));
+
+        let stored = store.push_patch_filtered(json_patch::Patch(Vec::new()));
+
+        // The second filter sees both patches the first returned
+        let paths: Vec<String> = stored
+            .iter()
+            .map(|patch| {
+                let value = serde_json::to_value(patch).unwrap();
+                value[0]["path"].as_str().unwrap_or_default().to_string()
+            })
+            .collect();
+        assert_eq!(paths, vec!["", "/second", "/first", "/second"]);
+    }
+}
@@ -22,11 +22,15 @@ pub fn get_shell_command() -> (String, &'static str) {
     }
 }
 
+/// Extensions Windows tries for a bare command name when `PATHEXT` is unset.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD";
+
 /// Resolve an executable by name, falling back to a refreshed PATH if needed.
 ///
 /// The search order is:
 /// 1. Explicit paths (absolute or containing a separator).
-/// 2. The current process PATH via `which`.
+/// 2. The current process PATH via `which`; on Windows a bare name also matches wrappers with
+///    a `PATHEXT` extension, e.g. `jbai-codex` finds `jbai-codex.cmd`.
 /// 3. A platform-specific refresh of PATH (login shell on Unix, PowerShell on Windows),
 ///    after which we re-run the `which` lookup and update the process PATH for future calls.
 pub async fn resolve_executable_path(executable: &str) -> Option<PathBuf> {
@@ -93,11 +97,48 @@ async fn refresh_path() -> bool {
 }
 
 async fn which(executable: &str) -> Option<PathBuf> {
-    let executable = executable.to_string();
-    tokio::task::spawn_blocking(move || which::which(executable))
+    let name = executable.to_string();
+    let found = tokio::task::spawn_blocking(move || which::which(name))
         .await
         .ok()
-        .and_then(|result| result.ok())
+        .and_then(|result| result.ok());
+    if found.is_some() || !cfg!(windows) || Path::new(executable).extension().is_some() {
+        return found;
+    }
+
+    // `which` reads PATHEXT once per process and finds no wrappers when it was unset then, so
+    // try each extension explicitly
+    for extension in pathext() {
+        let name = format!("{executable}{extension}");
+        if let Ok(Ok(found)) = tokio::task::spawn_blocking(move || which::which(name)).await {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Executable extensions from `PATHEXT`, lowercased, e.g. `.cmd`.
+fn pathext() -> Vec<String> {
+    let raw = std::env::var("PATHEXT")
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PATHEXT.to_string());
+    raw.split(';')
+        .map(|extension| extension.trim().to_ascii_lowercase())
+        .filter(|extension| extension.starts_with('.'))
+        .collect()
+}
+
+/// `name` without an executable extension on Windows, e.g. `jbai-codex.cmd` becomes
+/// `jbai-codex`. Unchanged on other platforms, where extensions are part of the name.
+pub fn strip_executable_extension(name: &str) -> &str {
+    if !cfg!(windows) {
+        return name;
+    }
+    match name.rfind('.') {
+        Some(dot) if pathext().contains(&name[dot..].to_ascii_lowercase()) => &name[..dot],
+        _ => name,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
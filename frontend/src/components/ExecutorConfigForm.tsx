@@ -54,6 +54,13 @@ export function ExecutorConfigForm({
     enabled: executor === BaseCodingAgent.JBAI,
   });
 
+  const { data: jbaiClients } = useQuery({
+    queryKey: ['jbai-clients'],
+    queryFn: configApi.getJbaiClients,
+    staleTime: Infinity,
+    enabled: executor === BaseCodingAgent.JBAI,
+  });
+
   // Custom handler for env field updates
   const handleEnvChange = useCallback(
     (envData: Record<string, string> | undefined) => {
@@ -95,6 +102,17 @@ export function ExecutorConfigForm({
     };
 
     if (executor === BaseCodingAgent.JBAI) {
+      if (jbaiClients) {
+        // Label the built-in client names; the second option is the custom command
+        base.client = {
+          oneOf: [
+            {
+              'ui:enumNames': jbaiClients.map((info) => info.name),
+            },
+            {},
+          ],
+        };
+      }
       base.model = {
         'ui:widget': 'select',
         'ui:options': {
@@ -107,7 +125,7 @@ export function ExecutorConfigForm({
     }
 
     return base;
-  }, [executor, jbaiModelOptions]);
+  }, [executor, jbaiClients, jbaiModelOptions]);
 
   // Pass the env update handler via formContext
   const formContext = useMemo(
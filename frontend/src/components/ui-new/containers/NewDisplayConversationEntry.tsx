@@ -313,6 +313,14 @@ function NewDisplayConversationEntry(props: Props) {
       // The new design doesn't need the next action bar
       return null;
 
+    case 'agent_event':
+      return (
+        <SystemMessageEntry
+          content={entry.content}
+          expansionKey={expansionKey}
+        />
+      );
+
     case 'user_feedback':
     case 'loading':
       // Fallback to legacy component for these entry types
@@ -31,6 +31,8 @@ export function useAgentAvailability(
           case 'LOGIN_DETECTED':
             setAvailability({ status: 'login_detected' });
             break;
+          // Stale credentials: installed, but a fresh login is likely needed
+          case 'EXPIRED':
           case 'INSTALLATION_FOUND':
             setAvailability({ status: 'installation_found' });
             break;
@@ -47,6 +47,7 @@ import {
   RenameBranchResponse,
   CheckEditorAvailabilityResponse,
   JbaiModelsResponse,
+  JbaiClientInfo,
   AvailabilityInfo,
   BaseCodingAgent,
   RunAgentSetupRequest,
@@ -964,6 +965,10 @@ export const configApi = {
     const response = await makeRequest('/api/jbai/models');
     return handleApiResponse<JbaiModelsResponse>(response);
   },
+  getJbaiClients: async (): Promise<JbaiClientInfo[]> => {
+    const response = await makeRequest('/api/jbai/clients');
+    return handleApiResponse<JbaiClientInfo[]>(response);
+  },
 };
 
 // Task Tags APIs (all tags are global)
@@ -1,7 +1,8 @@
-import { JbaiClient } from 'shared/types';
+import type { JbaiClient } from 'shared/types';
 
-const JBAI_MODELS: Record<JbaiClient, string[]> = {
-  [JbaiClient.CLAUDE]: [
+// Custom clients run their own wrapper, so no model list is known for them
+const JBAI_MODELS: Record<string, string[]> = {
+  CLAUDE: [
     'claude-opus-4-5-20251101',
     'claude-sonnet-4-5-20250929',
     'claude-haiku-4-5-20251001',
@@ -11,7 +12,7 @@ const JBAI_MODELS: Record<JbaiClient, string[]> = {
     'claude-3-7-sonnet-20250219',
     'claude-3-5-haiku-20241022',
   ],
-  [JbaiClient.CODEX]: [
+  CODEX: [
     'gpt-5.2-2025-12-11',
     'gpt-5.2',
     'gpt-5.1-2025-11-13',
@@ -31,7 +32,7 @@ const JBAI_MODELS: Record<JbaiClient, string[]> = {
     'o3-mini-2025-01-31',
     'o1-2024-12-17',
   ],
-  [JbaiClient.GEMINI]: [
+  GEMINI: [
     'gemini-3-pro-preview',
     'gemini-3-flash-preview',
     'gemini-2.5-pro',
@@ -40,7 +41,7 @@ const JBAI_MODELS: Record<JbaiClient, string[]> = {
     'gemini-2.0-flash-001',
     'gemini-2.0-flash-lite-001',
   ],
-  [JbaiClient.OPENCODE]: [
+  OPENCODE: [
     'gpt-5.2-2025-12-11',
     'gpt-5.2',
     'gpt-5.1-2025-11-13',
@@ -65,9 +66,9 @@ const JBAI_MODELS: Record<JbaiClient, string[]> = {
 export function getJbaiModelOptions(
   client?: JbaiClient | string | null
 ): string[] {
-  if (!client) {
+  if (!client || typeof client !== 'string') {
     return [];
   }
 
-  return JBAI_MODELS[client as JbaiClient] ?? [];
+  return JBAI_MODELS[client] ?? [];
 }
@@ -441,7 +441,19 @@ export enum BaseCodingAgent { CLAUDE_CODE = "CLAUDE_CODE", AMP = "AMP", GEMINI =
 
 export type CodingAgent = { "CLAUDE_CODE": ClaudeCode } | { "AMP": Amp } | { "GEMINI": Gemini } | { "CODEX": Codex } | { "OPENCODE": Opencode } | { "CURSOR_AGENT": CursorAgent } | { "QWEN_CODE": QwenCode } | { "COPILOT": Copilot } | { "DROID": Droid } | { "JBAI": Jbai };
 
-export type AvailabilityInfo = { "type": "LOGIN_DETECTED", last_auth_timestamp: bigint, } | { "type": "INSTALLATION_FOUND" } | { "type": "NOT_FOUND" };
+export type AvailabilityInfo = { "type": "LOGIN_DETECTED", last_auth_timestamp: bigint, 
+/**
+ * Unix time the detected credentials expire, when the token encodes it
+ */
+expires_at?: bigint | null, } | { "type": "EXPIRED", last_auth_timestamp: bigint, } | { "type": "INSTALLATION_FOUND" } | { "type": "NOT_FOUND" };
+
+export type PreflightReport = { checks: Array<PreflightCheck>, };
+
+export type PreflightCheck = { kind: PreflightCheckKind, status: PreflightStatus, };
+
+export type PreflightCheckKind = "binary" | "availability" | "model" | "working_dir";
+
+export type PreflightStatus = { "status": "ok" } | { "status": "failed", reason: string, };
 
 export type CommandBuilder = { 
 /**
@@ -467,15 +479,15 @@ export type ExecutorConfig = { [key in string]?: { "CLAUDE_CODE": ClaudeCode } |
 
 export type ExecutorConfigs = { executors: { [key in BaseCodingAgent]?: ExecutorConfig }, };
 
-export enum BaseAgentCapability { SESSION_FORK = "SESSION_FORK", SETUP_HELPER = "SETUP_HELPER" }
+export enum BaseAgentCapability { SESSION_FORK = "SESSION_FORK", SETUP_HELPER = "SETUP_HELPER", STDIN_STREAMING = "STDIN_STREAMING", PROPOSE_BEFORE_APPLY = "PROPOSE_BEFORE_APPLY" }
 
-export type ClaudeCode = { append_prompt: AppendPrompt, claude_code_router?: boolean | null, plan?: boolean | null, approvals?: boolean | null, model?: string | null, dangerously_skip_permissions?: boolean | null, disable_api_key?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type ClaudeCode = { append_prompt: AppendPrompt, claude_code_router?: boolean | null, plan?: boolean | null, approvals?: boolean | null, model?: string | null, dangerously_skip_permissions?: boolean | null, disable_api_key?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type Gemini = { append_prompt: AppendPrompt, model?: string | null, yolo?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Gemini = { append_prompt: AppendPrompt, model?: string | null, yolo?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type Amp = { append_prompt: AppendPrompt, dangerously_allow_all?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Amp = { append_prompt: AppendPrompt, dangerously_allow_all?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type Codex = { append_prompt: AppendPrompt, sandbox?: SandboxMode | null, ask_for_approval?: AskForApproval | null, oss?: boolean | null, model?: string | null, model_reasoning_effort?: ReasoningEffort | null, model_reasoning_summary?: ReasoningSummary | null, model_reasoning_summary_format?: ReasoningSummaryFormat | null, profile?: string | null, base_instructions?: string | null, include_apply_patch_tool?: boolean | null, model_provider?: string | null, compact_prompt?: string | null, developer_instructions?: string | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Codex = { append_prompt: AppendPrompt, sandbox?: SandboxMode | null, ask_for_approval?: AskForApproval | null, oss?: boolean | null, model?: string | null, model_reasoning_effort?: ReasoningEffort | null, model_reasoning_summary?: ReasoningSummary | null, model_reasoning_summary_format?: ReasoningSummaryFormat | null, profile?: string | null, base_instructions?: string | null, include_apply_patch_tool?: boolean | null, model_provider?: string | null, compact_prompt?: string | null, developer_instructions?: string | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
 export type SandboxMode = "auto" | "read-only" | "workspace-write" | "danger-full-access";
 
@@ -487,27 +499,29 @@ export type ReasoningSummary = "auto" | "concise" | "detailed" | "none";
 
 export type ReasoningSummaryFormat = "none" | "experimental";
 
-export type CursorAgent = { append_prompt: AppendPrompt, force?: boolean | null, model?: string | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type CursorAgent = { append_prompt: AppendPrompt, force?: boolean | null, model?: string | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type Copilot = { append_prompt: AppendPrompt, model?: string | null, allow_all_tools?: boolean | null, allow_tool?: string | null, deny_tool?: string | null, add_dir?: Array<string> | null, disable_mcp_server?: Array<string> | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Copilot = { append_prompt: AppendPrompt, model?: string | null, allow_all_tools?: boolean | null, allow_tool?: string | null, deny_tool?: string | null, add_dir?: Array<string> | null, disable_mcp_server?: Array<string> | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
 export type Opencode = { append_prompt: AppendPrompt, model?: string | null, mode?: string | null, 
 /**
  * Auto-approve agent actions
  */
-auto_approve: boolean, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+auto_approve: boolean, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type QwenCode = { append_prompt: AppendPrompt, yolo?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type QwenCode = { append_prompt: AppendPrompt, yolo?: boolean | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export type Droid = { append_prompt: AppendPrompt, autonomy: Autonomy, model?: string | null, reasoning_effort?: DroidReasoningEffort | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Droid = { append_prompt: AppendPrompt, autonomy: Autonomy, model?: string | null, reasoning_effort?: DroidReasoningEffort | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
 export type Autonomy = "normal" | "low" | "medium" | "high" | "skip-permissions-unsafe";
 
 export type DroidReasoningEffort = "none" | "dynamic" | "off" | "low" | "medium" | "high";
 
-export type Jbai = { append_prompt: AppendPrompt, client: JbaiClient, model?: string | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, };
+export type Jbai = { append_prompt: AppendPrompt, client: JbaiClient, model?: string | null, strict_model: boolean, manage_token_file: boolean, mcp_allowlist?: Array<string> | null, base_command_override?: string | null, additional_params?: Array<string> | null, env?: { [key in string]?: string } | null, normalize_newlines: boolean, isolated_home?: string | null, min_free_bytes?: bigint | null, spill_to_disk?: string | null, record_dir?: string | null, replay_dir?: string | null, coalesce_deltas: boolean, fallback_model?: string | null, checkpoint_interval_secs?: bigint | null, max_session_turns?: number | null, dedup_identical_prompts: boolean, max_wallclock_secs?: bigint | null, block_prompt_secrets: boolean, };
 
-export enum JbaiClient { CLAUDE = "CLAUDE", CODEX = "CODEX", GEMINI = "GEMINI", OPENCODE = "OPENCODE" }
+export type JbaiClient = "CLAUDE" | "CODEX" | "GEMINI" | "OPENCODE" | { "CUSTOM": string };
+
+export type JbaiClientInfo = { client: JbaiClient, name: string, base_command: string, capabilities: Array<BaseAgentCapability>, default_model: string | null, };
 
 export type AppendPrompt = string | null;
 
@@ -524,7 +538,63 @@ model_override?: string | null,
  * Optional relative path to execute the agent in (relative to container_ref).
  * If None, uses the container_ref directory directly.
  */
-working_dir: string | null, };
+working_dir: string | null, 
+/**
+ * Optional cap on agent turns for this attempt.
+ */
+max_turns?: number | null, 
+/**
+ * Optional worktree files (or line ranges) rendered into the prompt as labeled excerpts.
+ */
+context_manifest?: Array<FileRange> | null, 
+/**
+ * Only produce a plan, using the client's plan/dry-run mode.
+ */
+plan_only: boolean, 
+/**
+ * Optional sampling temperature, within `0.0..=2.0`, for clients that support one.
+ */
+temperature?: number | null, 
+/**
+ * Optional command (program and arguments) run in the effective directory once the agent
+ * exits, e.g. `["cargo", "build"]`. A failing command fails the attempt.
+ */
+post_command?: Array<string> | null, 
+/**
+ * Optional caller-supplied tags (ticket id, user id, ...) passed through unredacted to the
+ * log stream and spawn metrics.
+ */
+tags?: { [key in string]?: string } | null, 
+/**
+ * Optional MCP servers, by name, left out of the agent's MCP config for this attempt only.
+ */
+disable_mcp_servers?: Array<string> | null, 
+/**
+ * Optional commit of the agent's changes in the effective directory once it exits.
+ */
+auto_commit?: AutoCommit | null, 
+/**
+ * What to do when the effective directory has uncommitted changes before the agent starts.
+ */
+on_dirty_worktree: OnDirty, };
+
+export type AutoCommit = { 
+/**
+ * Commit message; `{executor}`, `{exit_code}` and `{prompt}` (its first line) are filled in.
+ */
+message_template: string, 
+/**
+ * Only commit when the agent (and any post-command) exited 0.
+ */
+only_on_success: boolean, };
+
+export type OnDirty = "allow" | "warn" | "reject";
+
+export type FileRange = { 
+/**
+ * Path relative to the agent's working directory.
+ */
+path: string, start?: number | null, end?: number | null, };
 
 export type CodingAgentFollowUpRequest = { prompt: string, session_id: string, 
 /**
@@ -535,7 +605,17 @@ executor_profile_id: ExecutorProfileId,
  * Optional relative path to execute the agent in (relative to container_ref).
  * If None, uses the container_ref directory directly.
  */
-working_dir: string | null, };
+working_dir: string | null, 
+/**
+ * Follow-ups already run in this session, not counting the initial request, counted
+ * against the profile's `max_session_turns`
+ */
+session_turns: number, 
+/**
+ * Prompt of the session's previous turn, compared against when the profile sets
+ * `dedup_identical_prompts`
+ */
+previous_prompt: string | null, };
 
 export type ReviewRequest = { executor_profile_id: ExecutorProfileId, context: Array<RepoReviewContext> | null, prompt: string, 
 /**
@@ -555,7 +635,48 @@ export type CommandRunResult = { exit_status: CommandExitStatus | null, output:
 
 export type NormalizedEntry = { timestamp: string | null, entry_type: NormalizedEntryType, content: string, };
 
-export type NormalizedEntryType = { "type": "user_message" } | { "type": "user_feedback", denied_tool: string, } | { "type": "assistant_message" } | { "type": "tool_use", tool_name: string, action_type: ActionType, status: ToolStatus, } | { "type": "system_message" } | { "type": "error_message", error_type: NormalizedEntryError, } | { "type": "thinking" } | { "type": "loading" } | { "type": "next_action", failed: boolean, execution_processes: number, needs_setup: boolean, };
+export type NormalizedEntryType = { "type": "user_message" } | { "type": "user_feedback", denied_tool: string, } | { "type": "assistant_message" } | { "type": "tool_use", tool_name: string, action_type: ActionType, status: ToolStatus, } | { "type": "system_message" } | { "type": "error_message", error_type: NormalizedEntryError, } | { "type": "thinking" } | { "type": "loading" } | { "type": "next_action", failed: boolean, execution_processes: number, needs_setup: boolean, } | { "type": "agent_event", event: AgentEvent, };
+
+export type AgentEvent = { "event": "cwd_changed", path: string, } | { "event": "model_switched", from: string, to: string, } | { "event": "phase_changed", phase: AgentPhase, } | { "event": "deprecation", message: string, } | { "event": "proposed_command", command: string, tool_call_id?: string, } | { "event": "question_pending", question: string, } | { "event": "mcp_server_failed", name: string, error: string, } | { "event": "diff", path: string, hunks: Array<DiffHunk>, } | { "event": "credit_balance", remaining: number, unit: string, } | { "event": "test_results", passed?: number, failed?: number, skipped?: number, } | { "event": "lint_results", tool: string, warnings?: number, errors?: number, } | { "event": "repeated_action", action: string, count: number, } | { "event": "post_command", command: string, output: string, exit_code: number | null, } | { "event": "agent_finished", exit_code: number | null, post_command_exit_code?: number, } | { "event": "model_fallback", from: string, to: string, } | { "event": "model_not_entitled", model: string, } | { "event": "unknown_model", model: string, } | { "event": "file_read", path: string, } | { "event": "session_meta", metadata: SessionMetadata, } | { "event": "run_tags", tags: { [key in string]?: string }, } | { "event": "suggested_actions", items: Array<string>, } | { "event": "proposed_edit", path: string, diff: string, tool_call_id: string, } | { "event": "binary_output", bytes: bigint, } | { "event": "git_op", operation: string, commit: string, message: string, } | { "event": "setup_instruction", command: string | null, text: string, } | { "event": "uncertainty_flag", excerpt: string, entry_index: number, } | { "event": "merge_conflict", files: Array<string>, } | { "event": "scratchpad_update", path: string, } | { "event": "usage", usage: TokenUsage, cost_usd: number | null, } | { "event": "out_of_bounds_access", path: string, } | { "event": "secret_redacted", kinds: Array<string>, } | { "event": "worktree_dirty", paths: Array<string>, } | { "event": "run_summary", edits: number, tool_calls: number, usage: TokenUsage, cost_usd: number | null, status: RunStatus, 
+/**
+ * Gaps between streamed deltas; only recorded when delta coalescing is off
+ */
+latency?: LatencyHistogram, };
+
+export type AgentPhase = "planning" | "executing";
+
+/**
+ * Tokens used; `input_tokens` includes prompt-cache reads and writes
+ */
+export type TokenUsage = { input_tokens: bigint, output_tokens: bigint, };
+
+/**
+ * How a run ended, as reported in [`AgentEvent::RunSummary`]
+ */
+export type RunStatus = "succeeded" | "failed" | "stopped";
+
+export type LatencyBucket = { 
+/**
+ * Inclusive upper bound in ms; unset for the last bucket, which holds every slower gap
+ */
+max_ms: bigint | null, count: number, };
+
+/**
+ * Distribution of the gaps between streamed deltas over a run
+ */
+export type LatencyHistogram = { 
+/**
+ * Empty until the first gap is recorded
+ */
+buckets: Array<LatencyBucket>, samples: number, max_ms: bigint, };
+
+export type SessionMetadata = { session_id: string, agent: BaseCodingAgent, model: string | null, 
+/**
+ * Command the client CLI is launched with
+ */
+client: string | null, created_at: string, worktree: string, };
+
+export type DiffHunk = { header: string, old_start: number, old_lines: number, new_start: number, new_lines: number, lines: Array<string>, };
 
 export type FileChange = { "action": "write", content: string, } | { "action": "delete" } | { "action": "rename", new_path: string, } | { "action": "edit", 
 /**